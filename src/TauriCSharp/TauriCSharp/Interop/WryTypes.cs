@@ -51,6 +51,23 @@ internal struct WryWindowParams
     [MarshalAs(UnmanagedType.U1)]
     public bool AutoplayEnabled;
 
+    // Initialization scripts (array of UTF-8 string pointers, null entries skipped)
+    public IntPtr InitScripts;
+    public nuint InitScriptCount;
+
+    // Proxy for all webview traffic
+    public WryProxyConfig Proxy;
+
+    // Page behavior
+    [MarshalAs(UnmanagedType.U1)]
+    public bool DefaultContextMenuEnabled;
+    [MarshalAs(UnmanagedType.U1)]
+    public bool AllowTextSelection;
+    [MarshalAs(UnmanagedType.U1)]
+    public bool DisableZoom;
+    [MarshalAs(UnmanagedType.U1)]
+    public bool TakeFocusOnClick;
+
     /// <summary>
     /// Creates default parameters matching Rust's Default implementation.
     /// </summary>
@@ -81,10 +98,42 @@ internal struct WryWindowParams
             AlwaysOnTop = false,
             DevtoolsEnabled = true,
             AutoplayEnabled = false,
+            InitScripts = IntPtr.Zero,
+            InitScriptCount = 0,
+            Proxy = default,
+            DefaultContextMenuEnabled = true,
+            AllowTextSelection = true,
+            DisableZoom = false,
+            TakeFocusOnClick = true,
         };
     }
 }
 
+/// <summary>
+/// Proxy server kind matching Rust WryProxyKind.
+/// </summary>
+internal enum WryProxyKind
+{
+    None = 0,
+    Http = 1,
+    Https = 2,
+    Socks5 = 3,
+}
+
+/// <summary>
+/// Proxy configuration matching Rust WryProxyConfig.
+/// A None kind or a null/empty host disables the proxy.
+/// </summary>
+[StructLayout(LayoutKind.Sequential)]
+internal struct WryProxyConfig
+{
+    public WryProxyKind Kind;
+    public IntPtr Host;
+    public ushort Port;
+    public IntPtr Username;
+    public IntPtr Password;
+}
+
 /// <summary>
 /// Window size matching Rust WrySize.
 /// </summary>
@@ -0,0 +1,334 @@
+//! Screen reader support for the host app
+//!
+//! Web content is exposed to assistive technology by the browser engine.
+//! These functions cover messages that come from the host instead, such as
+//! "File saved".
+
+use std::ffi::c_char;
+
+use crate::error::set_last_error;
+use crate::string::c_str_to_string;
+use crate::types::{WryAnnouncementPoliteness, WryWindow};
+use crate::window::get_window_state;
+
+// ============================================================================
+// Platform Announcements
+// ============================================================================
+
+#[cfg(target_os = "macos")]
+fn announce(message: &str, politeness: WryAnnouncementPoliteness) -> Result<(), String> {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {
+        static NSAccessibilityAnnouncementRequestedNotification: *const AnyObject;
+        static NSAccessibilityAnnouncementKey: *const AnyObject;
+        static NSAccessibilityPriorityKey: *const AnyObject;
+        fn NSAccessibilityPostNotificationWithUserInfo(
+            element: *mut AnyObject,
+            notification: *const AnyObject,
+            user_info: *mut AnyObject,
+        );
+    }
+
+    // NSAccessibilityPriorityMedium and NSAccessibilityPriorityHigh
+    let priority: isize = match politeness {
+        WryAnnouncementPoliteness::Polite => 50,
+        WryAnnouncementPoliteness::Assertive => 90,
+    };
+
+    let message = NSString::from_str(message);
+    unsafe {
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let priority: *mut AnyObject = msg_send![class!(NSNumber), numberWithInteger: priority];
+        let keys = [NSAccessibilityAnnouncementKey, NSAccessibilityPriorityKey];
+        let values = [
+            &*message as *const NSString as *const AnyObject,
+            priority as *const AnyObject,
+        ];
+        let user_info: *mut AnyObject = msg_send![
+            class!(NSDictionary),
+            dictionaryWithObjects: values.as_ptr(),
+            forKeys: keys.as_ptr(),
+            count: keys.len()
+        ];
+        NSAccessibilityPostNotificationWithUserInfo(
+            app,
+            NSAccessibilityAnnouncementRequestedNotification,
+            user_info,
+        );
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn announce(message: &str, politeness: WryAnnouncementPoliteness) -> Result<(), String> {
+    use windows::core::BSTR;
+    use windows::Win32::UI::Accessibility::{
+        NotificationKind_Other, NotificationProcessing_All,
+        NotificationProcessing_ImportantMostRecent, UiaHostProviderFromHwnd,
+        UiaRaiseNotificationEvent,
+    };
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetActiveWindow;
+
+    // Notifications are raised on behalf of a window, use the app's active one
+    let hwnd = unsafe { GetActiveWindow() };
+    if hwnd.is_invalid() {
+        return Err("No active window to announce from".to_string());
+    }
+
+    let processing = match politeness {
+        WryAnnouncementPoliteness::Polite => NotificationProcessing_All,
+        WryAnnouncementPoliteness::Assertive => NotificationProcessing_ImportantMostRecent,
+    };
+
+    unsafe {
+        let provider = UiaHostProviderFromHwnd(hwnd)
+            .map_err(|e| format!("Failed to get automation provider: {}", e))?;
+        UiaRaiseNotificationEvent(
+            &provider,
+            NotificationKind_Other,
+            processing,
+            &BSTR::from(message),
+            &BSTR::from("wry-announcement"),
+        )
+        .map_err(|e| format!("Failed to raise notification: {}", e))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn announce(message: &str, politeness: WryAnnouncementPoliteness) -> Result<(), String> {
+    use gtk::glib::subclass::signal::SignalId;
+    use gtk::glib::{Cast, ObjectExt, StaticType, ToValue};
+    use gtk::prelude::{GtkWindowExt, WidgetExt};
+
+    // Announce from the active toplevel, or any toplevel if none has focus
+    let toplevels = gtk::Window::list_toplevels();
+    let window = toplevels
+        .iter()
+        .filter_map(|w| w.clone().downcast::<gtk::Window>().ok())
+        .find(|w| w.is_active())
+        .map(|w| w.upcast::<gtk::Widget>())
+        .or_else(|| toplevels.into_iter().next())
+        .ok_or("No window to announce from")?;
+    let accessible = window
+        .accessible()
+        .ok_or("Window has no accessible object")?;
+
+    // ATK 2.50 "notification" carries politeness, ATK 2.46 "announcement" does not
+    let atk_type = gtk::atk::Object::static_type();
+    if SignalId::lookup("notification", atk_type).is_some() {
+        // ATK_LIVE_POLITE and ATK_LIVE_ASSERTIVE
+        let live: i32 = match politeness {
+            WryAnnouncementPoliteness::Polite => 1,
+            WryAnnouncementPoliteness::Assertive => 2,
+        };
+        accessible.emit_by_name::<()>("notification", &[&message as &dyn ToValue, &live]);
+    } else if SignalId::lookup("announcement", atk_type).is_some() {
+        accessible.emit_by_name::<()>("announcement", &[&message]);
+    } else {
+        return Err("Announcements require ATK 2.46 or newer".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn announce(_message: &str, _politeness: WryAnnouncementPoliteness) -> Result<(), String> {
+    Err("Not supported on this platform".to_string())
+}
+
+// ============================================================================
+// Platform Window Properties
+// ============================================================================
+
+/// Which accessible property of a window to set
+#[derive(Debug, Clone, Copy)]
+enum AccessibleText {
+    /// Name read when the window gains focus
+    Label,
+    /// Longer explanation read on request
+    Description,
+}
+
+#[cfg(target_os = "macos")]
+fn set_accessible_text(
+    window: &tao::window::Window,
+    kind: AccessibleText,
+    text: Option<&str>,
+) -> Result<(), String> {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::NSString;
+    use tao::platform::macos::WindowExtMacOS;
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    let text = text.map(NSString::from_str);
+    let text = text
+        .as_deref()
+        .map_or(std::ptr::null(), |t| t as *const NSString);
+    unsafe {
+        match kind {
+            AccessibleText::Label => {
+                let _: () = msg_send![ns_window, setAccessibilityLabel: text];
+            }
+            AccessibleText::Description => {
+                let _: () = msg_send![ns_window, setAccessibilityHelp: text];
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn set_accessible_text(
+    window: &tao::window::Window,
+    kind: AccessibleText,
+    text: Option<&str>,
+) -> Result<(), String> {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Accessibility::{
+        CLSID_AccPropServices, FullDescription_Property_GUID, IAccPropServices, Name_Property_GUID,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{CHILDID_SELF, OBJID_CLIENT};
+
+    let hwnd = HWND(window.hwnd() as *mut _);
+    // Dynamic annotation overrides the UI Automation property of the HWND
+    let property = match kind {
+        AccessibleText::Label => Name_Property_GUID,
+        AccessibleText::Description => FullDescription_Property_GUID,
+    };
+
+    unsafe {
+        let services: IAccPropServices =
+            CoCreateInstance(&CLSID_AccPropServices, None, CLSCTX_INPROC_SERVER)
+                .map_err(|e| format!("Failed to create AccPropServices: {}", e))?;
+        match text {
+            Some(text) => services.SetHwndPropStr(
+                hwnd,
+                OBJID_CLIENT.0 as u32,
+                CHILDID_SELF,
+                property,
+                &HSTRING::from(text),
+            ),
+            None => services.ClearHwndProps(hwnd, OBJID_CLIENT.0 as u32, CHILDID_SELF, &[property]),
+        }
+        .map_err(|e| format!("Failed to set accessible {:?}: {}", kind, e))
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_accessible_text(
+    window: &tao::window::Window,
+    kind: AccessibleText,
+    text: Option<&str>,
+) -> Result<(), String> {
+    use gtk::atk::prelude::AtkObjectExt;
+    use gtk::prelude::WidgetExt;
+    use tao::platform::unix::WindowExtUnix;
+
+    let accessible = window
+        .gtk_window()
+        .accessible()
+        .ok_or("Window has no accessible object")?;
+    match kind {
+        // ATK has no way back to the default name, fall back to the title
+        AccessibleText::Label => {
+            accessible.set_name(&text.map_or_else(|| window.title(), String::from))
+        }
+        AccessibleText::Description => accessible.set_description(text.unwrap_or("")),
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn set_accessible_text(
+    _window: &tao::window::Window,
+    _kind: AccessibleText,
+    _text: Option<&str>,
+) -> Result<(), String> {
+    Err("Not supported on this platform".to_string())
+}
+
+/// Shared body of the accessible label and description setters
+unsafe fn set_window_accessible_text(
+    window: WryWindow,
+    kind: AccessibleText,
+    text: *const c_char,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let text = c_str_to_string(text);
+    log::debug!("Setting accessible {:?}: {:?}", kind, text);
+    match set_accessible_text(&state.window, kind, text.as_deref()) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// Ask screen readers to speak a message from the host app
+///
+/// Must be called on the UI thread. On Windows the notification is raised
+/// on the app's active window, so nothing is spoken while the app is in the
+/// background.
+#[no_mangle]
+pub unsafe extern "C" fn wry_accessibility_announce(
+    message: *const c_char,
+    politeness: WryAnnouncementPoliteness,
+) -> bool {
+    let message = match c_str_to_string(message) {
+        Some(m) if !m.is_empty() => m,
+        _ => {
+            set_last_error("Null or empty message");
+            return false;
+        }
+    };
+
+    match announce(&message, politeness) {
+        Ok(()) => {
+            log::debug!("Announced ({:?}): {}", politeness, message);
+            true
+        }
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Set the name screen readers use for a window
+///
+/// Defaults to the window title. Pass null to restore the default.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_accessible_label(
+    window: WryWindow,
+    label: *const c_char,
+) -> bool {
+    set_window_accessible_text(window, AccessibleText::Label, label)
+}
+
+/// Set a longer description screen readers offer for a window
+///
+/// Read as the accessibility help text on macOS and the full description on
+/// Windows. Pass null to clear it.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_accessible_description(
+    window: WryWindow,
+    desc: *const c_char,
+) -> bool {
+    set_window_accessible_text(window, AccessibleText::Description, desc)
+}
@@ -3,7 +3,12 @@
 //! Manages the Tao event loop and window registry.
 
 use std::collections::HashMap;
-use std::ffi::c_char;
+use std::ffi::{c_char, CString};
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::thread::ThreadId;
+use std::time::{Duration, Instant};
 
 use tao::event::{Event, StartCause, WindowEvent};
 use tao::event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy};
@@ -11,9 +16,49 @@ use tao::platform::run_return::EventLoopExtRunReturn;
 use tao::window::WindowId;
 
 use crate::error::{error_result, get_last_error_ptr, set_last_error};
-use crate::types::{WryApp, WryErrorCode, WryResult};
+use crate::string::c_str_to_string;
+use crate::types::{
+    AppEventCallback, EventLoopCallback, TypedEventLoopCallback, WryApp, WryErrorCode,
+    WryEventLoopControlFlow, WryFeatureKind, WryLoadingState, WryResult, WryVersionInfo, WryWindow,
+};
 use crate::window::WindowState;
 
+/// Whether the event loop stops once the last window is closed
+///
+/// macOS apps conventionally keep running without windows.
+static TERMINATE_AFTER_LAST_WINDOW_CLOSE: AtomicBool =
+    AtomicBool::new(cfg!(not(target_os = "macos")));
+
+/// Thread that first called `wry_app_create`, treated as the UI thread
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+/// Port passed to new webviews' remote debugging server, 0 when disabled
+static REMOTE_DEBUGGING_PORT: AtomicU16 = AtomicU16::new(0);
+
+/// Set once the process has created its first webview
+static WEBVIEW_CREATED: AtomicBool = AtomicBool::new(false);
+
+/// Wake-up interval in milliseconds for the `Poll` control flow, 0 polls
+/// continuously
+static POLL_INTERVAL_MS: AtomicU32 = AtomicU32::new(0);
+
+/// Record that a webview is being created, after which process-wide engine
+/// settings can no longer change
+pub(crate) fn mark_webview_created() {
+    WEBVIEW_CREATED.store(true, Ordering::Relaxed);
+}
+
+/// Remote debugging port set by `wry_app_enable_remote_debugging`
+///
+/// Only WebView2 takes the port per webview.
+#[cfg(target_os = "windows")]
+pub(crate) fn remote_debugging_port() -> Option<u16> {
+    match REMOTE_DEBUGGING_PORT.load(Ordering::Relaxed) {
+        0 => None,
+        port => Some(port),
+    }
+}
+
 /// User events for cross-thread communication
 pub enum UserEvent {
     /// Request to quit the application
@@ -24,6 +69,27 @@ pub enum UserEvent {
     WebViewMessage { window_id: WindowId, message: String },
     /// Close/destroy a specific window
     DestroyWindow(WindowId),
+    /// The OS switched between light and dark appearance
+    AppearanceChanged(bool),
+    /// An OS appearance or accessibility setting may have changed and should
+    /// be re-queried
+    SystemSettingsChanged,
+    /// A window's own effective appearance switched between light and dark
+    WindowAppearanceChanged { window_id: WindowId, is_dark: bool },
+    /// Host-defined event carrying a JSON payload
+    Custom(String),
+    /// No-op used to probe whether the event loop is still alive
+    Ping,
+    /// A webview started or finished loading a page
+    PageLoad {
+        window_id: WindowId,
+        finished: bool,
+        url: String,
+    },
+    /// Window event tao does not report, as JSON for the window event callback
+    WindowJson { window_id: WindowId, json: String },
+    /// Hand keyboard focus from a window's webview back to the window
+    FocusParent(WindowId),
 }
 
 impl std::fmt::Debug for UserEvent {
@@ -35,6 +101,24 @@ impl std::fmt::Debug for UserEvent {
                 write!(f, "WebViewMessage {{ window_id: {:?}, message: {:?} }}", window_id, message)
             }
             UserEvent::DestroyWindow(id) => write!(f, "DestroyWindow({:?})", id),
+            UserEvent::AppearanceChanged(is_dark) => write!(f, "AppearanceChanged({})", is_dark),
+            UserEvent::SystemSettingsChanged => write!(f, "SystemSettingsChanged"),
+            UserEvent::WindowAppearanceChanged { window_id, is_dark } => write!(
+                f,
+                "WindowAppearanceChanged {{ window_id: {:?}, is_dark: {} }}",
+                window_id, is_dark
+            ),
+            UserEvent::Custom(json) => write!(f, "Custom({:?})", json),
+            UserEvent::Ping => write!(f, "Ping"),
+            UserEvent::PageLoad { window_id, finished, url } => write!(
+                f,
+                "PageLoad {{ window_id: {:?}, finished: {}, url: {:?} }}",
+                window_id, finished, url
+            ),
+            UserEvent::WindowJson { window_id, json } => {
+                write!(f, "WindowJson {{ window_id: {:?}, json: {:?} }}", window_id, json)
+            }
+            UserEvent::FocusParent(id) => write!(f, "FocusParent({:?})", id),
         }
     }
 }
@@ -51,12 +135,24 @@ pub struct AppState {
     pub custom_protocols: HashMap<String, ProtocolHandler>,
     /// Whether we should quit
     pub should_quit: bool,
+    /// Callback receiving app-level events as JSON
+    pub event_callback: Option<(AppEventCallback, *mut c_void)>,
+    /// Last observed system dark mode state
+    pub is_dark_mode: bool,
+    /// Last observed "reduce motion" accessibility option
+    pub is_reduced_motion: bool,
+    /// Last observed high-contrast display mode
+    pub is_high_contrast: bool,
+    /// Control flow applied to each event unless the host asks otherwise
+    pub default_control_flow: WryEventLoopControlFlow,
 }
 
 /// A custom protocol handler
 pub struct ProtocolHandler {
     pub callback: crate::types::CustomProtocolCallback,
     pub user_data: *mut std::os::raw::c_void,
+    /// Response cache shared by every webview using this scheme
+    pub cache: Option<std::sync::Arc<crate::protocol::ProtocolCache>>,
 }
 
 // Safety: user_data is managed by the caller
@@ -68,6 +164,7 @@ impl AppState {
     pub fn new() -> Result<Self, String> {
         let event_loop = EventLoopBuilder::<UserEvent>::with_user_event().build();
         let proxy = event_loop.create_proxy();
+        crate::appearance::watch_system_settings(proxy.clone());
 
         Ok(Self {
             event_loop: Some(event_loop),
@@ -75,9 +172,49 @@ impl AppState {
             windows: HashMap::new(),
             custom_protocols: HashMap::new(),
             should_quit: false,
+            event_callback: None,
+            is_dark_mode: crate::appearance::system_is_dark_mode(),
+            is_reduced_motion: crate::appearance::system_prefers_reduced_motion(),
+            is_high_contrast: crate::appearance::system_is_high_contrast(),
+            default_control_flow: WryEventLoopControlFlow::Wait,
         })
     }
 
+    /// Report a dark mode change since it was last checked
+    pub fn check_appearance(&mut self) {
+        let is_dark = crate::appearance::system_is_dark_mode();
+        if is_dark != self.is_dark_mode {
+            log::debug!("System appearance changed: dark = {}", is_dark);
+            self.is_dark_mode = is_dark;
+            let _ = self.event_loop_proxy.send_event(UserEvent::AppearanceChanged(is_dark));
+        }
+    }
+
+    /// Report accessibility options that changed since they were last checked
+    pub fn check_accessibility_options(&mut self) {
+        let reduced_motion = crate::appearance::system_prefers_reduced_motion();
+        if reduced_motion != self.is_reduced_motion {
+            log::debug!("Reduce motion changed: {}", reduced_motion);
+            self.is_reduced_motion = reduced_motion;
+            let json = serde_json::json!({
+                "type": "accessibility-reduce-motion-changed",
+                "value": reduced_motion,
+            });
+            let _ = self.event_loop_proxy.send_event(UserEvent::Custom(json.to_string()));
+        }
+
+        let high_contrast = crate::appearance::system_is_high_contrast();
+        if high_contrast != self.is_high_contrast {
+            log::debug!("High contrast changed: {}", high_contrast);
+            self.is_high_contrast = high_contrast;
+            let json = serde_json::json!({
+                "type": "high-contrast-changed",
+                "value": high_contrast,
+            });
+            let _ = self.event_loop_proxy.send_event(UserEvent::Custom(json.to_string()));
+        }
+    }
+
     /// Get a window by ID
     pub fn get_window(&self, id: WindowId) -> Option<&WindowState> {
         self.windows.get(&id).map(|b| b.as_ref())
@@ -88,11 +225,26 @@ impl AppState {
         self.windows.get_mut(&id).map(|b| b.as_mut())
     }
 
+    /// Whether the event loop should stop
+    fn should_exit(&self) -> bool {
+        let terminate = TERMINATE_AFTER_LAST_WINDOW_CLOSE.load(Ordering::Relaxed);
+        self.should_quit || (self.windows.is_empty() && terminate)
+    }
+
     /// Request quit
     pub fn quit(&mut self) {
         self.should_quit = true;
         let _ = self.event_loop_proxy.send_event(UserEvent::Quit);
     }
+
+    /// Deliver a serialized event to the app event callback
+    pub fn emit_event(&self, event_json: &str) {
+        if let Some((callback, user_data)) = self.event_callback {
+            if let Ok(c_json) = CString::new(event_json) {
+                callback(c_json.as_ptr(), user_data);
+            }
+        }
+    }
 }
 
 // ============================================================================
@@ -108,6 +260,7 @@ pub extern "C" fn wry_app_create() -> WryApp {
     // Initialize logging
     let _ = env_logger::try_init();
     log::info!("wry_app_create called");
+    let _ = MAIN_THREAD.set(std::thread::current().id());
 
     #[cfg(target_os = "linux")]
     {
@@ -131,7 +284,8 @@ pub extern "C" fn wry_app_create() -> WryApp {
     }
 }
 
-/// Run the event loop. Blocks until all windows closed or wry_app_quit called.
+/// Run the event loop. Blocks until wry_app_quit is called, or until all
+/// windows are closed if `wry_app_set_terminate_after_last_window_close` is on.
 ///
 /// # Safety
 /// Must be called on main thread with a valid app handle.
@@ -142,6 +296,7 @@ pub unsafe extern "C" fn wry_app_run(app: WryApp) -> WryResult {
     }
 
     log::info!("wry_app_run called");
+    warn_if_not_main_thread("wry_app_run");
     let state = &mut *(app as *mut AppState);
 
     let mut event_loop = match state.event_loop.take() {
@@ -168,30 +323,12 @@ pub unsafe extern "C" fn wry_app_run(app: WryApp) -> WryResult {
             gtk::main_iteration_do(false);
         }
 
-        *control_flow = ControlFlow::Wait;
-
-        match event {
-            Event::NewEvents(StartCause::Init) => {
-                log::debug!("Event loop initialized");
-            }
-
-            Event::UserEvent(user_event) => {
-                handle_user_event(state, user_event, control_flow);
-            }
-
-            Event::WindowEvent { window_id, event, .. } => {
-                handle_window_event(state, window_id, event, control_flow);
-            }
+        *control_flow = tao_control_flow(state.default_control_flow, None);
 
-            Event::LoopDestroyed => {
-                log::info!("Event loop destroyed");
-            }
-
-            _ => {}
-        }
+        dispatch_event(state, event, control_flow);
 
         // Check if we should quit
-        if state.should_quit || state.windows.is_empty() {
+        if state.should_exit() {
             *control_flow = ControlFlow::Exit;
         }
     });
@@ -206,6 +343,235 @@ pub unsafe extern "C" fn wry_app_run(app: WryApp) -> WryResult {
     WryResult::ok()
 }
 
+/// Run the event loop for at most `timeout_ms` milliseconds, then return.
+///
+/// Lets a host with its own UI loop pump events cooperatively. Every event
+/// the host can observe is passed to `callback` as a JSON string; returning
+/// `Exit` from the callback stops pumping early. Windows can be created
+/// between pumps.
+///
+/// # Returns
+/// `Exit` once the app should stop (quit requested, all windows closed when
+/// terminating after the last window, or the callback returned `Exit`),
+/// otherwise the last flow the callback asked for.
+///
+/// # Safety
+/// Must be called on main thread with a valid app handle.
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_pump_timeout(
+    app: WryApp,
+    callback: EventLoopCallback,
+    user_data: *mut c_void,
+    timeout_ms: u32,
+) -> WryEventLoopControlFlow {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return WryEventLoopControlFlow::Exit;
+    }
+
+    pump_events(app, timeout_ms, |event| {
+        let json = crate::events::serialize_event(event)?;
+        let c_json = CString::new(json).ok()?;
+        Some(callback(c_json.as_ptr(), user_data))
+    })
+}
+
+/// Like `wry_event_loop_pump_timeout`, passing events as `WryEvent` structs
+///
+/// Window events with a typed payload set the matching `WryEventData`
+/// field; every other event has kind `Other` and carries the same JSON the
+/// string callback would receive.
+///
+/// # Safety
+/// Must be called on main thread with a valid app handle.
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_pump_timeout_typed(
+    app: WryApp,
+    callback: TypedEventLoopCallback,
+    user_data: *mut c_void,
+    timeout_ms: u32,
+) -> WryEventLoopControlFlow {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return WryEventLoopControlFlow::Exit;
+    }
+
+    pump_events(app, timeout_ms, |event| {
+        crate::events::with_typed_event(event, |typed| callback(typed, user_data))
+    })
+}
+
+/// Set the control flow used for every event the host does not override
+///
+/// `Wait` (the default) sleeps until the next event; `Poll` keeps the loop
+/// spinning, paced by `wry_event_loop_set_poll_interval_ms`. Applies to
+/// `wry_app_run` and the pump functions.
+///
+/// # Safety
+/// `app` must be a valid app handle.
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_set_control_flow_default(
+    app: WryApp,
+    flow: WryEventLoopControlFlow,
+) -> bool {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return false;
+    }
+    if flow == WryEventLoopControlFlow::Exit {
+        set_last_error("Exit cannot be the default control flow");
+        return false;
+    }
+
+    let state = &mut *(app as *mut AppState);
+    state.default_control_flow = flow;
+    true
+}
+
+/// Wake the loop every `interval_ms` milliseconds while polling instead of
+/// spinning; 0 (the default) polls continuously
+#[no_mangle]
+pub extern "C" fn wry_event_loop_set_poll_interval_ms(interval_ms: u32) {
+    POLL_INTERVAL_MS.store(interval_ms, Ordering::Relaxed);
+}
+
+/// Translate a host control flow, never waking later than `deadline`
+fn tao_control_flow(flow: WryEventLoopControlFlow, deadline: Option<Instant>) -> ControlFlow {
+    let wake_at = match flow {
+        WryEventLoopControlFlow::Exit => return ControlFlow::Exit,
+        WryEventLoopControlFlow::Wait => deadline,
+        WryEventLoopControlFlow::Poll => match POLL_INTERVAL_MS.load(Ordering::Relaxed) {
+            0 => return ControlFlow::Poll,
+            interval => {
+                let next = Instant::now() + Duration::from_millis(interval as u64);
+                Some(deadline.map_or(next, |deadline| next.min(deadline)))
+            }
+        },
+    };
+    match wake_at {
+        Some(instant) => ControlFlow::WaitUntil(instant),
+        None => ControlFlow::Wait,
+    }
+}
+
+/// Shared loop of the pump functions; `handle` returns the flow the host
+/// asked for, or None if it did not see the event
+unsafe fn pump_events(
+    app: WryApp,
+    timeout_ms: u32,
+    mut handle: impl FnMut(&Event<UserEvent>) -> Option<WryEventLoopControlFlow>,
+) -> WryEventLoopControlFlow {
+    warn_if_not_main_thread("wry_event_loop_pump_timeout");
+    let state = &mut *(app as *mut AppState);
+
+    let mut event_loop = match state.event_loop.take() {
+        Some(el) => el,
+        None => {
+            set_last_error("Event loop already consumed or running");
+            return WryEventLoopControlFlow::Exit;
+        }
+    };
+
+    let state_ptr = app as *mut AppState;
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms as u64);
+    let mut flow = state.default_control_flow;
+    let mut host_requested = false;
+
+    event_loop.run_return(|event, _event_loop, control_flow| {
+        let state = &mut *state_ptr;
+
+        #[cfg(target_os = "linux")]
+        while gtk::events_pending() {
+            gtk::main_iteration_do(false);
+        }
+
+        *control_flow = tao_control_flow(state.default_control_flow, Some(deadline));
+
+        if !is_suppressed(state, &event) {
+            if let Some(requested) = handle(&event) {
+                flow = requested;
+                host_requested = true;
+            }
+        }
+
+        // Poll intervals also resume the loop; only the deadline ends the pump
+        let timed_out = matches!(
+            event,
+            Event::NewEvents(StartCause::ResumeTimeReached { requested_resume, .. })
+                if requested_resume >= deadline
+        );
+
+        dispatch_event(state, event, control_flow);
+
+        // The host's flow outlasts the default, but not an exit from dispatch
+        if host_requested && *control_flow != ControlFlow::Exit {
+            *control_flow = tao_control_flow(flow, Some(deadline));
+        }
+
+        if state.should_exit() {
+            flow = WryEventLoopControlFlow::Exit;
+        }
+
+        if timed_out || flow == WryEventLoopControlFlow::Exit || Instant::now() >= deadline {
+            *control_flow = ControlFlow::Exit;
+        }
+    });
+
+    // Hand the event loop back so the next pump (or window creation) can use it
+    state.event_loop = Some(event_loop);
+
+    flow
+}
+
+/// Whether an event is dropped before reaching the host, such as touches
+/// on a window with touch disabled
+fn is_suppressed(state: &AppState, event: &Event<UserEvent>) -> bool {
+    match event {
+        Event::WindowEvent {
+            window_id,
+            event: WindowEvent::Touch(_),
+            ..
+        } => state.windows.get(window_id).is_some_and(|ws| !ws.touch_enabled),
+        _ => false,
+    }
+}
+
+/// Route a single event loop event to the matching handler
+fn dispatch_event(state: &mut AppState, event: Event<UserEvent>, control_flow: &mut ControlFlow) {
+    if is_suppressed(state, &event) {
+        return;
+    }
+
+    match event {
+        Event::NewEvents(StartCause::Init) => {
+            log::debug!("Event loop initialized");
+        }
+
+        Event::UserEvent(user_event) => {
+            handle_user_event(state, user_event, control_flow);
+        }
+
+        Event::WindowEvent { window_id, event, .. } => {
+            route_window_event(state, window_id, &event);
+            crate::window_ops::redirect_keyboard_input(state, window_id, &event);
+            if let Some(window_state) = state.windows.get(&window_id) {
+                crate::webview::dispatch_hotkeys(window_state, &event);
+            }
+            handle_window_event(state, window_id, event, control_flow);
+        }
+
+        Event::Opened { urls } => {
+            crate::deep_link::handle_opened_urls(urls.iter().map(|u| u.as_str()));
+        }
+
+        Event::LoopDestroyed => {
+            log::debug!("Event loop destroyed");
+        }
+
+        _ => {}
+    }
+}
+
 /// Handle user events (from other threads)
 fn handle_user_event(state: &mut AppState, event: UserEvent, control_flow: &mut ControlFlow) {
     match event {
@@ -236,11 +602,74 @@ fn handle_user_event(state: &mut AppState, event: UserEvent, control_flow: &mut
 
         UserEvent::DestroyWindow(window_id) => {
             log::debug!("Destroy window requested: {:?}", window_id);
-            state.windows.remove(&window_id);
+            if state.windows.remove(&window_id).is_some() {
+                crate::webview::cancel_async_js_calls(window_id, "Window was destroyed");
+            }
+        }
+
+        UserEvent::Ping => {}
+
+        UserEvent::SystemSettingsChanged => {
+            state.check_appearance();
+            state.check_accessibility_options();
+        }
+
+        UserEvent::WindowAppearanceChanged { window_id, is_dark } => {
+            if let Some(window_state) = state.windows.get(&window_id) {
+                window_state.callbacks.call_appearance(is_dark);
+            }
+        }
+
+        UserEvent::WindowJson { window_id, json } => {
+            if let Some(window_state) = state.windows.get(&window_id) {
+                window_state.callbacks.call_event(&json);
+            }
+        }
+
+        UserEvent::FocusParent(window_id) => {
+            if let Some(webview) = state.windows.get(&window_id).and_then(|w| w.webview.as_ref()) {
+                if let Err(e) = webview.focus_parent() {
+                    log::warn!("Failed to return focus to window: {}", e);
+                }
+            }
+        }
+
+        UserEvent::PageLoad { window_id, finished, .. } => {
+            if let Some(json) = crate::events::serialize_user_event(&event) {
+                state.emit_event(&json);
+            }
+            if let Some(window_state) = state.windows.get_mut(&window_id) {
+                if finished {
+                    window_state.loading_state = WryLoadingState::Idle;
+                    window_state.initial_load_finished = true;
+                } else {
+                    window_state.loading_state = WryLoadingState::Loading;
+                    // Scripts still running in the old page can never settle
+                    crate::webview::cancel_async_js_calls(window_id, "Page navigated away");
+                }
+            }
+        }
+
+        event @ (UserEvent::AppearanceChanged(_) | UserEvent::Custom(_)) => {
+            if let Some(json) = crate::events::serialize_user_event(&event) {
+                state.emit_event(&json);
+            }
         }
     }
 }
 
+/// Deliver a window event to that window's own event callback
+fn route_window_event(state: &AppState, window_id: WindowId, event: &WindowEvent) {
+    let window_state = match state.windows.get(&window_id) {
+        Some(ws) if ws.callbacks.has_event() => ws,
+        _ => return,
+    };
+
+    if let Some(json) = crate::events::serialize_window_event(window_id, event) {
+        window_state.callbacks.call_event(&json);
+    }
+}
+
 /// Handle window events
 fn handle_window_event(
     state: &mut AppState,
@@ -259,6 +688,7 @@ fn handle_window_event(
         if should_close {
             log::debug!("Window close requested and approved: {:?}", window_id);
             state.windows.remove(&window_id);
+            crate::webview::cancel_async_js_calls(window_id, "Window was destroyed");
         }
         return;
     }
@@ -291,6 +721,152 @@ fn handle_window_event(
     }
 }
 
+/// Set whether the event loop stops once the last window is closed
+///
+/// Applies to `wry_app_run` and `wry_event_loop_pump_timeout`. Defaults to
+/// true on Windows and Linux and false on macOS, where apps keep running
+/// until `wry_app_quit`.
+#[no_mangle]
+pub extern "C" fn wry_app_set_terminate_after_last_window_close(quit: bool) {
+    TERMINATE_AFTER_LAST_WINDOW_CLOSE.store(quit, Ordering::Relaxed);
+    log::debug!("Terminate after last window close: {}", quit);
+}
+
+/// Check whether the calling thread is the one that created the app
+///
+/// UI functions must be called on that thread. Returns false before
+/// `wry_app_create` has been called.
+#[no_mangle]
+pub extern "C" fn wry_thread_check_is_main() -> bool {
+    MAIN_THREAD.get() == Some(&std::thread::current().id())
+}
+
+/// Log a warning in debug builds when `function` is called off the UI thread
+pub(crate) fn warn_if_not_main_thread(function: &str) {
+    if cfg!(debug_assertions) && MAIN_THREAD.get().is_some() && !wry_thread_check_is_main() {
+        log::warn!("{} called off the main thread", function);
+    }
+}
+
+/// Serve remote debugging for webviews created from now on
+///
+/// Windows opens a Chrome DevTools Protocol endpoint, for external DevTools
+/// or automation tools. Linux opens the WebKit inspector HTTP server, which
+/// serves the inspector UI to a browser rather than CDP. macOS has no
+/// socket option; enable devtools and use Safari's Develop menu instead.
+///
+/// Must be called before any webview is created in the process, since the
+/// engine reads the setting only once; later calls fail. On Linux this
+/// sets the `WEBKIT_INSPECTOR_HTTP_SERVER` environment variable, so no other
+/// thread may be reading or writing the environment at the time. `port`
+/// must be non-zero and free; it is not checked here, and the server fails
+/// to start if it is taken.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_enable_remote_debugging(app: WryApp, port: u16) -> bool {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return false;
+    }
+
+    if cfg!(not(any(target_os = "windows", target_os = "linux"))) {
+        set_last_error("Not supported on this platform");
+        return false;
+    }
+
+    if WEBVIEW_CREATED.load(Ordering::Relaxed) {
+        set_last_error("Remote debugging must be enabled before any webview is created");
+        return false;
+    }
+
+    if port == 0 {
+        set_last_error("Remote debugging port must be non-zero");
+        return false;
+    }
+
+    // WebKitGTK reads this when its first web context starts
+    #[cfg(target_os = "linux")]
+    std::env::set_var("WEBKIT_INSPECTOR_HTTP_SERVER", format!("127.0.0.1:{}", port));
+
+    REMOTE_DEBUGGING_PORT.store(port, Ordering::Relaxed);
+    log::info!("Remote debugging enabled on port {}", port);
+    true
+}
+
+/// Run the event loop until the window's first page has loaded
+///
+/// For hosts that script the page right after creating a window. Returns
+/// immediately if the page already loaded. Events that arrive meanwhile are
+/// dispatched as usual. Cannot be called while `wry_app_run` or
+/// `wry_event_loop_pump_timeout` is running.
+///
+/// # Returns
+/// true once loading finished, false on timeout or if navigation failed
+///
+/// # Safety
+/// Must be called on main thread with valid app and window handles.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_block_until_loaded(
+    app: WryApp,
+    window: WryWindow,
+    timeout_ms: u32,
+) -> bool {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return false;
+    }
+
+    let window_id = match crate::window::get_window_state(window) {
+        Some(ws) if ws.initial_load_finished => return true,
+        Some(ws) => ws.id,
+        None => return false,
+    };
+
+    let state = &mut *(app as *mut AppState);
+    let mut event_loop = match state.event_loop.take() {
+        Some(el) => el,
+        None => {
+            set_last_error("Event loop already consumed or running");
+            return false;
+        }
+    };
+
+    let state_ptr = app as *mut AppState;
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms as u64);
+    let mut loaded = false;
+
+    event_loop.run_return(|event, _event_loop, control_flow| {
+        let state = &mut *state_ptr;
+
+        #[cfg(target_os = "linux")]
+        while gtk::events_pending() {
+            gtk::main_iteration_do(false);
+        }
+
+        *control_flow = ControlFlow::WaitUntil(deadline);
+        dispatch_event(state, event, control_flow);
+
+        let (finished, failed) = match state.get_window(window_id) {
+            Some(ws) => (
+                ws.initial_load_finished,
+                ws.loading_state == WryLoadingState::Failed,
+            ),
+            None => (false, true),
+        };
+        loaded = finished;
+
+        if finished || failed || state.should_exit() || Instant::now() >= deadline {
+            *control_flow = ControlFlow::Exit;
+        }
+    });
+
+    state.event_loop = Some(event_loop);
+
+    if !loaded {
+        set_last_error("Page did not finish loading");
+    }
+    loaded
+}
+
 /// Request app to quit
 ///
 /// # Safety
@@ -317,18 +893,526 @@ pub unsafe extern "C" fn wry_app_destroy(app: WryApp) {
     }
 
     log::info!("wry_app_destroy called");
+    warn_if_not_main_thread("wry_app_destroy");
     let _ = Box::from_raw(app as *mut AppState);
 }
 
+/// Set callback for app-level events
+///
+/// Events are delivered as JSON strings, e.g.
+/// `{"type":"appearance-changed","is_dark":true}`. The string is only valid
+/// for the duration of the callback.
+///
+/// # Safety
+/// Must be called with a valid app handle.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_set_event_callback(
+    app: WryApp,
+    callback: AppEventCallback,
+    user_data: *mut c_void,
+) {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return;
+    }
+
+    let state = &mut *(app as *mut AppState);
+    state.event_callback = Some((callback, user_data));
+    log::debug!("App event callback set");
+}
+
+// ============================================================================
+// Launch at Startup
+// ============================================================================
+
+/// Names end up in file and registry value names, so keep them simple
+fn valid_startup_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(['/', '\\'])
+        && !name.contains(char::is_control)
+        && name != "."
+        && name != ".."
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn home_dir() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME")
+        .filter(|h| !h.is_empty())
+        .map(std::path::PathBuf::from)
+}
+
+#[cfg(target_os = "macos")]
+fn startup_entry_path(app_name: &str) -> Option<std::path::PathBuf> {
+    Some(
+        home_dir()?
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", app_name)),
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn startup_entry_path(app_name: &str) -> Option<std::path::PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|c| !c.is_empty())
+        .map(std::path::PathBuf::from)
+        .or_else(|| home_dir().map(|h| h.join(".config")))?;
+    Some(config.join("autostart").join(format!("{}.desktop", app_name)))
+}
+
+#[cfg(target_os = "macos")]
+fn startup_entry_contents(app_name: &str, app_path: &str) -> String {
+    fn escape(s: &str) -> String {
+        s.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{}</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+</dict>
+</plist>
+"#,
+        escape(app_name),
+        escape(app_path)
+    )
+}
+
+#[cfg(target_os = "linux")]
+fn startup_entry_contents(app_name: &str, app_path: &str) -> String {
+    // Exec quoting rules: wrap in double quotes, escape ", `, $ and \, and
+    // double % so it is not read as a field code
+    let mut exec = String::from("\"");
+    for c in app_path.chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                exec.push('\\');
+                exec.push(c);
+            }
+            '%' => exec.push_str("%%"),
+            _ => exec.push(c),
+        }
+    }
+    exec.push('"');
+    // The string value escape rule applies on top, so backslashes double again
+    let exec = exec.replace('\\', "\\\\");
+
+    format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+        app_name, exec
+    )
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn add_startup_entry(app_name: &str, app_path: &str) -> Result<(), String> {
+    let path = startup_entry_path(app_name).ok_or("HOME is not set")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    std::fs::write(&path, startup_entry_contents(app_name, app_path))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn remove_startup_entry(app_name: &str) -> Result<(), String> {
+    let path = startup_entry_path(app_name).ok_or("HOME is not set")?;
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn has_startup_entry(app_name: &str) -> bool {
+    startup_entry_path(app_name).is_some_and(|p| p.is_file())
+}
+
+#[cfg(target_os = "windows")]
+const STARTUP_RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+#[cfg(target_os = "windows")]
+fn add_startup_entry(app_name: &str, app_path: &str) -> Result<(), String> {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::{RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+
+    let command: Vec<u16> = format!("\"{}\"", app_path)
+        .encode_utf16()
+        .chain(Some(0))
+        .collect();
+
+    let status = unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            &HSTRING::from(STARTUP_RUN_KEY),
+            &HSTRING::from(app_name),
+            REG_SZ.0,
+            Some(command.as_ptr() as *const c_void),
+            (command.len() * 2) as u32,
+        )
+    };
+    status.ok().map_err(|e| format!("Failed to write Run key: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn remove_startup_entry(app_name: &str) -> Result<(), String> {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::ERROR_FILE_NOT_FOUND;
+    use windows::Win32::System::Registry::{RegDeleteKeyValueW, HKEY_CURRENT_USER};
+
+    let status = unsafe {
+        RegDeleteKeyValueW(
+            HKEY_CURRENT_USER,
+            &HSTRING::from(STARTUP_RUN_KEY),
+            &HSTRING::from(app_name),
+        )
+    };
+    if status == ERROR_FILE_NOT_FOUND {
+        return Ok(());
+    }
+    status.ok().map_err(|e| format!("Failed to delete Run key value: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn has_startup_entry(app_name: &str) -> bool {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_SZ};
+
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            &HSTRING::from(STARTUP_RUN_KEY),
+            &HSTRING::from(app_name),
+            RRF_RT_REG_SZ,
+            None,
+            None,
+            None,
+        )
+    };
+    status.is_ok()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn add_startup_entry(_app_name: &str, _app_path: &str) -> Result<(), String> {
+    Err("Launch at startup is not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn remove_startup_entry(_app_name: &str) -> Result<(), String> {
+    Err("Launch at startup is not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn has_startup_entry(_app_name: &str) -> bool {
+    false
+}
+
+/// Register the app to launch when the user logs in
+///
+/// Uses a LaunchAgent plist on macOS, the HKCU `Run` key on Windows and an
+/// XDG autostart `.desktop` file on Linux. `app_name` identifies the entry
+/// and must not contain path separators; neither argument may contain
+/// control characters. An existing entry is replaced.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_launch_at_startup(
+    app_name: *const c_char,
+    app_path: *const c_char,
+) -> bool {
+    let app_name = match c_str_to_string(app_name) {
+        Some(n) if valid_startup_name(&n) => n,
+        _ => {
+            set_last_error("Null or invalid app name");
+            return false;
+        }
+    };
+
+    // Control characters could start new keys in the .desktop file
+    let app_path = match c_str_to_string(app_path) {
+        Some(p) if !p.is_empty() && !p.contains(char::is_control) => p,
+        _ => {
+            set_last_error("Null or invalid app path");
+            return false;
+        }
+    };
+
+    match add_startup_entry(&app_name, &app_path) {
+        Ok(()) => {
+            log::debug!("Registered {} to launch at startup", app_name);
+            true
+        }
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Remove the launch-at-login entry created by `wry_app_launch_at_startup`
+///
+/// Succeeds if no entry exists.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_remove_from_startup(app_name: *const c_char) -> bool {
+    let app_name = match c_str_to_string(app_name) {
+        Some(n) if valid_startup_name(&n) => n,
+        _ => {
+            set_last_error("Null or invalid app name");
+            return false;
+        }
+    };
+
+    match remove_startup_entry(&app_name) {
+        Ok(()) => {
+            log::debug!("Removed {} from startup", app_name);
+            true
+        }
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Check whether a launch-at-login entry exists for the app
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_is_launch_at_startup(app_name: *const c_char) -> bool {
+    match c_str_to_string(app_name) {
+        Some(n) if valid_startup_name(&n) => has_startup_entry(&n),
+        _ => false,
+    }
+}
+
 /// Get last error message (valid until next wry_* call)
 #[no_mangle]
 pub extern "C" fn wry_get_last_error() -> *const c_char {
     get_last_error_ptr()
 }
 
+/// ABI major version, bumped on any breaking change to exported functions or types
+pub const WRY_FFI_ABI_VERSION_MAJOR: u32 = 2;
+/// ABI minor version, bumped when functions or types are added
+pub const WRY_FFI_ABI_VERSION_MINOR: u32 = 48;
+/// ABI patch version, bumped for fixes that do not change the ABI
+pub const WRY_FFI_ABI_VERSION_PATCH: u32 = 0;
+
+/// Packed ABI version: `(major << 16) | (minor << 8) | patch`
+pub const WRY_FFI_ABI_VERSION: u32 = (WRY_FFI_ABI_VERSION_MAJOR << 16)
+    | (WRY_FFI_ABI_VERSION_MINOR << 8)
+    | WRY_FFI_ABI_VERSION_PATCH;
+
+/// Get the packed ABI version, `(major << 16) | (minor << 8) | patch`
+#[no_mangle]
+pub extern "C" fn wry_ffi_abi_version() -> u32 {
+    WRY_FFI_ABI_VERSION
+}
+
+/// Check whether this library can be used by a host built against
+/// `required_version` (packed like `wry_ffi_abi_version`)
+///
+/// Compatible when the major versions match and this library's minor
+/// version is at least the required one.
+#[no_mangle]
+pub extern "C" fn wry_ffi_abi_check_compatible(required_version: u32) -> bool {
+    let major = |version: u32| version >> 16;
+    let minor = |version: u32| (version >> 8) & 0xff;
+    major(required_version) == major(WRY_FFI_ABI_VERSION)
+        && minor(WRY_FFI_ABI_VERSION) >= minor(required_version)
+}
+
+/// Check whether an optional feature is compiled into this library and
+/// available on the running OS
+///
+/// Lets hosts that ship several native builds adapt their UI without
+/// probing each function for errors.
+#[no_mangle]
+pub extern "C" fn wry_ffi_feature_supported(feature: WryFeatureKind) -> bool {
+    let macos = cfg!(target_os = "macos");
+    let windows = cfg!(target_os = "windows");
+    let linux = cfg!(target_os = "linux");
+
+    match feature {
+        // Not provided by this library on any platform
+        WryFeatureKind::Menu
+        | WryFeatureKind::Tray
+        | WryFeatureKind::Notifications
+        | WryFeatureKind::GlobalShortcuts
+        | WryFeatureKind::Clipboard
+        | WryFeatureKind::FileWatcher => false,
+        WryFeatureKind::Transparency | WryFeatureKind::CustomCursor => macos || windows || linux,
+        WryFeatureKind::Proxy => {
+            if macos {
+                cfg!(feature = "mac-proxy")
+                    && macos_supports(c"WKWebsiteDataStore", c"setProxyConfigurations:")
+            } else {
+                windows || linux
+            }
+        }
+        WryFeatureKind::CorsBypass => linux && cfg!(any(debug_assertions, feature = "cors-bypass")),
+        WryFeatureKind::PdfExport => {
+            if macos {
+                macos_supports(c"WKWebView", c"createPDFWithConfiguration:completionHandler:")
+            } else {
+                windows || linux
+            }
+        }
+        WryFeatureKind::Snapshot => macos || windows || linux,
+        WryFeatureKind::SslErrorHandler | WryFeatureKind::MediaPermissions => windows || linux,
+        WryFeatureKind::Taskbar => windows,
+        WryFeatureKind::WindowTabs => macos,
+    }
+}
+
+/// Whether instances of an Objective-C class respond to a selector, for
+/// APIs newer than the minimum supported macOS
+#[cfg(target_os = "macos")]
+fn macos_supports(class: &std::ffi::CStr, selector: &std::ffi::CStr) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::{AnyClass, Sel};
+
+    match AnyClass::get(class) {
+        Some(class) => unsafe {
+            msg_send![class, instancesRespondToSelector: Sel::register(selector)]
+        },
+        None => false,
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn macos_supports(_class: &std::ffi::CStr, _selector: &std::ffi::CStr) -> bool {
+    false
+}
+
 /// Get version string
 #[no_mangle]
 pub extern "C" fn wry_version() -> *const c_char {
     static VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "\0");
     VERSION.as_ptr() as *const c_char
 }
+
+/// Version of the vendored tao crate; tao exposes no version constant
+const TAO_VERSION: &str = "0.34.5";
+
+/// Strings behind the pointers handed out by `wry_version_info`
+struct VersionStrings {
+    ffi_crate_version: CString,
+    webview_engine_version: CString,
+    tao_version: CString,
+    os_version: CString,
+    build_date: CString,
+}
+
+static VERSION_STRINGS: OnceLock<VersionStrings> = OnceLock::new();
+
+fn version_cstring(value: Option<String>) -> CString {
+    value
+        .and_then(|v| CString::new(v).ok())
+        .unwrap_or_else(|| CString::new("unknown").unwrap())
+}
+
+/// Fill `out` with library, engine and OS versions
+///
+/// The strings are computed on the first call and live for the rest of
+/// the process.
+#[no_mangle]
+pub unsafe extern "C" fn wry_version_info(out: *mut WryVersionInfo) -> bool {
+    if out.is_null() {
+        set_last_error("Output pointer is null");
+        return false;
+    }
+
+    let strings = VERSION_STRINGS.get_or_init(|| VersionStrings {
+        ffi_crate_version: version_cstring(Some(env!("CARGO_PKG_VERSION").to_string())),
+        webview_engine_version: version_cstring(wry::webview_version().ok()),
+        tao_version: version_cstring(Some(TAO_VERSION.to_string())),
+        os_version: version_cstring(os_version()),
+        build_date: version_cstring(option_env!("WRY_FFI_BUILD_DATE").map(str::to_string)),
+    });
+
+    *out = WryVersionInfo {
+        ffi_crate_version: strings.ffi_crate_version.as_ptr(),
+        webview_engine_version: strings.webview_engine_version.as_ptr(),
+        tao_version: strings.tao_version.as_ptr(),
+        os_version: strings.os_version.as_ptr(),
+        build_date: strings.build_date.as_ptr(),
+    };
+    true
+}
+
+#[cfg(target_os = "linux")]
+fn os_version() -> Option<String> {
+    let release = std::fs::read_to_string("/proc/sys/kernel/osrelease").ok()?;
+    let pretty = std::fs::read_to_string("/etc/os-release").ok().and_then(|contents| {
+        contents.lines().find_map(|line| {
+            line.strip_prefix("PRETTY_NAME=")
+                .map(|name| name.trim_matches('"').to_string())
+        })
+    });
+    Some(match pretty {
+        Some(name) => format!("{} (Linux {})", name, release.trim()),
+        None => format!("Linux {}", release.trim()),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn os_version() -> Option<String> {
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+
+    let version: Option<Retained<NSString>> = unsafe {
+        let info: *mut AnyObject = msg_send![class!(NSProcessInfo), processInfo];
+        msg_send![info, operatingSystemVersionString]
+    };
+    version.map(|v| format!("macOS {}", v))
+}
+
+#[cfg(target_os = "windows")]
+fn os_version() -> Option<String> {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ};
+
+    let read = |name: &str| -> Option<String> {
+        let key = HSTRING::from(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion");
+        let name = HSTRING::from(name);
+        let mut buffer = [0u16; 128];
+        let mut size = std::mem::size_of_val(&buffer) as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                &key,
+                &name,
+                RRF_RT_REG_SZ,
+                None,
+                Some(buffer.as_mut_ptr() as *mut c_void),
+                Some(&mut size),
+            )
+        };
+        if status.is_err() {
+            return None;
+        }
+        let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    };
+
+    let build = read("CurrentBuildNumber")?;
+    Some(match read("DisplayVersion") {
+        Some(display) => format!("Windows {} (build {})", display, build),
+        None => format!("Windows build {}", build),
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn os_version() -> Option<String> {
+    None
+}
@@ -0,0 +1,554 @@
+//! System appearance queries
+//!
+//! Reads OS-level appearance preferences such as dark mode and the
+//! accessibility display options, and watches the OS for changes to them.
+
+use tao::event_loop::EventLoopProxy;
+use tao::window::WindowId;
+
+use crate::app::UserEvent;
+
+// ============================================================================
+// Platform Queries
+// ============================================================================
+
+/// Query whether the OS is currently using a dark appearance
+#[cfg(target_os = "macos")]
+pub fn system_is_dark_mode() -> bool {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send, sel};
+
+    unsafe {
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        if app.is_null() {
+            return false;
+        }
+
+        // effectiveAppearance is only available on macOS 10.14+
+        let has_appearance: bool = msg_send![app, respondsToSelector: sel!(effectiveAppearance)];
+        if !has_appearance {
+            return false;
+        }
+
+        let appearance: *mut AnyObject = msg_send![app, effectiveAppearance];
+        appearance_is_dark(appearance)
+    }
+}
+
+/// Whether an NSAppearance is closer to dark than light aqua
+#[cfg(target_os = "macos")]
+unsafe fn appearance_is_dark(appearance: *mut objc2::runtime::AnyObject) -> bool {
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2_foundation::{NSArray, NSString};
+
+    if appearance.is_null() {
+        return false;
+    }
+
+    let names = NSArray::from_retained_slice(&[
+        NSString::from_str("NSAppearanceNameAqua"),
+        NSString::from_str("NSAppearanceNameDarkAqua"),
+    ]);
+    let best: Option<Retained<NSString>> =
+        msg_send![appearance, bestMatchFromAppearancesWithNames: &*names];
+    best.is_some_and(|name| name.to_string() == "NSAppearanceNameDarkAqua")
+}
+
+/// Query whether the OS is currently using a dark appearance
+#[cfg(target_os = "windows")]
+pub fn system_is_dark_mode() -> bool {
+    use once_cell::sync::Lazy;
+    use windows::core::{s, PCSTR};
+    use windows::Win32::System::LibraryLoader::{GetProcAddress, LoadLibraryA};
+
+    // Undocumented uxtheme export, looked up by ordinal
+    const SHOULD_APPS_USE_DARK_MODE_ORDINAL: u16 = 132;
+    type ShouldAppsUseDarkMode = unsafe extern "system" fn() -> bool;
+
+    static SHOULD_APPS_USE_DARK_MODE: Lazy<Option<ShouldAppsUseDarkMode>> = Lazy::new(|| unsafe {
+        let module = LoadLibraryA(s!("uxtheme.dll")).ok()?;
+        GetProcAddress(
+            module,
+            PCSTR::from_raw(SHOULD_APPS_USE_DARK_MODE_ORDINAL as usize as *const u8),
+        )
+        .map(|f| std::mem::transmute::<_, ShouldAppsUseDarkMode>(f))
+    });
+
+    SHOULD_APPS_USE_DARK_MODE
+        .map(|f| unsafe { f() })
+        .unwrap_or(false)
+}
+
+/// Query whether the OS is currently using a dark appearance
+#[cfg(target_os = "linux")]
+pub fn system_is_dark_mode() -> bool {
+    use gtk::prelude::*;
+
+    let settings = match gtk::Settings::default() {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if settings.is_gtk_application_prefer_dark_theme() {
+        return true;
+    }
+
+    // Themes such as "Adwaita-dark" only advertise darkness through their name
+    settings
+        .gtk_theme_name()
+        .map(|name| name.to_lowercase().contains("dark"))
+        .unwrap_or(false)
+}
+
+/// Query whether the OS is currently using a dark appearance
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn system_is_dark_mode() -> bool {
+    false
+}
+
+/// Query whether the user asked the OS to reduce motion
+#[cfg(target_os = "macos")]
+pub fn system_prefers_reduced_motion() -> bool {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+
+    unsafe {
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        !workspace.is_null() && msg_send![workspace, accessibilityDisplayShouldReduceMotion]
+    }
+}
+
+/// Query whether the user asked the OS to reduce motion
+#[cfg(target_os = "windows")]
+pub fn system_prefers_reduced_motion() -> bool {
+    use windows::core::BOOL;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    // "Show animations in Windows" in the accessibility settings
+    let mut animations = BOOL(1);
+    let queried = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut animations as *mut BOOL as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    queried.is_ok() && !animations.as_bool()
+}
+
+/// Query whether the user asked the OS to reduce motion
+#[cfg(target_os = "linux")]
+pub fn system_prefers_reduced_motion() -> bool {
+    use gtk::prelude::*;
+
+    gtk::Settings::default().is_some_and(|settings| !settings.is_gtk_enable_animations())
+}
+
+/// Query whether the user asked the OS to reduce motion
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn system_prefers_reduced_motion() -> bool {
+    false
+}
+
+/// Query whether a high-contrast display mode is active
+#[cfg(target_os = "macos")]
+pub fn system_is_high_contrast() -> bool {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+
+    unsafe {
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        !workspace.is_null() && msg_send![workspace, accessibilityDisplayShouldIncreaseContrast]
+    }
+}
+
+/// Query whether a high-contrast display mode is active
+#[cfg(target_os = "windows")]
+pub fn system_is_high_contrast() -> bool {
+    use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    let mut contrast = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+    let queried = unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            contrast.cbSize,
+            Some(&mut contrast as *mut HIGHCONTRASTW as *mut _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    };
+    queried.is_ok() && contrast.dwFlags.contains(HCF_HIGHCONTRASTON)
+}
+
+/// Query whether a high-contrast display mode is active
+#[cfg(target_os = "linux")]
+pub fn system_is_high_contrast() -> bool {
+    use gtk::prelude::*;
+
+    // GNOME switches to the "HighContrast" or "HighContrastInverse" theme
+    gtk::Settings::default()
+        .and_then(|settings| settings.gtk_theme_name())
+        .is_some_and(|name| name.to_lowercase().starts_with("highcontrast"))
+}
+
+/// Query whether a high-contrast display mode is active
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn system_is_high_contrast() -> bool {
+    false
+}
+
+// ============================================================================
+// Change Notifications
+// ============================================================================
+
+#[cfg(target_os = "macos")]
+pub(crate) mod observer {
+    use std::ffi::c_void;
+
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, NSObject};
+    use objc2::{define_class, msg_send, AnyThread, DefinedClass};
+
+    define_class!(
+        /// Runs a closure whenever an observed key path changes
+        #[unsafe(super(NSObject))]
+        #[ivars = Box<dyn Fn()>]
+        pub struct KeyValueObserver;
+
+        impl KeyValueObserver {
+            #[unsafe(method(observeValueForKeyPath:ofObject:change:context:))]
+            fn observe_value(
+                &self,
+                _key_path: *mut AnyObject,
+                _object: *mut AnyObject,
+                _change: *mut AnyObject,
+                _context: *mut c_void,
+            ) {
+                (self.ivars())();
+            }
+        }
+    );
+
+    impl KeyValueObserver {
+        pub fn new(on_change: Box<dyn Fn()>) -> Retained<Self> {
+            let this = Self::alloc().set_ivars(on_change);
+            unsafe { msg_send![super(this), init] }
+        }
+    }
+}
+
+/// Watch the OS appearance and accessibility settings for the rest of the
+/// process
+///
+/// Each change wakes the event loop with `UserEvent::SystemSettingsChanged`,
+/// which re-queries the settings rather than trusting the notification.
+/// Called once on the UI thread when the app is created.
+#[cfg(target_os = "macos")]
+pub(crate) fn watch_system_settings(proxy: EventLoopProxy<UserEvent>) {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+
+    let appearance_proxy = proxy.clone();
+    let observer = observer::KeyValueObserver::new(Box::new(move || {
+        let _ = appearance_proxy.send_event(UserEvent::SystemSettingsChanged);
+    }));
+    unsafe {
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let key = NSString::from_str("effectiveAppearance");
+        let _: () = msg_send![
+            app,
+            addObserver: &*observer,
+            forKeyPath: &*key,
+            options: 0usize,
+            context: std::ptr::null_mut::<std::ffi::c_void>()
+        ];
+    }
+    // NSApp does not retain its observers and is never deallocated
+    std::mem::forget(observer);
+
+    watch_accessibility_display_options(proxy);
+}
+
+/// Forward changes to reduce motion, increase contrast and the other
+/// accessibility display options, which are not key-value observable
+#[cfg(target_os = "macos")]
+fn watch_accessibility_display_options(proxy: EventLoopProxy<UserEvent>) {
+    use block2::RcBlock;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+
+    #[link(name = "AppKit", kind = "framework")]
+    extern "C" {
+        static NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification: *const AnyObject;
+    }
+
+    let handler = RcBlock::new(move |_notification: *mut AnyObject| {
+        let _ = proxy.send_event(UserEvent::SystemSettingsChanged);
+    });
+    unsafe {
+        let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+        if workspace.is_null() {
+            return;
+        }
+        let center: *mut AnyObject = msg_send![workspace, notificationCenter];
+        // The returned token's observer stays registered for the process
+        let _: *mut AnyObject = msg_send![
+            center,
+            addObserverForName: NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification,
+            object: std::ptr::null_mut::<AnyObject>(),
+            queue: std::ptr::null_mut::<AnyObject>(),
+            usingBlock: &*handler
+        ];
+    }
+}
+
+/// Watch the OS appearance and accessibility settings for the rest of the
+/// process
+///
+/// Each change wakes the event loop with `UserEvent::SystemSettingsChanged`,
+/// which re-queries the settings rather than trusting the notification.
+/// Called once on the UI thread when the app is created.
+#[cfg(target_os = "linux")]
+pub(crate) fn watch_system_settings(proxy: EventLoopProxy<UserEvent>) {
+    use gtk::prelude::*;
+
+    let settings = match gtk::Settings::default() {
+        Some(s) => s,
+        None => return,
+    };
+
+    let properties = [
+        "gtk-application-prefer-dark-theme",
+        "gtk-theme-name",
+        "gtk-enable-animations",
+    ];
+    for property in properties {
+        let proxy = proxy.clone();
+        settings.connect_notify_local(Some(property), move |_, _| {
+            let _ = proxy.send_event(UserEvent::SystemSettingsChanged);
+        });
+    }
+}
+
+/// Watch the OS appearance and accessibility settings for the rest of the
+/// process
+///
+/// Windows broadcasts setting changes to top-level windows instead, see
+/// `watch_window_settings`.
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub(crate) fn watch_system_settings(_proxy: EventLoopProxy<UserEvent>) {}
+
+/// Key-value observer reporting one window's effective appearance, removed
+/// on drop
+#[cfg(target_os = "macos")]
+pub struct WindowAppearanceObserver {
+    ns_window: objc2::rc::Retained<objc2::runtime::AnyObject>,
+    observer: objc2::rc::Retained<observer::KeyValueObserver>,
+}
+
+/// Key-value observer reporting one window's effective appearance, removed
+/// on drop
+#[cfg(not(target_os = "macos"))]
+pub struct WindowAppearanceObserver;
+
+#[cfg(target_os = "macos")]
+impl Drop for WindowAppearanceObserver {
+    fn drop(&mut self) {
+        use objc2::msg_send;
+        use objc2_foundation::NSString;
+
+        let key = NSString::from_str("effectiveAppearance");
+        unsafe {
+            let _: () = msg_send![
+                &*self.ns_window,
+                removeObserver: &*self.observer,
+                forKeyPath: &*key
+            ];
+        }
+    }
+}
+
+/// Watch a window's own effective appearance, which follows the OS theme
+/// unless the window or an ancestor overrides it
+///
+/// Each change is sent as `UserEvent::WindowAppearanceChanged`.
+#[cfg(target_os = "macos")]
+pub(crate) fn watch_window_appearance(
+    window: &tao::window::Window,
+    window_id: WindowId,
+    proxy: EventLoopProxy<UserEvent>,
+) -> Option<WindowAppearanceObserver> {
+    use std::cell::Cell;
+
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::NSString;
+    use tao::platform::macos::WindowExtMacOS;
+
+    let ns_window: Retained<AnyObject> =
+        unsafe { Retained::retain(window.ns_window() as *mut AnyObject) }?;
+    let is_dark = |ns_window: &AnyObject| unsafe {
+        let appearance: *mut AnyObject = msg_send![ns_window, effectiveAppearance];
+        appearance_is_dark(appearance)
+    };
+
+    // KVO can fire without the value changing, so only report real changes
+    let target = ns_window.clone();
+    let last = Cell::new(is_dark(&ns_window));
+    let observer = observer::KeyValueObserver::new(Box::new(move || {
+        let dark = is_dark(&target);
+        if last.replace(dark) != dark {
+            let _ = proxy.send_event(UserEvent::WindowAppearanceChanged {
+                window_id,
+                is_dark: dark,
+            });
+        }
+    }));
+
+    let key = NSString::from_str("effectiveAppearance");
+    unsafe {
+        let _: () = msg_send![
+            &*ns_window,
+            addObserver: &*observer,
+            forKeyPath: &*key,
+            options: 0usize,
+            context: std::ptr::null_mut::<std::ffi::c_void>()
+        ];
+    }
+    Some(WindowAppearanceObserver {
+        ns_window,
+        observer,
+    })
+}
+
+/// Watch a window's own effective appearance
+///
+/// Windows only follow the OS theme on other platforms.
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn watch_window_appearance(
+    _window: &tao::window::Window,
+    _window_id: WindowId,
+    _proxy: EventLoopProxy<UserEvent>,
+) -> Option<WindowAppearanceObserver> {
+    None
+}
+
+/// Identifies the settings subclass among any others on the window
+#[cfg(target_os = "windows")]
+const SETTINGS_SUBCLASS_ID: usize = 0x7365_7474;
+
+/// Forward OS setting changes broadcast to `window` to the event loop
+///
+/// `WM_SETTINGCHANGE` only reaches top-level windows, so every window is
+/// subclassed when it is created.
+#[cfg(target_os = "windows")]
+pub(crate) fn watch_window_settings(
+    window: &tao::window::Window,
+    proxy: EventLoopProxy<UserEvent>,
+) {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Shell::SetWindowSubclass;
+
+    let hwnd = HWND(window.hwnd() as *mut _);
+    let proxy = Box::into_raw(Box::new(proxy));
+    unsafe {
+        let installed = SetWindowSubclass(
+            hwnd,
+            Some(settings_subclass_proc),
+            SETTINGS_SUBCLASS_ID,
+            proxy as usize,
+        );
+        if !installed.as_bool() {
+            log::warn!("Failed to watch system settings for window");
+            drop(Box::from_raw(proxy));
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn settings_subclass_proc(
+    hwnd: windows::Win32::Foundation::HWND,
+    msg: u32,
+    wparam: windows::Win32::Foundation::WPARAM,
+    lparam: windows::Win32::Foundation::LPARAM,
+    _subclass_id: usize,
+    ref_data: usize,
+) -> windows::Win32::Foundation::LRESULT {
+    use windows::core::PCWSTR;
+    use windows::Win32::UI::Shell::{DefSubclassProc, RemoveWindowSubclass};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SPI_SETCLIENTAREAANIMATION, SPI_SETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_ACTION,
+        WM_NCDESTROY, WM_SETTINGCHANGE,
+    };
+
+    let proxy = ref_data as *mut EventLoopProxy<UserEvent>;
+
+    if msg == WM_SETTINGCHANGE {
+        // The light/dark switch is announced as the "ImmersiveColorSet" area
+        let area = match lparam.0 {
+            0 => None,
+            ptr => PCWSTR(ptr as *const u16).to_string().ok(),
+        };
+        // Accessibility options are announced by the SPI_SET* action that
+        // changed them
+        let action = SYSTEM_PARAMETERS_INFO_ACTION(wparam.0 as u32);
+        let accessibility = [SPI_SETCLIENTAREAANIMATION, SPI_SETHIGHCONTRAST];
+        if area.as_deref() == Some("ImmersiveColorSet") || accessibility.contains(&action) {
+            let _ = (*proxy).send_event(UserEvent::SystemSettingsChanged);
+        }
+    }
+
+    if msg == WM_NCDESTROY {
+        let result = DefSubclassProc(hwnd, msg, wparam, lparam);
+        let _ = RemoveWindowSubclass(hwnd, Some(settings_subclass_proc), SETTINGS_SUBCLASS_ID);
+        drop(Box::from_raw(proxy));
+        return result;
+    }
+
+    DefSubclassProc(hwnd, msg, wparam, lparam)
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// Check whether the OS is currently in dark mode
+///
+/// Should be called from the main thread.
+#[no_mangle]
+pub extern "C" fn wry_app_is_dark_mode() -> bool {
+    system_is_dark_mode()
+}
+
+/// Check whether the OS "reduce motion" accessibility option is enabled
+///
+/// Changes are reported to the app event callback as
+/// `{"type":"accessibility-reduce-motion-changed","value":true}` as soon as
+/// the OS announces them. Should be called from the main thread.
+#[no_mangle]
+pub extern "C" fn wry_app_is_reduced_motion() -> bool {
+    system_prefers_reduced_motion()
+}
+
+/// Check whether the OS is in a high-contrast display mode
+///
+/// "Increase contrast" on macOS, a contrast theme on Windows and the
+/// HighContrast GTK theme on Linux. Changes are reported to the app event
+/// callback as `{"type":"high-contrast-changed","value":true}` as soon as the
+/// OS announces them. Should be called from the main thread.
+#[no_mangle]
+pub extern "C" fn wry_app_is_high_contrast() -> bool {
+    system_is_high_contrast()
+}
@@ -12,8 +12,9 @@ use once_cell::sync::Lazy;
 use tao::window::WindowId;
 
 use crate::types::{
-    NavigationCallback, WebMessageCallback, WindowClosingCallback, WindowFocusCallback,
-    WindowMovedCallback, WindowResizedCallback, WryWindow,
+    AppearanceCallback, EventLoopCallback, NavigationCallback, SslErrorHandler, WebMessageCallback,
+    WebSocketHandler, WindowClosingCallback, WindowFocusCallback, WindowMovedCallback,
+    WindowResizedCallback, WryWebSocketEvent, WryWindow,
 };
 
 /// Global registry mapping WindowId to callback info for IPC routing
@@ -24,19 +25,26 @@ static CALLBACK_REGISTRY: Lazy<RwLock<HashMap<WindowId, CallbackEntry>>> =
 struct CallbackEntry {
     window_ptr: WryWindow,
     message_callback: Option<(WebMessageCallback, *mut c_void)>,
+    ssl_error_handler: (SslErrorHandler, *mut c_void),
+    websocket_handler: (WebSocketHandler, *mut c_void),
 }
 
 // Safety: callback pointers and user_data are managed by caller
 unsafe impl Send for CallbackEntry {}
 unsafe impl Sync for CallbackEntry {}
 
-/// Register a window in the callback registry
+/// Register a window in the callback registry, keeping any callbacks already set
 pub fn register_window(window_id: WindowId, window_ptr: WryWindow) {
     let mut registry = CALLBACK_REGISTRY.write().unwrap();
-    registry.insert(window_id, CallbackEntry {
-        window_ptr,
-        message_callback: None,
-    });
+    registry
+        .entry(window_id)
+        .or_insert(CallbackEntry {
+            window_ptr,
+            message_callback: None,
+            ssl_error_handler: (None, std::ptr::null_mut()),
+            websocket_handler: (None, std::ptr::null_mut()),
+        })
+        .window_ptr = window_ptr;
 }
 
 /// Unregister a window from the callback registry
@@ -45,6 +53,15 @@ pub fn unregister_window(window_id: WindowId) {
     registry.remove(&window_id);
 }
 
+/// Find a registered window by the id string used in event JSON
+pub fn find_window(window_id: &str) -> Option<WryWindow> {
+    let registry = CALLBACK_REGISTRY.read().unwrap();
+    registry
+        .iter()
+        .find(|(id, _)| crate::events::window_id_string(**id) == window_id)
+        .map(|(_, entry)| entry.window_ptr)
+}
+
 /// Set the message callback in the global registry
 pub fn set_global_message_callback(
     window_id: WindowId,
@@ -70,6 +87,62 @@ pub fn invoke_message_callback(window_id: WindowId, message: &str) {
     }
 }
 
+/// Set the TLS error handler in the global registry
+pub fn set_global_ssl_error_handler(
+    window_id: WindowId,
+    handler: SslErrorHandler,
+    user_data: *mut c_void,
+) {
+    let mut registry = CALLBACK_REGISTRY.write().unwrap();
+    if let Some(entry) = registry.get_mut(&window_id) {
+        entry.ssl_error_handler = (handler, user_data);
+    }
+}
+
+/// Ask the TLS error handler whether to proceed, false without a handler
+/// This is called from the platform TLS error hooks
+pub fn invoke_ssl_error_handler(window_id: WindowId, url: &str, error_code: i32) -> bool {
+    let registry = CALLBACK_REGISTRY.read().unwrap();
+    if let Some(entry) = registry.get(&window_id) {
+        if let (Some(handler), user_data) = entry.ssl_error_handler {
+            if let Ok(c_url) = CString::new(url) {
+                return unsafe { handler(c_url.as_ptr(), error_code, user_data) };
+            }
+        }
+    }
+    false
+}
+
+/// Set the WebSocket interception handler in the global registry
+pub fn set_global_websocket_handler(
+    window_id: WindowId,
+    handler: WebSocketHandler,
+    user_data: *mut c_void,
+) {
+    let mut registry = CALLBACK_REGISTRY.write().unwrap();
+    if let Some(entry) = registry.get_mut(&window_id) {
+        entry.websocket_handler = (handler, user_data);
+    }
+}
+
+/// Pass intercepted WebSocket activity to the handler, if one is set
+/// This is called from the webview's IPC handler closure
+pub fn invoke_websocket_handler(
+    window_id: WindowId,
+    connection_id: u32,
+    event: WryWebSocketEvent,
+    data: Option<&str>,
+) {
+    let registry = CALLBACK_REGISTRY.read().unwrap();
+    if let Some(entry) = registry.get(&window_id) {
+        if let (Some(handler), user_data) = entry.websocket_handler {
+            let data = data.and_then(|d| CString::new(d).ok());
+            let data_ptr = data.as_ref().map_or(std::ptr::null(), |d| d.as_ptr());
+            unsafe { handler(connection_id, event, data_ptr, user_data) };
+        }
+    }
+}
+
 /// Stored callback with its user data
 struct StoredCallback<F> {
     callback: F,
@@ -87,7 +160,9 @@ pub struct WindowCallbacks {
     resized: Option<StoredCallback<WindowResizedCallback>>,
     moved: Option<StoredCallback<WindowMovedCallback>>,
     focus: Option<StoredCallback<WindowFocusCallback>>,
+    appearance: Option<StoredCallback<AppearanceCallback>>,
     navigation: Option<StoredCallback<NavigationCallback>>,
+    event: Option<StoredCallback<EventLoopCallback>>,
     /// Cached window pointer for callbacks
     window_ptr: WryWindow,
 }
@@ -101,7 +176,9 @@ impl WindowCallbacks {
             resized: None,
             moved: None,
             focus: None,
+            appearance: None,
             navigation: None,
+            event: None,
             window_ptr: std::ptr::null_mut(),
         }
     }
@@ -150,6 +227,13 @@ impl WindowCallbacks {
         });
     }
 
+    pub fn set_appearance(&mut self, callback: AppearanceCallback, user_data: *mut c_void) {
+        self.appearance = callback.map(|_| StoredCallback {
+            callback,
+            user_data,
+        });
+    }
+
     pub fn set_navigation(&mut self, callback: NavigationCallback, user_data: *mut c_void) {
         self.navigation = Some(StoredCallback {
             callback,
@@ -157,10 +241,31 @@ impl WindowCallbacks {
         });
     }
 
+    pub fn set_event(&mut self, callback: EventLoopCallback, user_data: *mut c_void) {
+        self.event = Some(StoredCallback {
+            callback,
+            user_data,
+        });
+    }
+
     // ========================================================================
     // Callers
     // ========================================================================
 
+    /// Whether an event callback is registered (avoids serializing for nobody)
+    pub fn has_event(&self) -> bool {
+        self.event.is_some()
+    }
+
+    /// Call the event callback with a serialized event
+    pub fn call_event(&self, event_json: &str) {
+        if let Some(ref cb) = self.event {
+            if let Ok(c_json) = CString::new(event_json) {
+                (cb.callback)(c_json.as_ptr(), cb.user_data);
+            }
+        }
+    }
+
     /// Call the closing callback, returns true if close should proceed
     pub fn call_closing(&self) -> bool {
         if let Some(ref cb) = self.closing {
@@ -191,6 +296,17 @@ impl WindowCallbacks {
         }
     }
 
+    /// Call the appearance callback
+    pub fn call_appearance(&self, is_dark: bool) {
+        if let Some(StoredCallback {
+            callback: Some(callback),
+            user_data,
+        }) = self.appearance
+        {
+            unsafe { callback(is_dark, user_data) };
+        }
+    }
+
     /// Call the navigation callback, returns true if navigation should proceed
     pub fn call_navigation(&self, url: &str) -> bool {
         if let Some(ref cb) = self.navigation {
@@ -294,6 +410,46 @@ pub unsafe extern "C" fn wry_window_set_focus_callback(
     }
 }
 
+/// Set callback for changes to the window's effective appearance
+///
+/// Observes the NSWindow's `effectiveAppearance`, so it fires when the OS
+/// theme changes and when an appearance override on the window or its
+/// parent switches it between light and dark. Pass a null callback to remove
+/// it. macOS only.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_appearance_callback(
+    window: WryWindow,
+    callback: AppearanceCallback,
+    user_data: *mut c_void,
+) -> bool {
+    if !cfg!(target_os = "macos") {
+        crate::error::set_last_error("Not supported on this platform");
+        return false;
+    }
+
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if callback.is_none() {
+        state.appearance_observer = None;
+    } else if state.appearance_observer.is_none() {
+        let proxy = state.event_loop_proxy.clone();
+        state.appearance_observer =
+            crate::appearance::watch_window_appearance(&state.window, state.id, proxy);
+        if state.appearance_observer.is_none() {
+            crate::error::set_last_error("Failed to observe window appearance");
+            return false;
+        }
+    }
+
+    state.callbacks.set_window_ptr(window);
+    state.callbacks.set_appearance(callback, user_data);
+    log::debug!("Appearance callback set for window {:?}", state.id);
+    true
+}
+
 /// Set callback for navigation (can cancel)
 #[no_mangle]
 pub unsafe extern "C" fn wry_window_set_navigation_callback(
@@ -307,3 +463,24 @@ pub unsafe extern "C" fn wry_window_set_navigation_callback(
         log::debug!("Navigation callback set for window {:?}", state.id);
     }
 }
+
+/// Set callback receiving only this window's events as JSON
+///
+/// Called after the pump callback, with the same JSON strings. The returned
+/// control flow is ignored; use `wry_app_quit` to stop the event loop.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_event_callback(
+    window: WryWindow,
+    callback: EventLoopCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    state.callbacks.set_window_ptr(window);
+    state.callbacks.set_event(callback, user_data);
+    log::debug!("Event callback set for window {:?}", state.id);
+    true
+}
@@ -0,0 +1,369 @@
+//! Custom cursor images
+//!
+//! Builds native cursors from RGBA pixel data. tao only supports system
+//! cursor shapes and re-applies its own cursor as the pointer moves, so the
+//! custom cursor is hooked into each platform's cursor update instead.
+
+use crate::error::set_last_error;
+use crate::types::WryWindow;
+use crate::window::get_window_state_mut;
+
+// ============================================================================
+// Platform Cursors
+// ============================================================================
+
+/// Native cursor created from pixel data, released on drop
+#[cfg(target_os = "macos")]
+pub struct CustomCursor {
+    cursor: objc2::rc::Retained<objc2::runtime::AnyObject>,
+    /// Overlay view whose cursor rect shows `cursor`, added by `apply`
+    view: Option<objc2::rc::Retained<objc2::runtime::AnyObject>>,
+}
+
+/// Native cursor created from pixel data, released on drop
+#[cfg(target_os = "windows")]
+pub struct CustomCursor {
+    cursor: windows::Win32::UI::WindowsAndMessaging::HCURSOR,
+    /// Window subclassed by `apply` to show `cursor`
+    hwnd: Option<windows::Win32::Foundation::HWND>,
+}
+
+/// Native cursor created from pixel data, released on drop
+#[cfg(target_os = "linux")]
+pub struct CustomCursor(gtk::gdk::Cursor);
+
+/// Native cursor created from pixel data, released on drop
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub struct CustomCursor;
+
+#[cfg(target_os = "macos")]
+impl CustomCursor {
+    fn create(
+        _window: &tao::window::Window,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        hotspot_x: u32,
+        hotspot_y: u32,
+    ) -> Option<Self> {
+        use objc2::rc::{Allocated, Retained};
+        use objc2::runtime::AnyObject;
+        use objc2::{class, msg_send};
+        use objc2_foundation::NSPoint;
+
+        let image = crate::icon::ns_image_from_rgba(rgba, width, height)?;
+
+        unsafe {
+            let hotspot = NSPoint::new(hotspot_x as f64, hotspot_y as f64);
+            let cursor: Allocated<AnyObject> = msg_send![class!(NSCursor), alloc];
+            let cursor: Option<Retained<AnyObject>> =
+                msg_send![cursor, initWithImage: &*image, hotSpot: hotspot];
+            cursor.map(|cursor| CustomCursor { cursor, view: None })
+        }
+    }
+
+    /// Cover the content view with a click-through view owning a cursor rect
+    ///
+    /// tao's view re-adds a cursor rect for its own cursor whenever AppKit
+    /// resets them, so a one-off `[NSCursor set]` would not last.
+    fn apply(&mut self, window: &tao::window::Window) {
+        use objc2::msg_send;
+        use objc2::rc::{Allocated, Retained};
+        use objc2::runtime::AnyObject;
+        use objc2_foundation::NSRect;
+        use tao::platform::macos::WindowExtMacOS;
+
+        // NSViewWidthSizable | NSViewHeightSizable
+        const RESIZE_WITH_SUPERVIEW: usize = 2 | 16;
+
+        let ns_window = window.ns_window() as *mut AnyObject;
+        let class = cursor_view_class();
+        unsafe {
+            let content_view: *mut AnyObject = msg_send![ns_window, contentView];
+            let bounds: NSRect = msg_send![content_view, bounds];
+            let view: Allocated<AnyObject> = msg_send![class, alloc];
+            let view: Option<Retained<AnyObject>> = msg_send![view, initWithFrame: bounds];
+            let Some(view) = view else { return };
+
+            let ivar = class.instance_variable(CURSOR_IVAR).unwrap();
+            *ivar.load_ptr::<*const AnyObject>(&view) = &*self.cursor;
+            let _: () = msg_send![&*view, setAutoresizingMask: RESIZE_WITH_SUPERVIEW];
+            let _: () = msg_send![content_view, addSubview: &*view];
+            let _: () = msg_send![ns_window, invalidateCursorRectsForView: &*view];
+            let _: () = msg_send![&*self.cursor, set];
+            self.view = Some(view);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for CustomCursor {
+    fn drop(&mut self) {
+        use objc2::msg_send;
+
+        if let Some(view) = self.view.take() {
+            unsafe {
+                let _: () = msg_send![&*view, removeFromSuperview];
+            }
+        }
+    }
+}
+
+/// Instance variable holding the overlay view's unretained NSCursor
+#[cfg(target_os = "macos")]
+const CURSOR_IVAR: &std::ffi::CStr = c"wryCursor";
+
+/// NSView subclass showing its cursor over its bounds and passing clicks on
+#[cfg(target_os = "macos")]
+fn cursor_view_class() -> &'static objc2::runtime::AnyClass {
+    use std::sync::OnceLock;
+
+    use objc2::runtime::{AnyClass, AnyObject, ClassBuilder, Sel};
+    use objc2::{class, msg_send, sel};
+    use objc2_foundation::{NSPoint, NSRect};
+
+    extern "C" fn reset_cursor_rects(this: &AnyObject, _sel: Sel) {
+        unsafe {
+            let ivar = this.class().instance_variable(CURSOR_IVAR).unwrap();
+            let cursor = *ivar.load::<*const AnyObject>(this);
+            let bounds: NSRect = msg_send![this, bounds];
+            if !cursor.is_null() {
+                let _: () = msg_send![this, addCursorRect: bounds, cursor: cursor];
+            }
+        }
+    }
+
+    extern "C" fn hit_test(_this: &AnyObject, _sel: Sel, _point: NSPoint) -> *mut AnyObject {
+        std::ptr::null_mut()
+    }
+
+    static CLASS: OnceLock<&'static AnyClass> = OnceLock::new();
+    CLASS.get_or_init(|| {
+        let mut builder = ClassBuilder::new(c"WryCursorView", class!(NSView))
+            .expect("WryCursorView registered twice");
+        builder.add_ivar::<*const AnyObject>(CURSOR_IVAR);
+        unsafe {
+            builder.add_method(
+                sel!(resetCursorRects),
+                reset_cursor_rects as extern "C" fn(_, _),
+            );
+            builder.add_method(sel!(hitTest:), hit_test as extern "C" fn(_, _, _) -> _);
+        }
+        builder.register()
+    })
+}
+
+#[cfg(target_os = "windows")]
+impl CustomCursor {
+    fn create(
+        _window: &tao::window::Window,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        hotspot_x: u32,
+        hotspot_y: u32,
+    ) -> Option<Self> {
+        use windows::Win32::UI::WindowsAndMessaging::HCURSOR;
+
+        let icon = crate::icon::hicon_from_rgba(rgba, width, height, Some((hotspot_x, hotspot_y)))?;
+        Some(CustomCursor {
+            cursor: HCURSOR(icon.0),
+            hwnd: None,
+        })
+    }
+
+    /// Answer the window's WM_SETCURSOR with this cursor
+    ///
+    /// tao sets its own cursor on every WM_SETCURSOR, so a one-off
+    /// `SetCursor` would be replaced as soon as the pointer moves.
+    fn apply(&mut self, window: &tao::window::Window) {
+        use tao::platform::windows::WindowExtWindows;
+        use windows::Win32::Foundation::HWND;
+        use windows::Win32::UI::Shell::SetWindowSubclass;
+        use windows::Win32::UI::WindowsAndMessaging::SetCursor;
+
+        let hwnd = HWND(window.hwnd() as *mut _);
+        unsafe {
+            let cursor = self.cursor.0 as usize;
+            if !SetWindowSubclass(hwnd, Some(cursor_subclass_proc), CURSOR_SUBCLASS_ID, cursor)
+                .as_bool()
+            {
+                log::warn!("Failed to subclass window for custom cursor");
+            }
+            SetCursor(Some(self.cursor));
+        }
+        self.hwnd = Some(hwnd);
+    }
+}
+
+#[cfg(target_os = "windows")]
+impl Drop for CustomCursor {
+    fn drop(&mut self) {
+        use windows::Win32::UI::Shell::RemoveWindowSubclass;
+        use windows::Win32::UI::WindowsAndMessaging::DestroyCursor;
+
+        unsafe {
+            if let Some(hwnd) = self.hwnd {
+                let _ = RemoveWindowSubclass(hwnd, Some(cursor_subclass_proc), CURSOR_SUBCLASS_ID);
+            }
+            let _ = DestroyCursor(self.cursor);
+        }
+    }
+}
+
+/// Identifies the cursor subclass among any others on the window
+#[cfg(target_os = "windows")]
+const CURSOR_SUBCLASS_ID: usize = 0x6375_7273;
+
+#[cfg(target_os = "windows")]
+unsafe extern "system" fn cursor_subclass_proc(
+    hwnd: windows::Win32::Foundation::HWND,
+    msg: u32,
+    wparam: windows::Win32::Foundation::WPARAM,
+    lparam: windows::Win32::Foundation::LPARAM,
+    _subclass_id: usize,
+    ref_data: usize,
+) -> windows::Win32::Foundation::LRESULT {
+    use windows::Win32::Foundation::LRESULT;
+    use windows::Win32::UI::Shell::{DefSubclassProc, RemoveWindowSubclass};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SetCursor, HCURSOR, HTCLIENT, WM_NCDESTROY, WM_SETCURSOR,
+    };
+
+    // The hit-test result is in the low word, only the client area is ours
+    if msg == WM_SETCURSOR && (lparam.0 & 0xffff) as u32 == HTCLIENT {
+        SetCursor(Some(HCURSOR(ref_data as *mut _)));
+        return LRESULT(1);
+    }
+
+    if msg == WM_NCDESTROY {
+        let _ = RemoveWindowSubclass(hwnd, Some(cursor_subclass_proc), CURSOR_SUBCLASS_ID);
+    }
+
+    DefSubclassProc(hwnd, msg, wparam, lparam)
+}
+
+#[cfg(target_os = "linux")]
+impl CustomCursor {
+    fn create(
+        window: &tao::window::Window,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        hotspot_x: u32,
+        hotspot_y: u32,
+    ) -> Option<Self> {
+        use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+        use gtk::glib::Bytes;
+        use gtk::prelude::*;
+        use tao::platform::unix::WindowExtUnix;
+
+        let display = window.gtk_window().display();
+        let pixbuf = Pixbuf::from_bytes(
+            &Bytes::from(rgba),
+            Colorspace::Rgb,
+            true,
+            8,
+            width as i32,
+            height as i32,
+            (width * 4) as i32,
+        );
+
+        Some(CustomCursor(gtk::gdk::Cursor::from_pixbuf(
+            &display,
+            &pixbuf,
+            hotspot_x as i32,
+            hotspot_y as i32,
+        )))
+    }
+
+    fn apply(&mut self, window: &tao::window::Window) {
+        use gtk::prelude::*;
+        use tao::platform::unix::WindowExtUnix;
+
+        if let Some(gdk_window) = window.gtk_window().window() {
+            gdk_window.set_cursor(Some(&self.0));
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+impl CustomCursor {
+    fn create(
+        _window: &tao::window::Window,
+        _rgba: &[u8],
+        _width: u32,
+        _height: u32,
+        _hotspot_x: u32,
+        _hotspot_y: u32,
+    ) -> Option<Self> {
+        None
+    }
+
+    fn apply(&mut self, _window: &tao::window::Window) {}
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// Set a custom cursor from RGBA pixel data
+///
+/// `rgba` must hold exactly `width * height * 4` bytes, row by row from the
+/// top left. The hotspot is the click point in pixels from the top left.
+/// As with system cursors, the webview may replace the cursor as the pointer
+/// moves over page content.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_cursor_icon_rgba(
+    window: WryWindow,
+    rgba: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+    hotspot_x: u32,
+    hotspot_y: u32,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if rgba.is_null() {
+        set_last_error("Null cursor pixel data");
+        return false;
+    }
+
+    let expected = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|n| n.checked_mul(4));
+    if width == 0 || height == 0 || expected != Some(len) {
+        set_last_error(format!(
+            "Cursor data is {} bytes, expected {}x{}x4",
+            len, width, height
+        ));
+        return false;
+    }
+
+    if hotspot_x >= width || hotspot_y >= height {
+        set_last_error("Cursor hotspot outside the image");
+        return false;
+    }
+
+    let rgba = std::slice::from_raw_parts(rgba, len);
+    let cursor = CustomCursor::create(&state.window, rgba, width, height, hotspot_x, hotspot_y);
+    let mut cursor = match cursor {
+        Some(c) => c,
+        None => {
+            set_last_error("Failed to create cursor");
+            return false;
+        }
+    };
+
+    log::debug!("Setting custom cursor: {}x{}", width, height);
+
+    // Release the previous cursor first so its hook does not replace this one
+    state.custom_cursor = None;
+    cursor.apply(&state.window);
+    state.custom_cursor = Some(cursor);
+    true
+}
@@ -0,0 +1,156 @@
+//! Deep link handling
+//!
+//! Registers the app as the handler for a URL scheme and forwards opened
+//! URLs to the host.
+
+use std::ffi::{c_char, CString};
+use std::os::raw::c_void;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::error::set_last_error;
+use crate::string::c_str_to_string;
+use crate::types::UrlHandler;
+
+/// Handler for opened URLs (callback, user_data)
+static URL_HANDLER: Lazy<Mutex<Option<(UrlHandler, usize)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Forward URLs from `Event::Opened` to the registered handler
+pub fn handle_opened_urls<'a>(urls: impl IntoIterator<Item = &'a str>) {
+    let handler = *URL_HANDLER.lock().unwrap();
+    let (callback, user_data) = match handler {
+        Some((Some(callback), user_data)) => (callback, user_data),
+        _ => return,
+    };
+
+    for url in urls {
+        log::debug!("Opened URL: {}", url);
+        if let Ok(url) = CString::new(url) {
+            unsafe { callback(url.as_ptr(), user_data as *mut c_void) };
+        }
+    }
+}
+
+/// RFC 3986: ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
+fn valid_scheme(scheme: &str) -> bool {
+    let mut chars = scheme.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+// ============================================================================
+// Platform Registration
+// ============================================================================
+
+#[cfg(target_os = "macos")]
+fn register_scheme(scheme: &str) -> Result<(), String> {
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+
+    #[link(name = "CoreServices", kind = "framework")]
+    extern "C" {
+        fn LSSetDefaultHandlerForURLScheme(scheme: *const c_void, bundle_id: *const c_void) -> i32;
+    }
+
+    let bundle_id: Option<Retained<NSString>> = unsafe {
+        let bundle: *mut AnyObject = msg_send![class!(NSBundle), mainBundle];
+        msg_send![bundle, bundleIdentifier]
+    };
+    let bundle_id = bundle_id.ok_or("App has no bundle identifier")?;
+    let scheme = NSString::from_str(scheme);
+
+    // NSString is toll-free bridged with CFString
+    let status = unsafe {
+        LSSetDefaultHandlerForURLScheme(
+            &*scheme as *const NSString as *const c_void,
+            &*bundle_id as *const NSString as *const c_void,
+        )
+    };
+    if status != 0 {
+        return Err(format!("LSSetDefaultHandlerForURLScheme failed: {}", status));
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn register_scheme(scheme: &str) -> Result<(), String> {
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::System::Registry::{RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ};
+
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to get executable path: {}", e))?;
+    let key = format!(r"Software\Classes\{}", scheme);
+    let command_key = format!(r"{}\shell\open\command", key);
+    let command = format!("\"{}\" \"%1\"", exe.display());
+
+    let set = |key: &str, name: Option<&str>, value: &str| -> Result<(), String> {
+        let data: Vec<u16> = value.encode_utf16().chain(Some(0)).collect();
+        let name = name.map(HSTRING::from);
+        let status = unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(key),
+                name.as_ref().map(|n| PCWSTR(n.as_ptr())).unwrap_or(PCWSTR::null()),
+                REG_SZ.0,
+                Some(data.as_ptr() as *const c_void),
+                (data.len() * 2) as u32,
+            )
+        };
+        status.ok().map_err(|e| format!("Failed to write {}: {}", key, e))
+    };
+
+    set(&key, None, &format!("URL:{} Protocol", scheme))?;
+    set(&key, Some("URL Protocol"), "")?;
+    set(&command_key, None, &command)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn register_scheme(_scheme: &str) -> Result<(), String> {
+    Err("URL scheme registration is only supported on macOS and Windows".to_string())
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// Register the app as the handler for a URL scheme (e.g. "myapp")
+///
+/// On macOS the scheme must also be declared under `CFBundleURLTypes` in
+/// the app's Info.plist. On Windows this writes the handler under
+/// `HKCU\Software\Classes`, launching the current executable with the URL
+/// as its argument.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_register_url_scheme(scheme: *const c_char) -> bool {
+    let scheme = match c_str_to_string(scheme) {
+        Some(s) if valid_scheme(&s) => s,
+        _ => {
+            set_last_error("Null or invalid URL scheme");
+            return false;
+        }
+    };
+
+    match register_scheme(&scheme) {
+        Ok(()) => {
+            log::debug!("Registered URL scheme: {}", scheme);
+            true
+        }
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Set the handler for URLs opened with the app
+///
+/// Called on the UI thread for each URL the OS asks the app to open while
+/// the event loop runs. On Windows and Linux URLs arrive as command-line
+/// arguments instead, see `wry_app_single_instance`. Pass null to clear.
+#[no_mangle]
+pub extern "C" fn wry_app_set_url_handler(callback: UrlHandler, user_data: *mut c_void) {
+    *URL_HANDLER.lock().unwrap() = callback.map(|cb| (Some(cb), user_data as usize));
+    log::debug!("URL handler {}", if callback.is_some() { "set" } else { "cleared" });
+}
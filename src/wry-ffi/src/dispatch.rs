@@ -2,12 +2,106 @@
 //!
 //! Provides thread-safe invocation of callbacks on the UI thread.
 
+use std::cmp::{Ordering as CmpOrdering, Reverse};
+use std::collections::BinaryHeap;
+use std::ffi::c_char;
 use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
 use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use tao::event_loop::EventLoopProxy;
 
 use crate::app::{AppState, UserEvent};
 use crate::error::set_last_error;
-use crate::types::{InvokeCallback, WryApp};
+use crate::string::c_str_to_str;
+use crate::types::{InvokeCallback, WryApp, WryEventLoopProxy};
+
+/// Event loop proxy that can outlive borrows of the app state
+pub struct ProxyHandle {
+    pub proxy: EventLoopProxy<UserEvent>,
+    /// Cleared once a send reports the event loop has closed
+    pub alive: AtomicBool,
+}
+
+impl ProxyHandle {
+    /// Send an event, recording whether the event loop is still alive
+    pub fn send(&self, event: UserEvent) -> bool {
+        let sent = self.proxy.send_event(event).is_ok();
+        if !sent {
+            self.alive.store(false, Ordering::SeqCst);
+        }
+        sent
+    }
+}
+
+/// Callback queued by `wry_dispatch_after`, waiting for its deadline
+struct DelayedCallback {
+    deadline: Instant,
+    /// Keeps callbacks with the same deadline in the order they were queued
+    seq: u64,
+    proxy: EventLoopProxy<UserEvent>,
+    callback: Box<dyn FnOnce() + Send>,
+}
+
+impl PartialEq for DelayedCallback {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for DelayedCallback {}
+
+impl PartialOrd for DelayedCallback {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DelayedCallback {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        (self.deadline, self.seq).cmp(&(other.deadline, other.seq))
+    }
+}
+
+/// Feeds the timer thread shared by every `wry_dispatch_after` call
+static DELAYED_CALLBACKS: Lazy<Sender<DelayedCallback>> = Lazy::new(|| {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::Builder::new()
+        .name("wry-dispatch-timer".to_string())
+        .spawn(move || run_timer(receiver))
+        .expect("failed to spawn the dispatch timer thread");
+    sender
+});
+
+/// Queue each callback on its event loop once its deadline passes
+fn run_timer(receiver: mpsc::Receiver<DelayedCallback>) {
+    let mut pending: BinaryHeap<Reverse<DelayedCallback>> = BinaryHeap::new();
+    loop {
+        let now = Instant::now();
+        while let Some(Reverse(next)) = pending.peek() {
+            if next.deadline > now {
+                break;
+            }
+            let Reverse(due) = pending.pop().unwrap();
+            if due.proxy.send_event(UserEvent::InvokeCallback(due.callback)).is_err() {
+                log::warn!("Dropping delayed callback - event loop has exited");
+            }
+        }
+
+        let received = match pending.peek() {
+            Some(Reverse(next)) => receiver.recv_timeout(next.deadline - now),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(delayed) => pending.push(Reverse(delayed)),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
 
 // ============================================================================
 // FFI Functions
@@ -95,3 +189,162 @@ pub unsafe extern "C" fn wry_invoke_sync(
 
     log::debug!("wry_invoke_sync: callback completed");
 }
+
+/// Execute callback on UI thread after a delay (thread-safe)
+///
+/// The callback is queued once `delay_ms` milliseconds have passed, so it
+/// runs no earlier than that. It is dropped if the event loop has exited
+/// by then. One timer thread serves every delayed callback.
+#[no_mangle]
+pub unsafe extern "C" fn wry_dispatch_after(
+    app: WryApp,
+    delay_ms: u32,
+    callback: InvokeCallback,
+    user_data: *mut c_void,
+) {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return;
+    }
+
+    let state = &*(app as *const AppState);
+    log::debug!("wry_dispatch_after: queueing callback in {}ms", delay_ms);
+
+    static NEXT_SEQ: AtomicU64 = AtomicU64::new(0);
+    let user_data_ptr = user_data as usize;
+    let delayed = DelayedCallback {
+        deadline: Instant::now() + Duration::from_millis(delay_ms as u64),
+        seq: NEXT_SEQ.fetch_add(1, Ordering::Relaxed),
+        proxy: state.event_loop_proxy.clone(),
+        callback: Box::new(move || {
+            callback(user_data_ptr as *mut c_void);
+        }),
+    };
+    if DELAYED_CALLBACKS.send(delayed).is_err() {
+        set_last_error("Dispatch timer thread is not running");
+    }
+}
+
+/// Create a proxy for sending events to the event loop from any thread
+///
+/// The proxy must be freed with `wry_event_loop_proxy_destroy`.
+///
+/// # Returns
+/// Proxy handle or NULL on failure
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_create_proxy(app: WryApp) -> WryEventLoopProxy {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return std::ptr::null_mut();
+    }
+
+    let state = &*(app as *const AppState);
+    let handle = Box::new(ProxyHandle {
+        proxy: state.event_loop_proxy.clone(),
+        alive: AtomicBool::new(true),
+    });
+
+    Box::into_raw(handle) as WryEventLoopProxy
+}
+
+/// Destroy an event loop proxy
+///
+/// # Safety
+/// The proxy handle must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_proxy_destroy(proxy: WryEventLoopProxy) {
+    if proxy.is_null() {
+        return;
+    }
+
+    let _ = Box::from_raw(proxy as *mut ProxyHandle);
+}
+
+/// Inject a synthetic event into the event loop (thread-safe)
+///
+/// `event_json` must be a JSON object with a string `type` field, e.g.
+/// `{"type":"window-close-requested","window_id":"..."}`. The event is
+/// delivered unchanged to the app event callback and the pump callback.
+///
+/// Only user-event types are supported: the event is not turned into a
+/// native window event, so posting `window-close-requested` notifies
+/// listeners but does not close the window.
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_post_event(
+    proxy: WryEventLoopProxy,
+    event_json: *const c_char,
+) -> bool {
+    if proxy.is_null() {
+        set_last_error("Null proxy handle");
+        return false;
+    }
+
+    let json = match c_str_to_str(event_json) {
+        Some(j) => j,
+        None => {
+            set_last_error("Null or invalid event JSON");
+            return false;
+        }
+    };
+
+    let value: serde_json::Value = match serde_json::from_str(json) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("Failed to parse event JSON: {}", e));
+            return false;
+        }
+    };
+
+    if !value.get("type").is_some_and(|t| t.is_string()) {
+        set_last_error("Event JSON must be an object with a string \"type\" field");
+        return false;
+    }
+
+    let handle = &*(proxy as *const ProxyHandle);
+    if !handle.send(UserEvent::Custom(value.to_string())) {
+        set_last_error("Failed to post event - event loop may not be running");
+        return false;
+    }
+
+    true
+}
+
+/// Wake the event loop from any thread (thread-safe)
+///
+/// This is the recommended way to get a waiting loop to run again, e.g.
+/// after a background task has updated state the UI should pick up. The
+/// app event callback and the pump callback receive `{"type":"wake"}`.
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_wake(proxy: WryEventLoopProxy) -> bool {
+    if proxy.is_null() {
+        set_last_error("Null proxy handle");
+        return false;
+    }
+
+    let handle = &*(proxy as *const ProxyHandle);
+    if !handle.send(UserEvent::Custom(r#"{"type":"wake"}"#.to_string())) {
+        set_last_error("Failed to wake event loop - event loop may not be running");
+        return false;
+    }
+
+    true
+}
+
+/// Check whether the event loop behind a proxy is still alive (thread-safe)
+///
+/// Sends a no-op event; once the event loop has closed this returns false
+/// for the remaining lifetime of the proxy. Use it to guard sends from
+/// background threads.
+#[no_mangle]
+pub unsafe extern "C" fn wry_event_loop_proxy_is_valid(proxy: WryEventLoopProxy) -> bool {
+    if proxy.is_null() {
+        return false;
+    }
+
+    let handle = &*(proxy as *const ProxyHandle);
+    if !handle.alive.load(Ordering::SeqCst) {
+        return false;
+    }
+
+    handle.send(UserEvent::Ping)
+}
@@ -0,0 +1,388 @@
+//! Dragging data out of the app
+//!
+//! Starts a drag-and-drop operation carrying text, a URL or files that
+//! other applications can accept, as if dragged from a native view.
+
+use crate::error::set_last_error;
+use crate::string::c_str_to_string;
+use crate::types::{WryDragData, WryWindow};
+use crate::window::get_window_state;
+
+/// Drag contents copied out of the caller's memory
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+struct DragItem {
+    text: Option<String>,
+    url: Option<String>,
+    files: Vec<String>,
+    /// (pixels, width, height)
+    image: Option<(Vec<u8>, u32, u32)>,
+}
+
+/// Copy and validate the caller's drag data
+unsafe fn read_drag_data(data: &WryDragData) -> Result<DragItem, String> {
+    if data.file_paths.is_null() && data.file_count > 0 {
+        return Err("Null file paths".to_string());
+    }
+    let paths = if data.file_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(data.file_paths, data.file_count)
+    };
+    let files = paths
+        .iter()
+        .enumerate()
+        .map(|(i, &path)| {
+            c_str_to_string(path)
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| format!("File path {} is null or invalid", i))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let image = if data.image_rgba.is_null() || data.image_len == 0 {
+        None
+    } else {
+        let expected = data.image_width as usize * data.image_height as usize * 4;
+        if expected == 0 || data.image_len != expected {
+            return Err(format!(
+                "Drag image is {} bytes, expected {} for {}x{}",
+                data.image_len, expected, data.image_width, data.image_height
+            ));
+        }
+        let rgba = std::slice::from_raw_parts(data.image_rgba, data.image_len).to_vec();
+        Some((rgba, data.image_width, data.image_height))
+    };
+
+    let item = DragItem {
+        text: c_str_to_string(data.text),
+        url: c_str_to_string(data.url),
+        files,
+        image,
+    };
+    if item.text.is_none() && item.url.is_none() && item.files.is_empty() {
+        return Err("Drag data has no text, URL or files".to_string());
+    }
+    Ok(item)
+}
+
+/// Start dragging `data` out of the window
+///
+/// Must be called while the left mouse button is held, typically from a
+/// mousedown or dragstart handler in the page, like
+/// `wry_window_drag_begin`. On Windows this returns once the item has been
+/// dropped or the drag cancelled; on macOS the drag continues after it
+/// returns. Not supported on Linux.
+#[no_mangle]
+pub unsafe extern "C" fn wry_drag_item(window: WryWindow, data: *const WryDragData) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if data.is_null() {
+        set_last_error("Null drag data");
+        return false;
+    }
+
+    let item = match read_drag_data(&*data) {
+        Ok(item) => item,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    if !crate::window_ops::left_mouse_button_down(&state.window) {
+        log::warn!("wry_drag_item called without the left mouse button pressed");
+        set_last_error("Left mouse button is not pressed");
+        return false;
+    }
+
+    log::debug!(
+        "Starting drag: text={} url={} files={}",
+        item.text.is_some(),
+        item.url.is_some(),
+        item.files.len()
+    );
+    match start_drag(&state.window, &item) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn start_drag(window: &tao::window::Window, item: &DragItem) -> Result<(), String> {
+    use objc2::rc::Retained;
+    use objc2::runtime::{AnyObject, Bool};
+    use objc2::{class, msg_send};
+    use objc2_foundation::{NSPoint, NSSize, NSString};
+    use tao::platform::macos::WindowExtMacOS;
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    let ns_view = window.ns_view() as *mut AnyObject;
+
+    unsafe {
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let event: *mut AnyObject = msg_send![app, currentEvent];
+        if event.is_null() {
+            return Err("No mouse event to start the drag from".to_string());
+        }
+
+        // NSString and NSURL both write themselves to a pasteboard
+        let objects: Option<Retained<AnyObject>> = msg_send![class!(NSMutableArray), array];
+        let objects = objects.ok_or("Failed to create pasteboard item list")?;
+        if let Some(text) = &item.text {
+            let text = NSString::from_str(text);
+            let _: () = msg_send![&*objects, addObject: &*text];
+        }
+        if let Some(url) = &item.url {
+            let url = NSString::from_str(url);
+            let url: Option<Retained<AnyObject>> = msg_send![class!(NSURL), URLWithString: &*url];
+            let url = url.ok_or("Invalid drag URL")?;
+            let _: () = msg_send![&*objects, addObject: &*url];
+        }
+        for path in &item.files {
+            let path = NSString::from_str(path);
+            let url: Option<Retained<AnyObject>> =
+                msg_send![class!(NSURL), fileURLWithPath: &*path];
+            if let Some(url) = url {
+                let _: () = msg_send![&*objects, addObject: &*url];
+            }
+        }
+
+        let name = NSString::from_str("Apple CFPasteboard drag");
+        let pasteboard: *mut AnyObject =
+            msg_send![class!(NSPasteboard), pasteboardWithName: &*name];
+        let _: isize = msg_send![pasteboard, clearContents];
+        let written: Bool = msg_send![pasteboard, writeObjects: &*objects];
+        if !written.as_bool() {
+            return Err("Failed to write drag data to the pasteboard".to_string());
+        }
+
+        let image = match &item.image {
+            Some((rgba, width, height)) => crate::icon::ns_image_from_rgba(rgba, *width, *height),
+            None => match item.files.first() {
+                Some(path) => {
+                    let path = NSString::from_str(path);
+                    let workspace: *mut AnyObject = msg_send![class!(NSWorkspace), sharedWorkspace];
+                    msg_send![workspace, iconForFile: &*path]
+                }
+                None => crate::icon::ns_image_from_rgba(&[0; 4], 1, 1),
+            },
+        };
+        let image = image.ok_or("Failed to create drag image")?;
+
+        // Center the image under the cursor
+        let size: NSSize = msg_send![&*image, size];
+        let location: NSPoint = msg_send![event, locationInWindow];
+        let at = NSPoint::new(
+            location.x - size.width / 2.0,
+            location.y - size.height / 2.0,
+        );
+        let _: () = msg_send![
+            ns_window,
+            dragImage: &*image,
+            at: at,
+            offset: NSSize::new(0.0, 0.0),
+            event: event,
+            pasteboard: pasteboard,
+            source: ns_view,
+            slideBack: Bool::YES
+        ];
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn start_drag(window: &tao::window::Window, item: &DragItem) -> Result<(), String> {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::core::w;
+    use windows::Win32::Foundation::{HWND, POINT};
+    use windows::Win32::System::Com::IDataObject;
+    use windows::Win32::System::DataExchange::RegisterClipboardFormatW;
+    use windows::Win32::System::Ole::{
+        IDropSource, CF_HDROP, CF_UNICODETEXT, DROPEFFECT_COPY, DROPEFFECT_LINK,
+    };
+    use windows::Win32::UI::Shell::{SHCreateDataObject, SHDoDragDrop, DROPFILES};
+
+    fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    let data_object: IDataObject = unsafe { SHCreateDataObject(None, None, None) }
+        .map_err(|e| format!("Failed to create data object: {}", e))?;
+
+    let text = item.text.as_deref().or(item.url.as_deref());
+    if let Some(text) = text {
+        set_global_data(&data_object, CF_UNICODETEXT.0, &wide_bytes(&wide(text)))?;
+    }
+    if let Some(url) = &item.url {
+        let format = unsafe { RegisterClipboardFormatW(w!("UniformResourceLocatorW")) };
+        set_global_data(&data_object, format as u16, &wide_bytes(&wide(url)))?;
+    }
+    if !item.files.is_empty() {
+        // DROPFILES followed by NUL-separated wide paths and a final NUL
+        let header = DROPFILES {
+            pFiles: std::mem::size_of::<DROPFILES>() as u32,
+            pt: POINT::default(),
+            fNC: false.into(),
+            fWide: true.into(),
+        };
+        let mut paths: Vec<u16> = item.files.iter().flat_map(|p| wide(p)).collect();
+        paths.push(0);
+        let mut bytes = unsafe {
+            std::slice::from_raw_parts(
+                &header as *const DROPFILES as *const u8,
+                std::mem::size_of::<DROPFILES>(),
+            )
+        }
+        .to_vec();
+        bytes.extend_from_slice(&wide_bytes(&paths));
+        set_global_data(&data_object, CF_HDROP.0, &bytes)?;
+    }
+
+    if let Some((rgba, width, height)) = &item.image {
+        if let Err(e) = set_drag_image(&data_object, rgba, *width, *height) {
+            log::warn!("Failed to set drag image: {}", e);
+        }
+    }
+
+    let hwnd = HWND(window.hwnd() as *mut _);
+    unsafe {
+        SHDoDragDrop(
+            Some(hwnd),
+            &data_object,
+            None::<&IDropSource>,
+            DROPEFFECT_COPY | DROPEFFECT_LINK,
+        )
+    }
+    .map(|_| ())
+    .map_err(|e| format!("Drag failed: {}", e))
+}
+
+#[cfg(target_os = "windows")]
+fn wide_bytes(wide: &[u16]) -> Vec<u8> {
+    wide.iter().flat_map(|c| c.to_ne_bytes()).collect()
+}
+
+/// Store `bytes` in the data object under `format` as an HGLOBAL
+#[cfg(target_os = "windows")]
+fn set_global_data(
+    data_object: &windows::Win32::System::Com::IDataObject,
+    format: u16,
+    bytes: &[u8],
+) -> Result<(), String> {
+    use std::mem::ManuallyDrop;
+    use windows::Win32::Foundation::GlobalFree;
+    use windows::Win32::System::Com::{
+        DVASPECT_CONTENT, FORMATETC, STGMEDIUM, STGMEDIUM_0, TYMED_HGLOBAL,
+    };
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+
+    unsafe {
+        let global = GlobalAlloc(GMEM_MOVEABLE, bytes.len())
+            .map_err(|e| format!("Failed to allocate drag data: {}", e))?;
+        let ptr = GlobalLock(global) as *mut u8;
+        if ptr.is_null() {
+            let _ = GlobalFree(Some(global));
+            return Err("Failed to lock drag data".to_string());
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, bytes.len());
+        let _ = GlobalUnlock(global);
+
+        let format = FORMATETC {
+            cfFormat: format,
+            ptd: std::ptr::null_mut(),
+            dwAspect: DVASPECT_CONTENT.0,
+            lindex: -1,
+            tymed: TYMED_HGLOBAL.0 as u32,
+        };
+        let medium = STGMEDIUM {
+            tymed: TYMED_HGLOBAL.0 as u32,
+            u: STGMEDIUM_0 { hGlobal: global },
+            pUnkForRelease: ManuallyDrop::new(None),
+        };
+        // The data object owns the memory once SetData succeeds
+        data_object.SetData(&format, &medium, true).map_err(|e| {
+            let _ = GlobalFree(Some(global));
+            format!("Failed to set drag data: {}", e)
+        })
+    }
+}
+
+/// Show straight RGBA pixels under the cursor during the drag
+#[cfg(target_os = "windows")]
+fn set_drag_image(
+    data_object: &windows::Win32::System::Com::IDataObject,
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+) -> windows::core::Result<()> {
+    use windows::Win32::Foundation::{COLORREF, POINT, SIZE};
+    use windows::Win32::Graphics::Gdi::{
+        CreateDIBSection, DeleteObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::{CLSID_DragDropHelper, IDragSourceHelper, SHDRAGIMAGE};
+
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // Negative height makes the bitmap top-down
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    unsafe {
+        let mut bits: *mut std::ffi::c_void = std::ptr::null_mut();
+        let bitmap = CreateDIBSection(None, &info, DIB_RGB_COLORS, &mut bits, None, 0)?;
+
+        // The shell expects premultiplied BGRA
+        let pixels = std::slice::from_raw_parts_mut(bits as *mut u8, rgba.len());
+        for (dst, src) in pixels.chunks_exact_mut(4).zip(rgba.chunks_exact(4)) {
+            let alpha = src[3] as u16;
+            dst[0] = (src[2] as u16 * alpha / 255) as u8;
+            dst[1] = (src[1] as u16 * alpha / 255) as u8;
+            dst[2] = (src[0] as u16 * alpha / 255) as u8;
+            dst[3] = src[3];
+        }
+
+        let image = SHDRAGIMAGE {
+            sizeDragImage: SIZE {
+                cx: width as i32,
+                cy: height as i32,
+            },
+            ptOffset: POINT {
+                x: width as i32 / 2,
+                y: height as i32 / 2,
+            },
+            hbmpDragImage: bitmap,
+            crColorKey: COLORREF(0xFFFF_FFFF),
+        };
+
+        // The helper takes ownership of the bitmap on success
+        let result = CoCreateInstance::<_, IDragSourceHelper>(
+            &CLSID_DragDropHelper,
+            None,
+            CLSCTX_INPROC_SERVER,
+        )
+        .and_then(|helper| helper.InitializeFromBitmap(&image, data_object));
+        if result.is_err() {
+            let _ = DeleteObject(bitmap.into());
+        }
+        result
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn start_drag(_window: &tao::window::Window, _item: &DragItem) -> Result<(), String> {
+    Err("Not supported on this platform".to_string())
+}
@@ -0,0 +1,466 @@
+//! Event serialization
+//!
+//! Converts events into JSON strings for delivery to the host through the
+//! app event callback and the event loop pump callback, or into `WryEvent`
+//! structs for the typed pump callback.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+
+use serde_json::{json, Value};
+use tao::event::{ElementState, Event, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent};
+use tao::window::{Theme, WindowId};
+
+use crate::app::UserEvent;
+use crate::string::c_str_to_str;
+use crate::types::{
+    WryEvent, WryEventData, WryEventKind, WryKeyEvent, WryModifiers, WryMouseButton, WryMouseEvent,
+    WryPoint, WryPosition, WrySize, WryWheelEvent,
+};
+
+/// Format a window ID the same way in every event
+pub fn window_id_string(window_id: WindowId) -> String {
+    format!("{:?}", window_id)
+}
+
+/// Serialize an event loop event, returning None for events the host never sees
+pub fn serialize_event(event: &Event<UserEvent>) -> Option<String> {
+    match event {
+        Event::UserEvent(user_event) => serialize_user_event(user_event),
+        Event::WindowEvent {
+            window_id, event, ..
+        } => serialize_window_event(*window_id, event),
+        _ => None,
+    }
+}
+
+/// Serialize a user event, returning None for internal events the host never sees
+pub fn serialize_user_event(event: &UserEvent) -> Option<String> {
+    let value = match event {
+        UserEvent::AppearanceChanged(is_dark) => json!({
+            "type": "appearance-changed",
+            "is_dark": is_dark,
+        }),
+        UserEvent::PageLoad {
+            window_id,
+            finished,
+            url,
+        } => json!({
+            "type": "webview-page-load",
+            "window_id": window_id_string(*window_id),
+            "state": if *finished { "finished" } else { "started" },
+            "url": url,
+        }),
+        // Already JSON, forwarded untouched
+        UserEvent::Custom(json) => return Some(json.clone()),
+        _ => return None,
+    };
+
+    Some(value.to_string())
+}
+
+/// Serialize a window event, returning None for events the host never sees
+pub fn serialize_window_event(window_id: WindowId, event: &WindowEvent) -> Option<String> {
+    let id = window_id_string(window_id);
+
+    let value = match event {
+        WindowEvent::CloseRequested => json!({
+            "type": "window-close-requested",
+            "window_id": id,
+        }),
+        WindowEvent::Destroyed => json!({
+            "type": "window-destroyed",
+            "window_id": id,
+        }),
+        WindowEvent::Resized(size) => json!({
+            "type": "window-resized",
+            "window_id": id,
+            "width": size.width,
+            "height": size.height,
+        }),
+        WindowEvent::Moved(position) => json!({
+            "type": "window-moved",
+            "window_id": id,
+            "x": position.x,
+            "y": position.y,
+        }),
+        WindowEvent::Focused(focused) => json!({
+            "type": "window-focused",
+            "window_id": id,
+            "focused": focused,
+        }),
+        WindowEvent::KeyboardInput { event, .. } => json!({
+            "type": "window-keyboard-input",
+            "window_id": id,
+            "state": element_state_str(event.state),
+            "physical_key": format!("{:?}", event.physical_key),
+            "logical_key": format!("{:?}", event.logical_key),
+            "text": event.text,
+            "repeat": event.repeat,
+        }),
+        WindowEvent::ModifiersChanged(modifiers) => json!({
+            "type": "window-modifiers-changed",
+            "window_id": id,
+            "shift": modifiers.shift_key(),
+            "control": modifiers.control_key(),
+            "alt": modifiers.alt_key(),
+            "super": modifiers.super_key(),
+        }),
+        WindowEvent::CursorMoved { position, .. } => json!({
+            "type": "window-cursor-moved",
+            "window_id": id,
+            "x": position.x,
+            "y": position.y,
+        }),
+        WindowEvent::CursorEntered { .. } => json!({
+            "type": "window-cursor-entered",
+            "window_id": id,
+        }),
+        WindowEvent::CursorLeft { .. } => json!({
+            "type": "window-cursor-left",
+            "window_id": id,
+        }),
+        WindowEvent::MouseInput { state, button, .. } => json!({
+            "type": "window-mouse-input",
+            "window_id": id,
+            "state": element_state_str(*state),
+            "button": mouse_button_value(*button),
+        }),
+        WindowEvent::MouseWheel { delta, .. } => {
+            let (kind, x, y) = match delta {
+                MouseScrollDelta::LineDelta(x, y) => ("line", *x as f64, *y as f64),
+                MouseScrollDelta::PixelDelta(pos) => ("pixel", pos.x, pos.y),
+                _ => return None,
+            };
+            json!({
+                "type": "window-mouse-wheel",
+                "window_id": id,
+                "delta_kind": kind,
+                "x": x,
+                "y": y,
+            })
+        }
+        WindowEvent::DroppedFile(path) => json!({
+            "type": "window-dropped-file",
+            "window_id": id,
+            "path": path.to_string_lossy(),
+        }),
+        WindowEvent::HoveredFile(path) => json!({
+            "type": "window-hovered-file",
+            "window_id": id,
+            "path": path.to_string_lossy(),
+        }),
+        WindowEvent::HoveredFileCancelled => json!({
+            "type": "window-hovered-file-cancelled",
+            "window_id": id,
+        }),
+        WindowEvent::ReceivedImeText(text) => json!({
+            "type": "window-ime-text",
+            "window_id": id,
+            "text": text,
+        }),
+        WindowEvent::Touch(touch) => json!({
+            "type": "window-touch",
+            "window_id": id,
+            "touches": [{
+                "id": touch.id,
+                "x": touch.location.x,
+                "y": touch.location.y,
+                "phase": touch_phase_str(touch.phase),
+            }],
+        }),
+        WindowEvent::ScaleFactorChanged { scale_factor, .. } => json!({
+            "type": "window-scale-factor-changed",
+            "window_id": id,
+            "scale_factor": scale_factor,
+        }),
+        WindowEvent::ThemeChanged(theme) => json!({
+            "type": "window-theme-changed",
+            "window_id": id,
+            "theme": if *theme == Theme::Dark { "dark" } else { "light" },
+        }),
+        _ => return None,
+    };
+
+    Some(value.to_string())
+}
+
+fn element_state_str(state: ElementState) -> &'static str {
+    match state {
+        ElementState::Pressed => "Pressed",
+        _ => "Released",
+    }
+}
+
+fn touch_phase_str(phase: TouchPhase) -> &'static str {
+    match phase {
+        TouchPhase::Started => "started",
+        TouchPhase::Moved => "moved",
+        TouchPhase::Ended => "ended",
+        _ => "cancelled",
+    }
+}
+
+fn mouse_button_value(button: MouseButton) -> Value {
+    match button {
+        MouseButton::Left => json!("left"),
+        MouseButton::Right => json!("right"),
+        MouseButton::Middle => json!("middle"),
+        MouseButton::Other(n) => json!(n),
+        _ => json!("unknown"),
+    }
+}
+
+thread_local! {
+    static KEY_NAME: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Turn a `physical_key` string from a keyboard event into a display name
+///
+/// Accepts the raw string or the same string as a JSON literal. Letters and
+/// digits lose their `Key`/`Digit` prefix, so `"KeyA"` becomes `"A"`, and
+/// `"Enter"` becomes `"Return"`. Names are English; there is no localization.
+///
+/// Returns null for null or invalid input. The string is valid until the
+/// next call on the same thread and must not be freed.
+///
+/// # Safety
+/// `physical_key_json` must be a valid C string or null.
+#[no_mangle]
+pub unsafe extern "C" fn wry_key_event_to_string(
+    physical_key_json: *const c_char,
+) -> *const c_char {
+    let raw = match c_str_to_str(physical_key_json) {
+        Some(s) => s.trim(),
+        None => return std::ptr::null(),
+    };
+    let code = if raw.starts_with('"') {
+        match serde_json::from_str::<String>(raw) {
+            Ok(s) => s,
+            Err(_) => return std::ptr::null(),
+        }
+    } else {
+        raw.to_string()
+    };
+
+    let name = match CString::new(key_code_name(&code)) {
+        Ok(name) => name,
+        Err(_) => return std::ptr::null(),
+    };
+
+    KEY_NAME.with(|buf| {
+        let mut buf = buf.borrow_mut();
+        *buf = Some(name);
+        buf.as_ref().map_or(std::ptr::null(), |s| s.as_ptr())
+    })
+}
+
+/// Display name for a `KeyCode` debug string
+fn key_code_name(code: &str) -> String {
+    // Native codes look like `Unidentified(Gtk(65))`
+    if code.starts_with("Unidentified") {
+        return "Unidentified".to_string();
+    }
+
+    let name = match code {
+        "Enter" => "Return",
+        "NumpadEnter" => "Numpad Return",
+        "Escape" => "Esc",
+        "Backquote" => "`",
+        "Backslash" | "IntlBackslash" => "\\",
+        "BracketLeft" => "[",
+        "BracketRight" => "]",
+        "Comma" => ",",
+        "Equal" => "=",
+        "Minus" => "-",
+        "Period" => ".",
+        "Quote" => "'",
+        "Semicolon" => ";",
+        "Slash" => "/",
+        "ArrowUp" => "Up",
+        "ArrowDown" => "Down",
+        "ArrowLeft" => "Left",
+        "ArrowRight" => "Right",
+        "PageUp" => "Page Up",
+        "PageDown" => "Page Down",
+        "CapsLock" => "Caps Lock",
+        "NumLock" => "Num Lock",
+        "ScrollLock" => "Scroll Lock",
+        "PrintScreen" => "Print Screen",
+        "ContextMenu" => "Menu",
+        "SuperLeft" | "SuperRight" => "Super",
+        _ => {
+            if let Some(rest) = code.strip_prefix("Key") {
+                return rest.to_string();
+            }
+            if let Some(rest) = code.strip_prefix("Digit") {
+                return rest.to_string();
+            }
+            if let Some(rest) = code.strip_prefix("Numpad") {
+                return format!("Numpad {}", rest);
+            }
+            for side in ["Left", "Right"] {
+                if let Some(rest) = code.strip_suffix(side) {
+                    if matches!(rest, "Shift" | "Control" | "Alt") {
+                        return format!("{} {}", side, rest);
+                    }
+                }
+            }
+            code
+        }
+    };
+
+    name.to_string()
+}
+
+/// Convert an event loop event to a `WryEvent` and pass it to `f`, returning
+/// None for events the host never sees
+///
+/// Strings in the event only live until `f` returns.
+pub fn with_typed_event<R>(event: &Event<UserEvent>, f: impl FnOnce(&WryEvent) -> R) -> Option<R> {
+    let (window_id, window_event) = match event {
+        Event::WindowEvent {
+            window_id, event, ..
+        } => (*window_id, event),
+        _ => {
+            let json = CString::new(serialize_event(event)?).ok()?;
+            return Some(f(&WryEvent {
+                kind: WryEventKind::Other,
+                window_id: std::ptr::null(),
+                json: json.as_ptr(),
+                data: WryEventData { none: 0 },
+            }));
+        }
+    };
+
+    let id = CString::new(window_id_string(window_id)).ok()?;
+    // Keeps key strings alive for the call
+    let mut strings: Vec<CString> = Vec::new();
+    let mut json = None;
+
+    let (kind, data) = match window_event {
+        WindowEvent::CloseRequested => {
+            (WryEventKind::WindowCloseRequested, WryEventData { none: 0 })
+        }
+        WindowEvent::Destroyed => (WryEventKind::WindowDestroyed, WryEventData { none: 0 }),
+        WindowEvent::Resized(size) => (
+            WryEventKind::WindowResized,
+            WryEventData {
+                size: WrySize {
+                    width: size.width,
+                    height: size.height,
+                },
+            },
+        ),
+        WindowEvent::Moved(position) => (
+            WryEventKind::WindowMoved,
+            WryEventData {
+                position: WryPosition {
+                    x: position.x,
+                    y: position.y,
+                },
+            },
+        ),
+        WindowEvent::Focused(focused) => (
+            WryEventKind::WindowFocused,
+            WryEventData { focused: *focused },
+        ),
+        WindowEvent::KeyboardInput { event, .. } => {
+            let mut c_string = |s: String| {
+                let c = CString::new(s).unwrap_or_default();
+                let ptr = c.as_ptr();
+                strings.push(c);
+                ptr
+            };
+            let key = WryKeyEvent {
+                pressed: event.state == ElementState::Pressed,
+                repeat: event.repeat,
+                physical_key: c_string(format!("{:?}", event.physical_key)),
+                logical_key: c_string(format!("{:?}", event.logical_key)),
+                text: match event.text {
+                    Some(text) => c_string(text.to_string()),
+                    None => std::ptr::null(),
+                },
+            };
+            (WryEventKind::KeyboardInput, WryEventData { key })
+        }
+        WindowEvent::ModifiersChanged(modifiers) => (
+            WryEventKind::ModifiersChanged,
+            WryEventData {
+                modifiers: WryModifiers {
+                    shift: modifiers.shift_key(),
+                    control: modifiers.control_key(),
+                    alt: modifiers.alt_key(),
+                    super_key: modifiers.super_key(),
+                },
+            },
+        ),
+        WindowEvent::CursorMoved { position, .. } => (
+            WryEventKind::CursorMoved,
+            WryEventData {
+                cursor: WryPoint {
+                    x: position.x,
+                    y: position.y,
+                },
+            },
+        ),
+        WindowEvent::CursorEntered { .. } => {
+            (WryEventKind::CursorEntered, WryEventData { none: 0 })
+        }
+        WindowEvent::CursorLeft { .. } => (WryEventKind::CursorLeft, WryEventData { none: 0 }),
+        WindowEvent::MouseInput { state, button, .. } => {
+            let (button, other_button) = match button {
+                MouseButton::Left => (WryMouseButton::Left, 0),
+                MouseButton::Right => (WryMouseButton::Right, 0),
+                MouseButton::Middle => (WryMouseButton::Middle, 0),
+                MouseButton::Other(n) => (WryMouseButton::Other, *n),
+                _ => (WryMouseButton::Other, 0),
+            };
+            let mouse = WryMouseEvent {
+                pressed: *state == ElementState::Pressed,
+                button,
+                other_button,
+            };
+            (WryEventKind::MouseInput, WryEventData { mouse })
+        }
+        WindowEvent::MouseWheel { delta, .. } => {
+            let wheel = match delta {
+                MouseScrollDelta::LineDelta(x, y) => WryWheelEvent {
+                    is_line_delta: true,
+                    x: *x as f64,
+                    y: *y as f64,
+                },
+                MouseScrollDelta::PixelDelta(pos) => WryWheelEvent {
+                    is_line_delta: false,
+                    x: pos.x,
+                    y: pos.y,
+                },
+                _ => return None,
+            };
+            (WryEventKind::MouseWheel, WryEventData { wheel })
+        }
+        WindowEvent::ScaleFactorChanged { scale_factor, .. } => (
+            WryEventKind::ScaleFactorChanged,
+            WryEventData {
+                scale_factor: *scale_factor,
+            },
+        ),
+        WindowEvent::ThemeChanged(theme) => (
+            WryEventKind::ThemeChanged,
+            WryEventData {
+                is_dark: *theme == Theme::Dark,
+            },
+        ),
+        other => {
+            json = Some(CString::new(serialize_window_event(window_id, other)?).ok()?);
+            (WryEventKind::Other, WryEventData { none: 0 })
+        }
+    };
+
+    Some(f(&WryEvent {
+        kind,
+        window_id: id.as_ptr(),
+        json: json.as_ref().map_or(std::ptr::null(), |j| j.as_ptr()),
+        data,
+    }))
+}
@@ -0,0 +1,483 @@
+//! Window icons
+//!
+//! Sets window icons from RGBA pixel data, image files or bundle resources.
+//! macOS windows have no icon of their own, so there the Dock icon is set.
+
+use std::ffi::c_char;
+
+use tao::window::Icon;
+
+use crate::error::set_last_error;
+use crate::string::c_str_to_string;
+use crate::types::WryWindow;
+use crate::window::{get_window_state, WindowState};
+
+/// Set or clear the icon shown for a window
+fn apply_icon(state: &WindowState, icon: Option<Icon>) {
+    #[cfg(target_os = "windows")]
+    {
+        // set_window_icon only sets ICON_SMALL, the taskbar uses ICON_BIG
+        use tao::platform::windows::WindowExtWindows;
+        state.window.set_taskbar_icon(icon.clone());
+    }
+
+    state.window.set_window_icon(icon);
+}
+
+/// Validate pixel data and build a tao icon from it
+fn icon_from_rgba(rgba: Vec<u8>, width: u32, height: u32) -> Option<Icon> {
+    match Icon::from_rgba(rgba, width, height) {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            set_last_error(format!("Invalid icon data: {}", e));
+            None
+        }
+    }
+}
+
+// ============================================================================
+// Platform Images
+// ============================================================================
+
+/// Build an NSImage from straight (non-premultiplied) RGBA pixels
+#[cfg(target_os = "macos")]
+pub fn ns_image_from_rgba(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+) -> Option<objc2::rc::Retained<objc2::runtime::AnyObject>> {
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::{NSSize, NSString};
+
+    let color_space = NSString::from_str("NSDeviceRGBColorSpace");
+
+    unsafe {
+        let rep: Allocated<AnyObject> = msg_send![class!(NSBitmapImageRep), alloc];
+        let rep: Option<Retained<AnyObject>> = msg_send![
+            rep,
+            initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>(),
+            pixelsWide: width as isize,
+            pixelsHigh: height as isize,
+            bitsPerSample: 8isize,
+            samplesPerPixel: 4isize,
+            hasAlpha: true,
+            isPlanar: false,
+            colorSpaceName: &*color_space,
+            bytesPerRow: (width * 4) as isize,
+            bitsPerPixel: 32isize
+        ];
+        let rep = rep?;
+
+        // NSBitmapImageRep expects premultiplied alpha
+        let data: *mut u8 = msg_send![&*rep, bitmapData];
+        if data.is_null() {
+            return None;
+        }
+        let pixels = std::slice::from_raw_parts_mut(data, rgba.len());
+        for (dst, src) in pixels.chunks_exact_mut(4).zip(rgba.chunks_exact(4)) {
+            let alpha = src[3] as u16;
+            dst[0] = (src[0] as u16 * alpha / 255) as u8;
+            dst[1] = (src[1] as u16 * alpha / 255) as u8;
+            dst[2] = (src[2] as u16 * alpha / 255) as u8;
+            dst[3] = src[3];
+        }
+
+        let size = NSSize::new(width as f64, height as f64);
+        let image: Allocated<AnyObject> = msg_send![class!(NSImage), alloc];
+        let image: Option<Retained<AnyObject>> = msg_send![image, initWithSize: size];
+        let image = image?;
+        let _: () = msg_send![&*image, addRepresentation: &*rep];
+        Some(image)
+    }
+}
+
+/// Build an HICON from straight RGBA pixels
+///
+/// With a hotspot the result is a cursor. The caller owns the handle and
+/// must release it with DestroyIcon or DestroyCursor. Returns None for an
+/// empty image or a buffer that is not width x height x 4 bytes.
+#[cfg(target_os = "windows")]
+pub fn hicon_from_rgba(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    hotspot: Option<(u32, u32)>,
+) -> Option<windows::Win32::UI::WindowsAndMessaging::HICON> {
+    use windows::Win32::Graphics::Gdi::{CreateBitmap, DeleteObject};
+    use windows::Win32::UI::WindowsAndMessaging::{CreateIconIndirect, ICONINFO};
+
+    let expected = (width as usize).checked_mul(height as usize).and_then(|n| n.checked_mul(4));
+    if width == 0 || height == 0 || expected != Some(rgba.len()) {
+        return None;
+    }
+
+    // 32bpp color bitmaps are BGRA; the alpha channel replaces the mask
+    let bgra: Vec<u8> = rgba
+        .chunks_exact(4)
+        .flat_map(|px| [px[2], px[1], px[0], px[3]])
+        .collect();
+
+    // Monochrome rows are padded to 16 bits
+    let mask_stride = width.div_ceil(16) as usize * 2;
+    let mask = vec![0u8; mask_stride * height as usize];
+
+    let (hotspot_x, hotspot_y) = hotspot.unwrap_or((0, 0));
+
+    unsafe {
+        let color = CreateBitmap(
+            width as i32,
+            height as i32,
+            1,
+            32,
+            Some(bgra.as_ptr() as *const _),
+        );
+        let mask = CreateBitmap(
+            width as i32,
+            height as i32,
+            1,
+            1,
+            Some(mask.as_ptr() as *const _),
+        );
+
+        let info = ICONINFO {
+            fIcon: hotspot.is_none().into(),
+            xHotspot: hotspot_x,
+            yHotspot: hotspot_y,
+            hbmMask: mask,
+            hbmColor: color,
+        };
+        let icon = CreateIconIndirect(&info);
+
+        // The icon keeps its own copies of the bitmaps
+        let _ = DeleteObject(color.into());
+        let _ = DeleteObject(mask.into());
+
+        icon.ok()
+    }
+}
+
+/// Set the Dock icon, or restore the bundle icon with None
+#[cfg(target_os = "macos")]
+fn set_dock_icon(image: Option<&objc2::runtime::AnyObject>) {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+
+    unsafe {
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let _: () = msg_send![app, setApplicationIconImage: image];
+    }
+}
+
+/// Decode an image file into RGBA pixels (width, height, pixels)
+#[cfg(target_os = "linux")]
+fn decode_image_file(path: &str) -> Result<(u32, u32, Vec<u8>), String> {
+    use gtk::gdk_pixbuf::Pixbuf;
+
+    let pixbuf = Pixbuf::from_file(path).map_err(|e| format!("Failed to load {}: {}", path, e))?;
+    let pixbuf = pixbuf
+        .add_alpha(false, 0, 0, 0)
+        .map_err(|e| format!("Failed to convert {}: {}", path, e))?;
+
+    let width = pixbuf.width() as usize;
+    let height = pixbuf.height() as usize;
+    let stride = pixbuf.rowstride() as usize;
+    let bytes = pixbuf.read_pixel_bytes();
+
+    // Rows may be padded beyond width * 4
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in 0..height {
+        let start = row * stride;
+        rgba.extend_from_slice(&bytes[start..start + width * 4]);
+    }
+
+    Ok((width as u32, height as u32, rgba))
+}
+
+/// Decode an image file into RGBA pixels (width, height, pixels)
+///
+/// Uses WIC, so PNG, ICO, BMP and JPEG are supported. For multi-size ICO
+/// files the largest image is used.
+#[cfg(target_os = "windows")]
+fn decode_image_file(path: &str) -> Result<(u32, u32, Vec<u8>), String> {
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::GENERIC_READ;
+    use windows::Win32::Graphics::Imaging::WICDecodeMetadataCacheOnDemand;
+
+    let fail = |e: windows::core::Error| format!("Failed to load {}: {}", path, e);
+
+    unsafe {
+        let decoder = wic_factory()
+            .and_then(|factory| {
+                factory.CreateDecoderFromFilename(
+                    &HSTRING::from(path),
+                    None,
+                    GENERIC_READ,
+                    WICDecodeMetadataCacheOnDemand,
+                )
+            })
+            .map_err(fail)?;
+        decode_largest_frame(&decoder)
+            .map_err(fail)?
+            .ok_or_else(|| format!("No images in {}", path))
+    }
+}
+
+/// Decode an encoded image held in a stream into RGBA pixels (width, height, pixels)
+#[cfg(target_os = "windows")]
+pub fn decode_image_stream(
+    stream: &windows::Win32::System::Com::IStream,
+) -> Result<(u32, u32, Vec<u8>), String> {
+    use windows::Win32::Graphics::Imaging::WICDecodeMetadataCacheOnDemand;
+
+    let fail = |e: windows::core::Error| format!("Failed to decode image: {}", e);
+
+    unsafe {
+        let decoder = wic_factory()
+            .and_then(|factory| {
+                factory.CreateDecoderFromStream(
+                    stream,
+                    std::ptr::null(),
+                    WICDecodeMetadataCacheOnDemand,
+                )
+            })
+            .map_err(fail)?;
+        decode_largest_frame(&decoder)
+            .map_err(fail)?
+            .ok_or_else(|| "Image has no frames".to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+unsafe fn wic_factory(
+) -> windows::core::Result<windows::Win32::Graphics::Imaging::IWICImagingFactory> {
+    use windows::Win32::Graphics::Imaging::CLSID_WICImagingFactory;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+
+    CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)
+}
+
+/// Convert the largest frame of a decoded image to RGBA, None if it has no frames
+#[cfg(target_os = "windows")]
+unsafe fn decode_largest_frame(
+    decoder: &windows::Win32::Graphics::Imaging::IWICBitmapDecoder,
+) -> windows::core::Result<Option<(u32, u32, Vec<u8>)>> {
+    use windows::Win32::Graphics::Imaging::{GUID_WICPixelFormat32bppRGBA, WICConvertBitmapSource};
+
+    let mut best = None;
+    let mut best_area = 0u64;
+    for index in 0..decoder.GetFrameCount()? {
+        let frame = decoder.GetFrame(index)?;
+        let (mut width, mut height) = (0, 0);
+        frame.GetSize(&mut width, &mut height)?;
+        let area = width as u64 * height as u64;
+        if area > best_area {
+            best_area = area;
+            best = Some(frame);
+        }
+    }
+    let Some(frame) = best else {
+        return Ok(None);
+    };
+
+    let converted = WICConvertBitmapSource(&GUID_WICPixelFormat32bppRGBA, &frame)?;
+    let (mut width, mut height) = (0, 0);
+    converted.GetSize(&mut width, &mut height)?;
+
+    let mut rgba = vec![0u8; width as usize * height as usize * 4];
+    converted.CopyPixels(std::ptr::null(), width * 4, &mut rgba)?;
+
+    Ok(Some((width, height, rgba)))
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// Set the window icon from RGBA pixel data
+///
+/// `rgba` must hold exactly `width * height * 4` bytes, row by row from the
+/// top left. On macOS this sets the Dock icon.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_icon_rgba(
+    window: WryWindow,
+    rgba: *const u8,
+    len: usize,
+    width: u32,
+    height: u32,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if rgba.is_null() {
+        set_last_error("Null icon pixel data");
+        return false;
+    }
+
+    let rgba = std::slice::from_raw_parts(rgba, len);
+    let icon = match icon_from_rgba(rgba.to_vec(), width, height) {
+        Some(i) => i,
+        None => return false,
+    };
+
+    #[cfg(target_os = "macos")]
+    {
+        let image = match ns_image_from_rgba(rgba, width, height) {
+            Some(i) => i,
+            None => {
+                set_last_error("Failed to create icon image");
+                return false;
+            }
+        };
+        set_dock_icon(Some(&image));
+    }
+
+    log::debug!("Setting window icon: {}x{}", width, height);
+    apply_icon(state, Some(icon));
+    true
+}
+
+/// Set the window icon from an image file
+///
+/// Supports PNG and ICO on every platform, plus any other format the
+/// platform decoder understands (ICNS on macOS, BMP and JPEG on Windows and
+/// Linux). On macOS this sets the Dock icon.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_icon_file(window: WryWindow, path: *const c_char) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let path = match c_str_to_string(path) {
+        Some(p) => p,
+        None => {
+            set_last_error("Null or invalid path");
+            return false;
+        }
+    };
+
+    log::debug!("Setting window icon from file: {}", path);
+
+    #[cfg(target_os = "macos")]
+    {
+        use objc2::rc::{Allocated, Retained};
+        use objc2::runtime::AnyObject;
+        use objc2::{class, msg_send};
+        use objc2_foundation::NSString;
+
+        let _ = state;
+        let ns_path = NSString::from_str(&path);
+        let image: Option<Retained<AnyObject>> = {
+            let image: Allocated<AnyObject> = msg_send![class!(NSImage), alloc];
+            msg_send![image, initWithContentsOfFile: &*ns_path]
+        };
+
+        match image {
+            Some(image) => {
+                set_dock_icon(Some(&image));
+                true
+            }
+            None => {
+                set_last_error(format!("Failed to load {}", path));
+                false
+            }
+        }
+    }
+
+    #[cfg(any(target_os = "windows", target_os = "linux"))]
+    {
+        let (width, height, rgba) = match decode_image_file(&path) {
+            Ok(image) => image,
+            Err(e) => {
+                set_last_error(e);
+                return false;
+            }
+        };
+
+        match icon_from_rgba(rgba, width, height) {
+            Some(icon) => {
+                apply_icon(state, Some(icon));
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        let _ = state;
+        set_last_error("Icon files are not supported on this platform");
+        false
+    }
+}
+
+/// Remove a custom window icon, restoring the default
+///
+/// On macOS this restores the Dock icon from the app bundle.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_clear_icon(window: WryWindow) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    #[cfg(target_os = "macos")]
+    set_dock_icon(None);
+
+    log::debug!("Clearing window icon");
+    apply_icon(state, None);
+    true
+}
+
+/// Set the icon from an image resource in the app bundle
+///
+/// On macOS `bundle_resource_name` is looked up with `NSImage imageNamed:`,
+/// e.g. "AppIcon" for `AppIcon.icns` in the bundle's Resources directory,
+/// and set as the Dock icon. Elsewhere it is treated as a file path and
+/// passed to `wry_window_set_icon_file`.
+///
+/// # Returns
+/// false if the resource is not found
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_icon_from_bundle(
+    window: WryWindow,
+    bundle_resource_name: *const c_char,
+) -> bool {
+    #[cfg(not(target_os = "macos"))]
+    return wry_window_set_icon_file(window, bundle_resource_name);
+
+    #[cfg(target_os = "macos")]
+    {
+        use objc2::runtime::AnyObject;
+        use objc2::{class, msg_send};
+        use objc2_foundation::NSString;
+
+        if get_window_state(window).is_none() {
+            set_last_error("Invalid window handle");
+            return false;
+        }
+
+        let name = match c_str_to_string(bundle_resource_name) {
+            Some(n) => n,
+            None => {
+                set_last_error("Null or invalid resource name");
+                return false;
+            }
+        };
+
+        let ns_name = NSString::from_str(&name);
+        let image: *mut AnyObject = msg_send![class!(NSImage), imageNamed: &*ns_name];
+        if image.is_null() {
+            set_last_error(format!("Bundle resource not found: {}", name));
+            return false;
+        }
+
+        set_dock_icon(Some(&*image));
+        log::debug!("Set icon from bundle resource: {}", name);
+        true
+    }
+}
@@ -21,40 +21,112 @@
 
 #![allow(clippy::missing_safety_doc)]
 
+pub mod accessibility;
 pub mod app;
+pub mod appearance;
 pub mod bridge;
 pub mod callbacks;
+pub mod cursor;
+pub mod deep_link;
 pub mod dispatch;
+pub mod drag;
 pub mod error;
+pub mod events;
+pub mod icon;
+pub mod monitor;
+pub mod process;
 pub mod protocol;
+pub mod single_instance;
 pub mod string;
+pub mod taskbar;
 pub mod types;
 pub mod webview;
 pub mod window;
 pub mod window_ops;
 
 // Re-export public FFI functions
+pub use accessibility::{
+    wry_accessibility_announce, wry_window_set_accessible_description,
+    wry_window_set_accessible_label,
+};
 pub use app::{
-    wry_app_create, wry_app_destroy, wry_app_quit, wry_app_run, wry_get_last_error, wry_version,
+    wry_app_create, wry_app_destroy, wry_app_enable_remote_debugging, wry_app_is_launch_at_startup,
+    wry_app_launch_at_startup, wry_app_quit, wry_app_remove_from_startup, wry_app_run,
+    wry_app_set_event_callback, wry_app_set_terminate_after_last_window_close,
+    wry_event_loop_pump_timeout, wry_event_loop_pump_timeout_typed,
+    wry_event_loop_set_control_flow_default, wry_event_loop_set_poll_interval_ms,
+    wry_ffi_abi_check_compatible, wry_ffi_abi_version, wry_ffi_feature_supported,
+    wry_get_last_error, wry_thread_check_is_main, wry_version, wry_version_info,
+    wry_webview_block_until_loaded,
 };
+pub use appearance::{wry_app_is_dark_mode, wry_app_is_high_contrast, wry_app_is_reduced_motion};
 pub use callbacks::{
-    wry_window_set_closing_callback, wry_window_set_focus_callback,
-    wry_window_set_message_callback, wry_window_set_moved_callback,
-    wry_window_set_navigation_callback, wry_window_set_resized_callback,
+    wry_window_set_appearance_callback, wry_window_set_closing_callback,
+    wry_window_set_event_callback, wry_window_set_focus_callback, wry_window_set_message_callback,
+    wry_window_set_moved_callback, wry_window_set_navigation_callback,
+    wry_window_set_resized_callback,
+};
+pub use cursor::wry_window_set_cursor_icon_rgba;
+pub use deep_link::{wry_app_register_url_scheme, wry_app_set_url_handler};
+pub use dispatch::{
+    wry_dispatch_after, wry_event_loop_create_proxy, wry_event_loop_post_event,
+    wry_event_loop_proxy_destroy, wry_event_loop_proxy_is_valid, wry_event_loop_wake, wry_invoke,
+    wry_invoke_sync,
+};
+pub use drag::wry_drag_item;
+pub use events::wry_key_event_to_string;
+pub use icon::{
+    wry_window_clear_icon, wry_window_set_icon_file, wry_window_set_icon_from_bundle,
+    wry_window_set_icon_rgba,
+};
+pub use monitor::{
+    wry_app_screen_scale_factor, wry_monitor_list, wry_monitor_primary,
+    wry_window_get_monitor_work_area,
+};
+pub use process::{wry_app_cpu_usage, wry_app_memory_usage};
+pub use protocol::{
+    wry_custom_protocol_unregister, wry_protocol_list, wry_register_protocol,
+    wry_register_protocol_with_cache, wry_webview_set_default_headers, wry_webview_set_scheme_cors,
 };
-pub use dispatch::{wry_invoke, wry_invoke_sync};
-pub use protocol::wry_register_protocol;
+pub use single_instance::wry_app_single_instance;
 pub use string::wry_string_free;
+pub use taskbar::{wry_jump_list_clear, wry_jump_list_set, wry_thumbnail_toolbar_set_buttons};
 pub use types::*;
 pub use webview::{
-    wry_webview_close_devtools, wry_webview_evaluate_script, wry_webview_get_url,
+    wry_hotkey_match_event, wry_webview_add_init_script, wry_webview_add_preload_module,
+    wry_webview_call_async_javascript, wry_webview_clear_local_storage, wry_webview_close_devtools,
+    wry_webview_disable_zoom, wry_webview_evaluate_script, wry_webview_evaluate_script_in_frame,
+    wry_webview_execute_command, wry_webview_focus, wry_webview_get_cookies,
+    wry_webview_get_loading_state, wry_webview_get_local_storage_item, wry_webview_get_pdf,
+    wry_webview_get_snapshot, wry_webview_get_url, wry_webview_handle_drop, wry_webview_hide,
+    wry_webview_intercept_websocket, wry_webview_is_focused, wry_webview_is_visible,
     wry_webview_load_html, wry_webview_navigate, wry_webview_open_devtools,
-    wry_webview_send_message, wry_webview_set_zoom,
+    wry_webview_register_hotkey, wry_webview_remove_init_script, wry_webview_send_message,
+    wry_webview_set_allow_link_opening, wry_webview_set_allow_text_selection,
+    wry_webview_set_cookie, wry_webview_set_cursor_blink_rate,
+    wry_webview_set_default_context_menu_enabled, wry_webview_set_local_storage_item,
+    wry_webview_set_media_permissions, wry_webview_set_ssl_error_handler,
+    wry_webview_set_take_focus_on_click, wry_webview_set_zoom, wry_webview_show,
+    wry_webview_unregister_hotkey, wry_webview_websocket_close, wry_webview_websocket_send,
 };
+#[cfg(any(debug_assertions, feature = "cors-bypass"))]
+pub use webview::wry_webview_set_cors_bypass;
 pub use window::{wry_window_create, wry_window_destroy};
 pub use window_ops::{
-    wry_window_close, wry_window_focus, wry_window_get_position, wry_window_get_size,
-    wry_window_get_title, wry_window_is_visible, wry_window_maximize, wry_window_minimize,
-    wry_window_set_fullscreen, wry_window_set_position, wry_window_set_size,
-    wry_window_set_title, wry_window_set_visible, wry_window_unmaximize,
+    wry_window_begin_sheet, wry_window_bring_to_front, wry_window_close,
+    wry_window_convert_point_from_screen, wry_window_convert_point_to_screen, wry_window_drag_begin,
+    wry_window_end_sheet, wry_window_focus, wry_window_get_position, wry_window_get_size,
+    wry_window_get_theme, wry_window_get_title, wry_window_is_always_on_bottom,
+    wry_window_is_content_protected, wry_window_is_focusable, wry_window_is_visible,
+    wry_window_maximize, wry_window_minimize, wry_window_resize_begin, wry_window_restore,
+    wry_window_select_tab_at_index, wry_window_set_always_on_bottom, wry_window_set_content_insets,
+    wry_window_set_content_protected, wry_window_set_cursor_icon, wry_window_set_drag_accept_types,
+    wry_window_set_focusable, wry_window_set_fullscreen, wry_window_set_ime_allowed,
+    wry_window_set_ime_position, wry_window_set_keyboard_input_redirect,
+    wry_window_set_pinch_zoom_enabled, wry_window_set_position,
+    wry_window_set_rotate_gesture_enabled, wry_window_set_shadow_color, wry_window_set_size,
+    wry_window_set_tab_group_identifier, wry_window_set_theme, wry_window_set_title,
+    wry_window_set_titlebar_style, wry_window_set_touch_enabled, wry_window_set_visible,
+    wry_window_set_window_buttons_visibility, wry_window_snapshot, wry_window_toggle_maximize,
+    wry_window_unmaximize,
 };
@@ -0,0 +1,334 @@
+//! Monitor queries
+//!
+//! Enumerates connected monitors as JSON, with or without a window.
+
+use std::ffi::c_char;
+
+use serde_json::{json, Value};
+use tao::monitor::MonitorHandle;
+
+use crate::app::AppState;
+use crate::error::set_last_error;
+use crate::string::string_to_c_string;
+use crate::types::{WryApp, WryRect, WryWindow};
+use crate::window::get_window_state;
+
+/// Describe a monitor as JSON
+pub fn monitor_to_json(monitor: &MonitorHandle) -> Value {
+    let position = monitor.position();
+    let size = monitor.size();
+
+    let mut value = json!({
+        "name": monitor.name(),
+        "scale_factor": monitor.scale_factor(),
+        "position": { "x": position.x, "y": position.y },
+        "size": { "width": size.width, "height": size.height },
+    });
+
+    let work_area = monitor_work_area(monitor);
+    value["work_area"] = json!({
+        "x": work_area.x,
+        "y": work_area.y,
+        "width": work_area.width,
+        "height": work_area.height,
+    });
+
+    // Omitted rather than null when the platform does not report a rate
+    if let Some(refresh_rate) = refresh_rate_millihertz(monitor) {
+        value["refresh_rate_mhz"] = json!(refresh_rate);
+    }
+
+    value
+}
+
+/// Refresh rate in millihertz of the monitor's active display mode
+#[cfg(target_os = "windows")]
+fn refresh_rate_millihertz(monitor: &MonitorHandle) -> Option<u32> {
+    use tao::platform::windows::MonitorHandleExtWindows;
+    use windows::core::PCWSTR;
+    use windows::Win32::Graphics::Gdi::{
+        EnumDisplaySettingsW, GetMonitorInfoW, DEVMODEW, ENUM_CURRENT_SETTINGS, HMONITOR,
+        MONITORINFOEXW,
+    };
+
+    let mut info = MONITORINFOEXW::default();
+    info.monitorInfo.cbSize = std::mem::size_of::<MONITORINFOEXW>() as u32;
+    let hmonitor = HMONITOR(monitor.hmonitor() as *mut _);
+    if !unsafe { GetMonitorInfoW(hmonitor, &mut info.monitorInfo) }.as_bool() {
+        return None;
+    }
+
+    let mut mode = DEVMODEW {
+        dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+        ..Default::default()
+    };
+    let device = PCWSTR(info.szDevice.as_ptr());
+    if !unsafe { EnumDisplaySettingsW(device, ENUM_CURRENT_SETTINGS, &mut mode) }.as_bool() {
+        return None;
+    }
+
+    // 0 and 1 both mean the hardware default rate
+    match mode.dmDisplayFrequency {
+        0 | 1 => None,
+        hz => Some(hz * 1000),
+    }
+}
+
+/// Refresh rate in millihertz of the monitor's active display mode
+#[cfg(target_os = "macos")]
+fn refresh_rate_millihertz(monitor: &MonitorHandle) -> Option<u32> {
+    use std::ffi::c_void;
+    use tao::platform::macos::MonitorHandleExtMacOS;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGDisplayCopyDisplayMode(display: u32) -> *mut c_void;
+        fn CGDisplayModeGetRefreshRate(mode: *mut c_void) -> f64;
+        fn CGDisplayModeRelease(mode: *mut c_void);
+    }
+
+    let hz = unsafe {
+        let mode = CGDisplayCopyDisplayMode(monitor.native_id());
+        if mode.is_null() {
+            return None;
+        }
+        let hz = CGDisplayModeGetRefreshRate(mode);
+        CGDisplayModeRelease(mode);
+        hz
+    };
+
+    // Built-in panels report 0 for their fixed rate
+    (hz > 0.0).then(|| (hz * 1000.0).round() as u32)
+}
+
+/// Refresh rate in millihertz of the monitor's active display mode
+#[cfg(target_os = "linux")]
+fn refresh_rate_millihertz(monitor: &MonitorHandle) -> Option<u32> {
+    use gtk::prelude::MonitorExt;
+    use tao::platform::unix::MonitorHandleExtUnix;
+
+    // GDK already reports millihertz
+    let rate = monitor.gdk_monitor().refresh_rate();
+    (rate > 0).then_some(rate as u32)
+}
+
+/// Refresh rate in millihertz of the monitor's active display mode
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn refresh_rate_millihertz(_monitor: &MonitorHandle) -> Option<u32> {
+    None
+}
+
+/// Full monitor rectangle, used where the work area is unavailable
+#[cfg(not(target_os = "linux"))]
+fn monitor_rect(monitor: &MonitorHandle) -> WryRect {
+    let position = monitor.position();
+    let size = monitor.size();
+    WryRect {
+        x: position.x,
+        y: position.y,
+        width: size.width,
+        height: size.height,
+    }
+}
+
+/// Monitor area not covered by the taskbar, dock or menu bar
+#[cfg(target_os = "windows")]
+pub fn monitor_work_area(monitor: &MonitorHandle) -> WryRect {
+    use tao::platform::windows::MonitorHandleExtWindows;
+    use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, HMONITOR, MONITORINFO};
+
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+        ..Default::default()
+    };
+    let hmonitor = HMONITOR(monitor.hmonitor() as *mut _);
+    if !unsafe { GetMonitorInfoW(hmonitor, &mut info) }.as_bool() {
+        return monitor_rect(monitor);
+    }
+
+    let work = info.rcWork;
+    WryRect {
+        x: work.left,
+        y: work.top,
+        width: (work.right - work.left).max(0) as u32,
+        height: (work.bottom - work.top).max(0) as u32,
+    }
+}
+
+/// Monitor area not covered by the taskbar, dock or menu bar
+#[cfg(target_os = "macos")]
+pub fn monitor_work_area(monitor: &MonitorHandle) -> WryRect {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSRect;
+    use tao::platform::macos::MonitorHandleExtMacOS;
+
+    let screen = match monitor.ns_screen() {
+        Some(s) => s as *mut AnyObject,
+        None => return monitor_rect(monitor),
+    };
+
+    let (visible, primary_height) = unsafe {
+        let visible: NSRect = msg_send![screen, visibleFrame];
+        let screens: *mut AnyObject = msg_send![class!(NSScreen), screens];
+        let primary: *mut AnyObject = msg_send![screens, firstObject];
+        if primary.is_null() {
+            return monitor_rect(monitor);
+        }
+        let primary_frame: NSRect = msg_send![primary, frame];
+        (visible, primary_frame.size.height)
+    };
+
+    // Cocoa puts the origin at the bottom left of the primary screen
+    let scale = monitor.scale_factor();
+    let top = primary_height - (visible.origin.y + visible.size.height);
+    WryRect {
+        x: (visible.origin.x * scale).round() as i32,
+        y: (top * scale).round() as i32,
+        width: (visible.size.width * scale).round() as u32,
+        height: (visible.size.height * scale).round() as u32,
+    }
+}
+
+/// Monitor area not covered by the taskbar, dock or menu bar
+#[cfg(target_os = "linux")]
+pub fn monitor_work_area(monitor: &MonitorHandle) -> WryRect {
+    use gtk::prelude::MonitorExt;
+    use tao::platform::unix::MonitorHandleExtUnix;
+
+    // GDK reports application pixels
+    let work = monitor.gdk_monitor().workarea();
+    let scale = monitor.scale_factor();
+    WryRect {
+        x: (work.x() as f64 * scale).round() as i32,
+        y: (work.y() as f64 * scale).round() as i32,
+        width: (work.width().max(0) as f64 * scale).round() as u32,
+        height: (work.height().max(0) as f64 * scale).round() as u32,
+    }
+}
+
+/// Monitor area not covered by the taskbar, dock or menu bar
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub fn monitor_work_area(monitor: &MonitorHandle) -> WryRect {
+    monitor_rect(monitor)
+}
+
+/// Query monitors from the event loop, or from any window while the event
+/// loop is running and has been taken out of the app state
+fn with_monitors<T>(
+    state: &AppState,
+    query: impl FnOnce(&mut dyn Iterator<Item = MonitorHandle>, Option<MonitorHandle>) -> T,
+) -> Option<T> {
+    if let Some(event_loop) = &state.event_loop {
+        return Some(query(
+            &mut event_loop.available_monitors(),
+            event_loop.primary_monitor(),
+        ));
+    }
+
+    let window = &state.windows.values().next()?.window;
+    Some(query(&mut window.available_monitors(), window.primary_monitor()))
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// List all connected monitors as a JSON array (caller must free with wry_string_free)
+///
+/// Each entry has `name`, `scale_factor`, `position` and `size` in physical
+/// pixels, `work_area` excluding taskbars and docks, and `refresh_rate_mhz`
+/// of the active display mode where known. Works before any window is
+/// created.
+#[no_mangle]
+pub unsafe extern "C" fn wry_monitor_list(app: WryApp) -> *mut c_char {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return std::ptr::null_mut();
+    }
+
+    let state = &*(app as *const AppState);
+    let monitors = with_monitors(state, |monitors, _| {
+        monitors.map(|m| monitor_to_json(&m)).collect::<Vec<_>>()
+    });
+
+    match monitors {
+        Some(monitors) => string_to_c_string(&Value::Array(monitors).to_string()),
+        None => {
+            set_last_error("No event loop or window available");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Get the primary monitor as JSON (caller must free with wry_string_free)
+///
+/// Returns null if the platform does not report a primary monitor.
+#[no_mangle]
+pub unsafe extern "C" fn wry_monitor_primary(app: WryApp) -> *mut c_char {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return std::ptr::null_mut();
+    }
+
+    let state = &*(app as *const AppState);
+    match with_monitors(state, |_, primary| primary) {
+        Some(Some(monitor)) => string_to_c_string(&monitor_to_json(&monitor).to_string()),
+        Some(None) => {
+            set_last_error("No primary monitor");
+            std::ptr::null_mut()
+        }
+        None => {
+            set_last_error("No event loop or window available");
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Get the primary monitor's scale factor, e.g. to pick icon sizes at startup
+///
+/// Works before any window is created. Returns 1.0 if there is no primary
+/// monitor.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_screen_scale_factor(app: WryApp) -> f64 {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return 1.0;
+    }
+
+    let state = &*(app as *const AppState);
+    match with_monitors(state, |_, primary| primary) {
+        Some(Some(monitor)) => monitor.scale_factor(),
+        _ => 1.0,
+    }
+}
+
+/// Get the work area of the monitor the window is on
+///
+/// The work area excludes the taskbar, dock and menu bar, in physical pixels.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_get_monitor_work_area(
+    window: WryWindow,
+    out: *mut WryRect,
+) -> bool {
+    if out.is_null() {
+        set_last_error("Null output pointer");
+        return false;
+    }
+
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let monitor = match state.window.current_monitor() {
+        Some(m) => m,
+        None => {
+            set_last_error("Window is not on a monitor");
+            return false;
+        }
+    };
+
+    *out = monitor_work_area(&monitor);
+    true
+}
@@ -0,0 +1,425 @@
+//! Process resource usage
+//!
+//! Memory and CPU figures for the host process and the engine's helper
+//! processes.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::error::set_last_error;
+use crate::types::WryMemoryInfo;
+
+/// Fill `out` with the process's current memory usage
+///
+/// `webview_bytes` sums the resident memory of child processes, which the
+/// engine uses for rendering and networking on Linux and Windows. macOS
+/// runs them as system services outside the process tree, so it is 0 there.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_memory_usage(out: *mut WryMemoryInfo) -> bool {
+    if out.is_null() {
+        set_last_error("Null output pointer");
+        return false;
+    }
+
+    match memory_usage() {
+        Ok(info) => {
+            *out = info;
+            true
+        }
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn memory_usage() -> Result<WryMemoryInfo, String> {
+    use std::collections::{HashMap, HashSet};
+
+    /// A `kB` field of /proc/<pid>/status, in bytes
+    fn status_bytes(status: &str, field: &str) -> Option<u64> {
+        let line = status.lines().find(|l| l.starts_with(field))?;
+        let kb: u64 = line[field.len()..]
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse()
+            .ok()?;
+        Some(kb * 1024)
+    }
+
+    let status = std::fs::read_to_string("/proc/self/status")
+        .map_err(|e| format!("Failed to read /proc/self/status: {}", e))?;
+
+    // Parent (field 4) and start time (field 22) of every process, counted
+    // from the field after the `(comm)` in stat
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    let mut start_times: HashMap<u32, u64> = HashMap::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let pid: u32 = match entry.file_name().to_str().and_then(|s| s.parse().ok()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            let stat = match std::fs::read_to_string(entry.path().join("stat")) {
+                Ok(stat) => stat,
+                Err(_) => continue,
+            };
+            let fields: Vec<&str> = match stat.rsplit_once(')') {
+                Some((_, rest)) => rest.split_whitespace().collect(),
+                None => continue,
+            };
+            let ppid = fields.get(1).and_then(|s| s.parse().ok());
+            let start = fields.get(19).and_then(|s| s.parse().ok());
+            if let (Some(ppid), Some(start)) = (ppid, start) {
+                children.entry(ppid).or_default().push(pid);
+                start_times.insert(pid, start);
+            }
+        }
+    }
+
+    // A PID can be reused after its process exits, so only count children
+    // that started after the parent they claim, and each process once
+    let mut webview_bytes = 0;
+    let mut visited = HashSet::new();
+    let mut pending = vec![std::process::id()];
+    while let Some(parent) = pending.pop() {
+        let parent_start = start_times.get(&parent).copied().unwrap_or(0);
+        for &pid in children.get(&parent).into_iter().flatten() {
+            if start_times.get(&pid).is_some_and(|&start| start < parent_start)
+                || !visited.insert(pid)
+            {
+                continue;
+            }
+            if let Ok(child) = std::fs::read_to_string(format!("/proc/{}/status", pid)) {
+                webview_bytes += status_bytes(&child, "VmRSS:").unwrap_or(0);
+            }
+            pending.push(pid);
+        }
+    }
+
+    Ok(WryMemoryInfo {
+        rss_bytes: status_bytes(&status, "VmRSS:").unwrap_or(0),
+        virtual_bytes: status_bytes(&status, "VmSize:").unwrap_or(0),
+        webview_bytes,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn memory_usage() -> Result<WryMemoryInfo, String> {
+    let info: mach::TaskBasicInfo = mach::query(mach::MACH_TASK_BASIC_INFO)?;
+    Ok(WryMemoryInfo {
+        rss_bytes: info.resident_size,
+        virtual_bytes: info.virtual_size,
+        webview_bytes: 0,
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn memory_usage() -> Result<WryMemoryInfo, String> {
+    use std::collections::{HashMap, HashSet};
+
+    use windows::Win32::Foundation::{CloseHandle, FILETIME, HANDLE};
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+    use windows::Win32::System::ProcessStatus::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+    use windows::Win32::System::Threading::{
+        GetCurrentProcess, GetCurrentProcessId, GetProcessTimes, OpenProcess,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    fn working_set(process: HANDLE) -> Option<u64> {
+        let mut counters = PROCESS_MEMORY_COUNTERS::default();
+        let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+        unsafe { GetProcessMemoryInfo(process, &mut counters, size) }.ok()?;
+        Some(counters.WorkingSetSize as u64)
+    }
+
+    /// Creation time in 100 ns intervals
+    fn created(process: HANDLE) -> Option<u64> {
+        let [mut creation, mut exit, mut kernel, mut user] = [FILETIME::default(); 4];
+        unsafe { GetProcessTimes(process, &mut creation, &mut exit, &mut kernel, &mut user) }
+            .ok()?;
+        Some((creation.dwHighDateTime as u64) << 32 | creation.dwLowDateTime as u64)
+    }
+
+    let rss_bytes = working_set(unsafe { GetCurrentProcess() })
+        .ok_or_else(|| "GetProcessMemoryInfo failed".to_string())?;
+
+    // Address space in use is the total minus what is still available
+    let mut status = MEMORYSTATUSEX {
+        dwLength: std::mem::size_of::<MEMORYSTATUSEX>() as u32,
+        ..Default::default()
+    };
+    let virtual_bytes = match unsafe { GlobalMemoryStatusEx(&mut status) } {
+        Ok(()) => status.ullTotalVirtual - status.ullAvailVirtual,
+        Err(_) => 0,
+    };
+
+    // WebView2 processes descend from this one
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    if let Ok(snapshot) = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) } {
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut more = unsafe { Process32FirstW(snapshot, &mut entry) }.is_ok();
+        while more {
+            children
+                .entry(entry.th32ParentProcessID)
+                .or_default()
+                .push(entry.th32ProcessID);
+            more = unsafe { Process32NextW(snapshot, &mut entry) }.is_ok();
+        }
+        let _ = unsafe { CloseHandle(snapshot) };
+    }
+
+    // The snapshot keeps the parent PID of exited processes, which may have
+    // been reused, so only count children created after the parent they
+    // claim, and each process once
+    let mut webview_bytes = 0;
+    let mut visited = HashSet::new();
+    let root = unsafe { GetCurrentProcessId() };
+    let root_created = created(unsafe { GetCurrentProcess() }).unwrap_or(0);
+    let mut pending = vec![(root, root_created)];
+    while let Some((parent, parent_created)) = pending.pop() {
+        for &pid in children.get(&parent).into_iter().flatten() {
+            if pid == 0 || !visited.insert(pid) {
+                continue;
+            }
+            let process =
+                match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+                    Ok(p) => p,
+                    Err(_) => continue,
+                };
+            match created(process) {
+                Some(child_created) if child_created >= parent_created => {
+                    webview_bytes += working_set(process).unwrap_or(0);
+                    pending.push((pid, child_created));
+                }
+                _ => {}
+            }
+            let _ = unsafe { CloseHandle(process) };
+        }
+    }
+
+    Ok(WryMemoryInfo {
+        rss_bytes,
+        virtual_bytes,
+        webview_bytes,
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn memory_usage() -> Result<WryMemoryInfo, String> {
+    Err("Not supported on this platform".to_string())
+}
+
+/// Minimum time between CPU samples
+const CPU_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Last CPU sample and the usage computed from it
+struct CpuSample {
+    taken: Instant,
+    /// CPU time used by this process, in seconds
+    process: f64,
+    /// CPU time available on all cores, in seconds, from an arbitrary start
+    capacity: f64,
+    usage: f64,
+}
+
+static CPU_SAMPLE: Mutex<Option<CpuSample>> = Mutex::new(None);
+
+/// Fraction of the machine's CPU time used by this process, 0.0 to 1.0
+///
+/// Averaged since the previous sample. Samples are taken at most every
+/// 250 ms; calls in between return the cached value. The first call only
+/// takes a sample and returns 0.0. Helper processes of the engine are not
+/// included. Returns -1.0 if CPU times cannot be read.
+#[no_mangle]
+pub extern "C" fn wry_app_cpu_usage() -> f64 {
+    let mut last = CPU_SAMPLE.lock().unwrap();
+    if let Some(sample) = last.as_ref() {
+        if sample.taken.elapsed() < CPU_SAMPLE_INTERVAL {
+            return sample.usage;
+        }
+    }
+
+    let (process, capacity) = match cpu_times() {
+        Ok(times) => times,
+        Err(e) => {
+            set_last_error(e);
+            return -1.0;
+        }
+    };
+
+    let usage = match last.as_ref() {
+        Some(prev) if capacity > prev.capacity => {
+            ((process - prev.process) / (capacity - prev.capacity)).clamp(0.0, 1.0)
+        }
+        Some(prev) => prev.usage,
+        None => 0.0,
+    };
+
+    *last = Some(CpuSample {
+        taken: Instant::now(),
+        process,
+        capacity,
+        usage,
+    });
+    usage
+}
+
+/// CPU time available on all cores since the first call, for platforms
+/// without a system-wide counter
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn wall_capacity() -> f64 {
+    static START: once_cell::sync::Lazy<Instant> = once_cell::sync::Lazy::new(Instant::now);
+
+    let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+    START.elapsed().as_secs_f64() * cores as f64
+}
+
+/// Process and total CPU time, in clock ticks from /proc
+#[cfg(target_os = "linux")]
+fn cpu_times() -> Result<(f64, f64), String> {
+    let stat = std::fs::read_to_string("/proc/self/stat")
+        .map_err(|e| format!("Failed to read /proc/self/stat: {}", e))?;
+    // utime and stime are the 12th and 13th fields after `(comm)`
+    let fields: Vec<&str> = stat
+        .rsplit_once(')')
+        .map(|(_, rest)| rest.split_whitespace().collect())
+        .unwrap_or_default();
+    let process: f64 = fields
+        .get(11..13)
+        .ok_or_else(|| "Unexpected /proc/self/stat format".to_string())?
+        .iter()
+        .map(|f| f.parse::<f64>().unwrap_or(0.0))
+        .sum();
+
+    // The first line of /proc/stat totals every core
+    let system = std::fs::read_to_string("/proc/stat")
+        .map_err(|e| format!("Failed to read /proc/stat: {}", e))?;
+    let capacity: f64 = system
+        .lines()
+        .next()
+        .filter(|l| l.starts_with("cpu "))
+        .ok_or_else(|| "Unexpected /proc/stat format".to_string())?
+        .split_whitespace()
+        .skip(1)
+        // guest time is already counted in user time
+        .take(8)
+        .map(|f| f.parse::<f64>().unwrap_or(0.0))
+        .sum();
+
+    Ok((process, capacity))
+}
+
+#[cfg(target_os = "macos")]
+fn cpu_times() -> Result<(f64, f64), String> {
+    // Live threads, plus threads that have already exited
+    let live: mach::TaskThreadTimesInfo = mach::query(mach::TASK_THREAD_TIMES_INFO)?;
+    let exited: mach::TaskBasicInfo = mach::query(mach::MACH_TASK_BASIC_INFO)?;
+    let process = [
+        live.user_time,
+        live.system_time,
+        exited.user_time,
+        exited.system_time,
+    ]
+    .iter()
+    .map(|t| t.seconds as f64 + t.microseconds as f64 / 1e6)
+    .sum();
+    Ok((process, wall_capacity()))
+}
+
+#[cfg(target_os = "windows")]
+fn cpu_times() -> Result<(f64, f64), String> {
+    use windows::Win32::Foundation::FILETIME;
+    use windows::Win32::System::Threading::{GetCurrentProcess, GetProcessTimes};
+
+    let (mut created, mut exited, mut kernel, mut user) = Default::default();
+    unsafe {
+        GetProcessTimes(
+            GetCurrentProcess(),
+            &mut created,
+            &mut exited,
+            &mut kernel,
+            &mut user,
+        )
+    }
+    .map_err(|e| format!("GetProcessTimes failed: {}", e))?;
+
+    // FILETIME counts 100 ns intervals
+    let seconds =
+        |t: FILETIME| ((t.dwHighDateTime as u64) << 32 | t.dwLowDateTime as u64) as f64 / 1e7;
+    Ok((seconds(kernel) + seconds(user), wall_capacity()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn cpu_times() -> Result<(f64, f64), String> {
+    Err("Not supported on this platform".to_string())
+}
+
+/// Mach task queries
+#[cfg(target_os = "macos")]
+mod mach {
+    /// `time_value_t`
+    #[repr(C)]
+    #[derive(Default, Clone, Copy)]
+    pub struct TimeValue {
+        pub seconds: i32,
+        pub microseconds: i32,
+    }
+
+    /// `mach_task_basic_info`
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct TaskBasicInfo {
+        pub virtual_size: u64,
+        pub resident_size: u64,
+        pub resident_size_max: u64,
+        pub user_time: TimeValue,
+        pub system_time: TimeValue,
+        pub policy: i32,
+        pub suspend_count: i32,
+    }
+
+    /// `task_thread_times_info`
+    #[repr(C)]
+    #[derive(Default)]
+    pub struct TaskThreadTimesInfo {
+        pub user_time: TimeValue,
+        pub system_time: TimeValue,
+    }
+
+    pub const TASK_THREAD_TIMES_INFO: u32 = 3;
+    pub const MACH_TASK_BASIC_INFO: u32 = 20;
+    const KERN_SUCCESS: i32 = 0;
+
+    extern "C" {
+        static mach_task_self_: u32;
+        fn task_info(task: u32, flavor: u32, info: *mut i32, count: *mut u32) -> i32;
+    }
+
+    /// Query `flavor` of the current task into `T`, its matching struct
+    pub fn query<T: Default>(flavor: u32) -> Result<T, String> {
+        let mut info = T::default();
+        let mut count = (std::mem::size_of::<T>() / 4) as u32;
+        let result = unsafe {
+            task_info(
+                mach_task_self_,
+                flavor,
+                &mut info as *mut T as *mut i32,
+                &mut count,
+            )
+        };
+        if result != KERN_SUCCESS {
+            return Err(format!("task_info failed: {}", result));
+        }
+        Ok(info)
+    }
+}
@@ -2,13 +2,204 @@
 //!
 //! Allows registering custom URL schemes like `app://` for serving local resources.
 
+use std::collections::HashMap;
 use std::ffi::c_char;
 use std::os::raw::c_void;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use http::{Method, Request, Response, StatusCode};
+use once_cell::sync::Lazy;
 
 use crate::app::{AppState, ProtocolHandler};
-use crate::error::error_result;
-use crate::string::c_str_to_string;
-use crate::types::{CustomProtocolCallback, WryApp, WryErrorCode, WryResult};
+use crate::error::{error_result, set_last_error};
+use crate::string::{c_str_to_string, string_to_c_string};
+use crate::types::{
+    CustomProtocolCallback, WryApp, WryCacheControl, WryCacheControlKind, WryCustomProtocolHeader,
+    WryErrorCode, WryResult, WryWindow,
+};
+use crate::window::get_window_state;
+
+/// Maximum number of responses kept per scheme
+const MAX_CACHE_ENTRIES: usize = 256;
+
+/// Origins allowed to fetch from each scheme, an empty list allows any
+static SCHEME_CORS: Lazy<Mutex<HashMap<String, Vec<String>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A cached protocol response
+struct CachedResponse {
+    body: Vec<u8>,
+    mime_type: String,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+/// LRU cache of protocol responses keyed by request URI, only GET and HEAD
+/// requests with a successful response are cached
+pub struct ProtocolCache {
+    policy: WryCacheControl,
+    entries: Mutex<(HashMap<String, CachedResponse>, u64)>,
+}
+
+impl ProtocolCache {
+    /// Create a cache for the given policy, or None if caching is disabled
+    pub fn new(policy: WryCacheControl) -> Option<Self> {
+        match policy.kind {
+            WryCacheControlKind::None => None,
+            WryCacheControlKind::MaxAgeSeconds if policy.max_age_seconds == 0 => None,
+            _ => Some(Self {
+                policy,
+                entries: Mutex::new((HashMap::new(), 0)),
+            }),
+        }
+    }
+
+    /// Value for the `Cache-Control` response header
+    pub fn header_value(&self) -> String {
+        match self.policy.kind {
+            WryCacheControlKind::Immutable => "public, max-age=31536000, immutable".to_string(),
+            _ => format!("max-age={}", self.policy.max_age_seconds),
+        }
+    }
+
+    /// Whether responses to requests with `method` may be cached
+    pub fn caches_method(method: &Method) -> bool {
+        *method == Method::GET || *method == Method::HEAD
+    }
+
+    /// Look up a response, dropping it if it has expired
+    pub fn get(&self, uri: &str) -> Option<(Vec<u8>, String)> {
+        let mut guard = self.entries.lock().unwrap();
+        let (entries, clock) = &mut *guard;
+
+        let expired = match entries.get(uri) {
+            Some(entry) => match self.policy.kind {
+                WryCacheControlKind::MaxAgeSeconds => {
+                    entry.stored_at.elapsed()
+                        >= Duration::from_secs(self.policy.max_age_seconds as u64)
+                }
+                _ => false,
+            },
+            None => return None,
+        };
+
+        if expired {
+            entries.remove(uri);
+            return None;
+        }
+
+        *clock += 1;
+        let entry = entries.get_mut(uri)?;
+        entry.last_used = *clock;
+        Some((entry.body.clone(), entry.mime_type.clone()))
+    }
+
+    /// Store a successful response, evicting the least recently used one
+    /// when full
+    pub fn insert(&self, uri: &str, status: StatusCode, body: &[u8], mime_type: &str) {
+        if !status.is_success() {
+            return;
+        }
+
+        let mut guard = self.entries.lock().unwrap();
+        let (entries, clock) = &mut *guard;
+
+        if entries.len() >= MAX_CACHE_ENTRIES && !entries.contains_key(uri) {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone());
+            if let Some(key) = oldest {
+                entries.remove(&key);
+            }
+        }
+
+        *clock += 1;
+        entries.insert(
+            uri.to_string(),
+            CachedResponse {
+                body: body.to_vec(),
+                mime_type: mime_type.to_string(),
+                stored_at: Instant::now(),
+                last_used: *clock,
+            },
+        );
+    }
+}
+
+/// Headers added to every custom protocol response of one webview, shared
+/// with its protocol handlers
+pub type DefaultHeaders = Arc<Mutex<HeaderMap>>;
+
+/// Add the default headers the response does not already set
+pub fn apply_default_headers<T>(
+    mut response: Response<T>,
+    defaults: &DefaultHeaders,
+) -> Response<T> {
+    let defaults = defaults.lock().unwrap();
+    let headers = response.headers_mut();
+    for name in defaults.keys() {
+        if !headers.contains_key(name) {
+            for value in defaults.get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+    }
+    response
+}
+
+/// `Access-Control-Allow-Origin` value for a request to `scheme`, None if
+/// the scheme has no CORS policy or the origin is not allowed
+fn cors_allowed_origin<T>(scheme: &str, request: &Request<T>) -> Option<HeaderValue> {
+    let policies = SCHEME_CORS.lock().unwrap();
+    let allowed = policies.get(scheme)?;
+    if allowed.is_empty() {
+        return Some(HeaderValue::from_static("*"));
+    }
+    let origin = request.headers().get(header::ORIGIN)?;
+    let origin_str = origin.to_str().ok()?;
+    allowed.iter().any(|o| o == origin_str).then(|| origin.clone())
+}
+
+/// Answer a CORS preflight for `scheme`, None if the request is not one or
+/// the scheme has no CORS policy
+pub fn cors_preflight_response<T>(scheme: &str, request: &Request<T>) -> Option<Response<()>> {
+    if request.method() != Method::OPTIONS
+        || !request.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD)
+        || !SCHEME_CORS.lock().unwrap().contains_key(scheme)
+    {
+        return None;
+    }
+
+    let mut response = Response::builder().status(StatusCode::NO_CONTENT);
+    if let Some(origin) = cors_allowed_origin(scheme, request) {
+        response = response
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_ALLOW_METHODS, "GET, POST, PUT, DELETE, OPTIONS");
+        if let Some(headers) = request.headers().get(header::ACCESS_CONTROL_REQUEST_HEADERS) {
+            response = response.header(header::ACCESS_CONTROL_ALLOW_HEADERS, headers);
+        }
+    }
+    response.header(header::VARY, "Origin").body(()).ok()
+}
+
+/// Add the scheme's `Access-Control-Allow-Origin` header to a response
+pub fn apply_cors_headers<T, B>(
+    mut response: Response<B>,
+    scheme: &str,
+    request: &Request<T>,
+) -> Response<B> {
+    if let Some(origin) = cors_allowed_origin(scheme, request) {
+        let headers = response.headers_mut();
+        if origin != "*" {
+            headers.append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+    }
+    response
+}
 
 // ============================================================================
 // FFI Functions
@@ -27,6 +218,26 @@ pub unsafe extern "C" fn wry_register_protocol(
     scheme: *const c_char,
     callback: CustomProtocolCallback,
     user_data: *mut c_void,
+) -> WryResult {
+    wry_register_protocol_with_cache(app, scheme, callback, user_data, WryCacheControl::default())
+}
+
+/// Register custom protocol handler with response caching
+///
+/// Same as `wry_register_protocol`, but successful responses are cached by
+/// request URI according to `cache_control`, so repeated requests for the
+/// same asset are served without calling the handler. Only use this for
+/// schemes serving immutable or slowly-changing content.
+///
+/// # Safety
+/// Must be called with a valid app handle and callback.
+#[no_mangle]
+pub unsafe extern "C" fn wry_register_protocol_with_cache(
+    app: WryApp,
+    scheme: *const c_char,
+    callback: CustomProtocolCallback,
+    user_data: *mut c_void,
+    cache_control: WryCacheControl,
 ) -> WryResult {
     if app.is_null() {
         return error_result(WryErrorCode::InvalidHandle, "Null app handle");
@@ -37,7 +248,7 @@ pub unsafe extern "C" fn wry_register_protocol(
         None => return error_result(WryErrorCode::InvalidParameter, "Null or invalid scheme"),
     };
 
-    log::info!("Registering custom protocol: {}", scheme);
+    log::info!("Registering custom protocol: {} ({:?})", scheme, cache_control.kind);
 
     let state = &mut *(app as *mut AppState);
 
@@ -47,9 +258,294 @@ pub unsafe extern "C" fn wry_register_protocol(
         ProtocolHandler {
             callback,
             user_data,
+            cache: ProtocolCache::new(cache_control).map(Arc::new),
         },
     );
 
     log::debug!("Protocol '{}' registered successfully", scheme);
     WryResult::ok()
 }
+
+/// Unregister a custom protocol handler
+///
+/// Wry cannot remove a scheme from a live webview, so this only affects
+/// webviews created after the call. Existing webviews keep serving the
+/// scheme through the handler they were built with; the caller must keep
+/// that handler's `user_data` alive until those windows are destroyed.
+///
+/// # Safety
+/// Must be called with a valid app handle.
+#[no_mangle]
+pub unsafe extern "C" fn wry_custom_protocol_unregister(
+    app: WryApp,
+    scheme: *const c_char,
+) -> WryResult {
+    if app.is_null() {
+        return error_result(WryErrorCode::InvalidHandle, "Null app handle");
+    }
+
+    let scheme = match c_str_to_string(scheme) {
+        Some(s) => s,
+        None => return error_result(WryErrorCode::InvalidParameter, "Null or invalid scheme"),
+    };
+
+    let state = &mut *(app as *mut AppState);
+
+    if state.custom_protocols.remove(&scheme).is_none() {
+        return error_result(
+            WryErrorCode::InvalidParameter,
+            format!("Protocol '{}' is not registered", scheme),
+        );
+    }
+
+    log::info!("Unregistered custom protocol: {}", scheme);
+    WryResult::ok()
+}
+
+/// List registered custom protocol schemes as a JSON array, e.g. `["app","asset"]`
+///
+/// Returns `[]` when none are registered. Caller must free with wry_string_free.
+///
+/// # Safety
+/// Must be called with a valid app handle.
+#[no_mangle]
+pub unsafe extern "C" fn wry_protocol_list(app: WryApp) -> *mut c_char {
+    if app.is_null() {
+        set_last_error("Null app handle");
+        return std::ptr::null_mut();
+    }
+
+    let state = &*(app as *const AppState);
+    let mut schemes: Vec<&str> = state.custom_protocols.keys().map(String::as_str).collect();
+    schemes.sort_unstable();
+    string_to_c_string(&serde_json::Value::from(schemes).to_string())
+}
+
+/// Set headers added to every custom protocol response of a webview
+///
+/// Useful for CORS or security headers shared by all resources. Headers the
+/// response already sets, such as `Content-Type` or `Cache-Control` from
+/// the protocol cache, take precedence. Replaces any previous list; pass a
+/// count of 0 to clear it.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_default_headers(
+    window: WryWindow,
+    headers: *const WryCustomProtocolHeader,
+    count: usize,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if headers.is_null() && count > 0 {
+        set_last_error("Null headers");
+        return false;
+    }
+
+    let entries = if count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(headers, count)
+    };
+
+    let mut map = HeaderMap::new();
+    for header in entries {
+        let name =
+            c_str_to_string(header.name).and_then(|n| HeaderName::from_bytes(n.as_bytes()).ok());
+        let value = c_str_to_string(header.value).and_then(|v| HeaderValue::from_str(&v).ok());
+        match (name, value) {
+            (Some(name), Some(value)) => {
+                map.append(name, value);
+            }
+            _ => {
+                set_last_error("Invalid header name or value");
+                return false;
+            }
+        }
+    }
+
+    log::debug!("Setting {} default protocol header(s)", map.len());
+    *state.default_headers.lock().unwrap() = map;
+    true
+}
+
+/// Allow cross-origin fetches from a custom scheme
+///
+/// Responses from `scheme` get an `Access-Control-Allow-Origin` header and
+/// preflight requests are answered without calling the handler. A null
+/// `allowed_origins` or a count of 0 allows any origin (`*`); otherwise the
+/// request's `Origin` is echoed back only if it is in the list. Applies to
+/// all webviews, including existing ones, and replaces any previous policy.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_scheme_cors(
+    scheme: *const c_char,
+    allowed_origins: *const *const c_char,
+    origin_count: usize,
+) -> bool {
+    let scheme = match c_str_to_string(scheme) {
+        Some(s) => s,
+        None => {
+            set_last_error("Null or invalid scheme");
+            return false;
+        }
+    };
+
+    let entries = if allowed_origins.is_null() || origin_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(allowed_origins, origin_count)
+    };
+
+    let mut origins = Vec::with_capacity(entries.len());
+    for &origin in entries {
+        match c_str_to_string(origin).filter(|o| HeaderValue::from_str(o).is_ok()) {
+            Some(origin) => origins.push(origin),
+            None => {
+                set_last_error("Invalid origin");
+                return false;
+            }
+        }
+    }
+
+    log::debug!("Setting CORS policy for '{}': {:?}", scheme, origins);
+    SCHEME_CORS.lock().unwrap().insert(scheme, origins);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(kind: WryCacheControlKind, max_age_seconds: u32) -> ProtocolCache {
+        ProtocolCache::new(WryCacheControl {
+            kind,
+            max_age_seconds,
+        })
+        .unwrap()
+    }
+
+    fn uri(n: usize) -> String {
+        format!("app://localhost/{}", n)
+    }
+
+    #[test]
+    fn disabled_policies_create_no_cache() {
+        let none = WryCacheControl {
+            kind: WryCacheControlKind::None,
+            max_age_seconds: 60,
+        };
+        let zero = WryCacheControl {
+            kind: WryCacheControlKind::MaxAgeSeconds,
+            max_age_seconds: 0,
+        };
+        assert!(ProtocolCache::new(none).is_none());
+        assert!(ProtocolCache::new(zero).is_none());
+    }
+
+    #[test]
+    fn only_get_and_head_are_cached() {
+        assert!(ProtocolCache::caches_method(&Method::GET));
+        assert!(ProtocolCache::caches_method(&Method::HEAD));
+        assert!(!ProtocolCache::caches_method(&Method::POST));
+        assert!(!ProtocolCache::caches_method(&Method::PUT));
+        assert!(!ProtocolCache::caches_method(&Method::DELETE));
+    }
+
+    #[test]
+    fn only_successful_responses_are_stored() {
+        let cache = cache(WryCacheControlKind::Immutable, 0);
+        cache.insert(&uri(0), StatusCode::NOT_FOUND, b"missing", "text/plain");
+        cache.insert(
+            &uri(1),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            b"error",
+            "text/plain",
+        );
+        cache.insert(&uri(2), StatusCode::OK, b"found", "text/plain");
+
+        assert!(cache.get(&uri(0)).is_none());
+        assert!(cache.get(&uri(1)).is_none());
+        assert_eq!(
+            cache.get(&uri(2)),
+            Some((b"found".to_vec(), "text/plain".to_string()))
+        );
+    }
+
+    #[test]
+    fn full_cache_evicts_the_least_recently_used_entry() {
+        let cache = cache(WryCacheControlKind::Immutable, 0);
+        for n in 0..MAX_CACHE_ENTRIES {
+            cache.insert(&uri(n), StatusCode::OK, b"body", "text/plain");
+        }
+        // Touch the oldest entry so the second oldest becomes the LRU one
+        assert!(cache.get(&uri(0)).is_some());
+
+        cache.insert(
+            &uri(MAX_CACHE_ENTRIES),
+            StatusCode::OK,
+            b"body",
+            "text/plain",
+        );
+
+        assert_eq!(cache.entries.lock().unwrap().0.len(), MAX_CACHE_ENTRIES);
+        assert!(cache.get(&uri(1)).is_none());
+        assert!(cache.get(&uri(0)).is_some());
+        assert!(cache.get(&uri(2)).is_some());
+        assert!(cache.get(&uri(MAX_CACHE_ENTRIES)).is_some());
+    }
+
+    #[test]
+    fn replacing_an_entry_in_a_full_cache_evicts_nothing() {
+        let cache = cache(WryCacheControlKind::Immutable, 0);
+        for n in 0..MAX_CACHE_ENTRIES {
+            cache.insert(&uri(n), StatusCode::OK, b"old", "text/plain");
+        }
+
+        cache.insert(&uri(0), StatusCode::OK, b"new", "text/html");
+
+        assert_eq!(cache.entries.lock().unwrap().0.len(), MAX_CACHE_ENTRIES);
+        assert_eq!(
+            cache.get(&uri(0)),
+            Some((b"new".to_vec(), "text/html".to_string()))
+        );
+        assert!(cache.get(&uri(1)).is_some());
+    }
+
+    #[test]
+    fn max_age_entries_expire() {
+        let cache = cache(WryCacheControlKind::MaxAgeSeconds, 60);
+        cache.insert(&uri(0), StatusCode::OK, b"fresh", "text/plain");
+        cache.insert(&uri(1), StatusCode::OK, b"stale", "text/plain");
+        if let Some(entry) = cache.entries.lock().unwrap().0.get_mut(&uri(1)) {
+            entry.stored_at = Instant::now() - Duration::from_secs(61);
+        }
+
+        assert!(cache.get(&uri(0)).is_some());
+        assert!(cache.get(&uri(1)).is_none());
+        assert!(!cache.entries.lock().unwrap().0.contains_key(&uri(1)));
+    }
+
+    #[test]
+    fn immutable_entries_never_expire() {
+        let cache = cache(WryCacheControlKind::Immutable, 0);
+        cache.insert(&uri(0), StatusCode::OK, b"body", "text/plain");
+        if let Some(entry) = cache.entries.lock().unwrap().0.get_mut(&uri(0)) {
+            entry.stored_at = Instant::now() - Duration::from_secs(61);
+        }
+
+        assert!(cache.get(&uri(0)).is_some());
+    }
+
+    #[test]
+    fn header_value_follows_the_policy() {
+        assert_eq!(
+            cache(WryCacheControlKind::Immutable, 0).header_value(),
+            "public, max-age=31536000, immutable"
+        );
+        assert_eq!(
+            cache(WryCacheControlKind::MaxAgeSeconds, 30).header_value(),
+            "max-age=30"
+        );
+    }
+}
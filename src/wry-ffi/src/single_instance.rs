@@ -0,0 +1,281 @@
+//! Single instance enforcement
+//!
+//! The first instance holds a system-wide lock and listens for later
+//! instances, which forward their command-line arguments and then exit.
+
+use std::ffi::{c_char, CString};
+use std::io::{Read, Write};
+use std::os::raw::c_void;
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use crate::error::set_last_error;
+use crate::string::c_str_to_string;
+use crate::types::SingleInstanceCallback;
+
+/// Lock held by the primary instance for the lifetime of the process
+static INSTANCE_LOCK: Lazy<Mutex<Option<InstanceLock>>> = Lazy::new(|| Mutex::new(None));
+
+/// Callback invoked with the arguments of a later instance
+struct ArgsHandler {
+    callback: SingleInstanceCallback,
+    user_data: usize,
+}
+
+impl ArgsHandler {
+    fn call(&self, args_json: &str) {
+        let args = match CString::new(args_json) {
+            Ok(s) => s,
+            Err(_) => return,
+        };
+        (self.callback)(args.as_ptr(), self.user_data as *mut c_void);
+    }
+}
+
+/// Read one message from a later instance and pass it to the handler
+fn handle_connection(mut stream: impl Read, handler: &ArgsHandler) {
+    let mut args_json = String::new();
+    match stream.read_to_string(&mut args_json) {
+        Ok(_) => {
+            log::debug!("Second instance launched with args: {}", args_json);
+            handler.call(&args_json);
+        }
+        Err(e) => log::warn!("Failed to read second instance args: {}", e),
+    }
+}
+
+/// Command-line arguments of this process as a JSON array
+fn args_json() -> String {
+    let args: Vec<String> = std::env::args_os()
+        .map(|a| a.to_string_lossy().into_owned())
+        .collect();
+    serde_json::to_string(&args).unwrap_or_else(|_| "[]".to_string())
+}
+
+// ============================================================================
+// Platform Locks
+// ============================================================================
+
+/// Outcome of trying to become the primary instance
+enum Acquired {
+    Primary(InstanceLock),
+    /// Another instance holds the lock, with the result of forwarding to it
+    Secondary(Result<(), String>),
+}
+
+/// Retry `connect` while the running instance may still be creating its
+/// listener
+fn connect_with_retry<T>(mut connect: impl FnMut() -> std::io::Result<T>) -> Result<T, String> {
+    let mut attempts = 0;
+    loop {
+        match connect() {
+            Ok(stream) => return Ok(stream),
+            Err(_) if attempts < 10 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            Err(e) => return Err(format!("Failed to reach running instance: {}", e)),
+        }
+    }
+}
+
+#[cfg(unix)]
+struct InstanceLock {
+    _file: std::fs::File,
+}
+
+#[cfg(unix)]
+fn acquire(app_id: &str, handler: ArgsHandler) -> Result<Acquired, String> {
+    use std::fs::{File, OpenOptions, TryLockError};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    // temp_dir() honors $TMPDIR
+    let dir = std::env::temp_dir();
+    let lock_path = dir.join(format!("{}.lock", app_id));
+    let socket_path = dir.join(format!("{}.sock", app_id));
+
+    let file: File = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open {}: {}", lock_path.display(), e))?;
+
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            let forwarded =
+                connect_with_retry(|| UnixStream::connect(&socket_path)).and_then(|mut stream| {
+                    stream
+                        .write_all(args_json().as_bytes())
+                        .map_err(|e| format!("Failed to send args to running instance: {}", e))
+                });
+            return Ok(Acquired::Secondary(forwarded));
+        }
+        Err(TryLockError::Error(e)) => {
+            return Err(format!("Failed to lock {}: {}", lock_path.display(), e));
+        }
+    }
+
+    // Holding the lock means any existing socket is stale
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)
+        .map_err(|e| format!("Failed to bind {}: {}", socket_path.display(), e))?;
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            handle_connection(stream, &handler);
+        }
+    });
+
+    Ok(Acquired::Primary(InstanceLock { _file: file }))
+}
+
+#[cfg(windows)]
+struct InstanceLock {
+    _mutex: windows::Win32::Foundation::HANDLE,
+}
+
+// Safety: the mutex handle is only closed when the process exits
+#[cfg(windows)]
+unsafe impl Send for InstanceLock {}
+
+#[cfg(windows)]
+fn acquire(app_id: &str, handler: ArgsHandler) -> Result<Acquired, String> {
+    use std::os::windows::io::FromRawHandle;
+
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::{
+        CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, ERROR_PIPE_CONNECTED,
+    };
+    use windows::Win32::Storage::FileSystem::PIPE_ACCESS_INBOUND;
+    use windows::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+    use windows::Win32::System::Threading::CreateMutexW;
+
+    let mutex_name = HSTRING::from(format!("Local\\{}", app_id));
+    let pipe_name = format!(r"\\.\pipe\{}", app_id);
+
+    let mutex = unsafe { CreateMutexW(None, false, &mutex_name) }
+        .map_err(|e| format!("Failed to create mutex: {}", e))?;
+
+    if unsafe { GetLastError() } == ERROR_ALREADY_EXISTS {
+        // Only the primary instance keeps the mutex open
+        let _ = unsafe { CloseHandle(mutex) };
+
+        let forwarded =
+            connect_with_retry(|| std::fs::OpenOptions::new().write(true).open(&pipe_name))
+                .and_then(|mut pipe| {
+                    pipe.write_all(args_json().as_bytes())
+                        .map_err(|e| format!("Failed to send args to running instance: {}", e))
+                });
+        return Ok(Acquired::Secondary(forwarded));
+    }
+
+    let pipe_name = HSTRING::from(pipe_name);
+    std::thread::spawn(move || loop {
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                &pipe_name,
+                PIPE_ACCESS_INBOUND,
+                PIPE_TYPE_BYTE | PIPE_WAIT,
+                PIPE_UNLIMITED_INSTANCES,
+                0,
+                4096,
+                0,
+                None,
+            )
+        };
+        if pipe.is_invalid() {
+            log::error!("Failed to create single instance pipe");
+            return;
+        }
+
+        let connected = match unsafe { ConnectNamedPipe(pipe, None) } {
+            Ok(()) => true,
+            Err(e) => e.code() == ERROR_PIPE_CONNECTED.to_hresult(),
+        };
+
+        // The File takes ownership and closes the pipe handle
+        let pipe = unsafe { std::fs::File::from_raw_handle(pipe.0) };
+        if connected {
+            handle_connection(pipe, &handler);
+        }
+    });
+
+    Ok(Acquired::Primary(InstanceLock { _mutex: mutex }))
+}
+
+#[cfg(not(any(unix, windows)))]
+struct InstanceLock;
+
+#[cfg(not(any(unix, windows)))]
+fn acquire(_app_id: &str, _handler: ArgsHandler) -> Result<Acquired, String> {
+    Ok(Acquired::Primary(InstanceLock))
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// Ensure only one instance of the app runs at a time
+///
+/// The first caller for `app_id` becomes the primary instance and returns
+/// true. Later instances forward their command-line arguments to it and
+/// return false, after which they should exit; the last error is set if
+/// the arguments could not be forwarded. The callback runs on a
+/// background thread in the primary instance with the arguments as a JSON
+/// array string; use `wry_invoke` to get back to the UI thread.
+///
+/// Uses a named mutex on Windows and a lock file in `$TMPDIR` on macOS and
+/// Linux. `app_id` must be usable in a file name.
+#[no_mangle]
+pub unsafe extern "C" fn wry_app_single_instance(
+    app_id: *const c_char,
+    callback: SingleInstanceCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let app_id = match c_str_to_string(app_id) {
+        Some(id) if !id.is_empty() && !id.contains(['/', '\\', '\0']) => id,
+        _ => {
+            set_last_error("Null or invalid app id");
+            return false;
+        }
+    };
+
+    let mut lock = INSTANCE_LOCK.lock().unwrap();
+    if lock.is_some() {
+        log::debug!("Single instance lock already held");
+        return true;
+    }
+
+    let handler = ArgsHandler {
+        callback,
+        user_data: user_data as usize,
+    };
+
+    match acquire(&app_id, handler) {
+        Ok(Acquired::Primary(instance)) => {
+            log::debug!("Acquired single instance lock: {}", app_id);
+            *lock = Some(instance);
+            true
+        }
+        Ok(Acquired::Secondary(forwarded)) => {
+            log::info!("Another instance of {} is running", app_id);
+            // Still not the primary, even if its arguments were lost
+            if let Err(e) = forwarded {
+                log::error!("Failed to forward args to running instance: {}", e);
+                set_last_error(e);
+            }
+            false
+        }
+        Err(e) => {
+            // Without a working lock, let this instance run
+            log::error!("Single instance check failed: {}", e);
+            set_last_error(e);
+            true
+        }
+    }
+}
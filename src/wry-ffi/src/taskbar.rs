@@ -0,0 +1,524 @@
+//! Windows taskbar integration
+//!
+//! Jump List tasks shown when right-clicking the app's taskbar button, and
+//! toolbar buttons shown under a window's taskbar thumbnail. Other platforms
+//! have no equivalent, so these functions fail there.
+
+use crate::error::set_last_error;
+use crate::string::c_str_to_string;
+use crate::types::{WryJumpListItem, WryJumpListItemKind, WryThumbnailButton, WryWindow};
+use crate::window::get_window_state;
+
+/// Most buttons a thumbnail toolbar can hold
+const MAX_THUMBNAIL_BUTTONS: usize = 7;
+
+/// Jump List entry with its strings copied out of the caller's memory
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+enum JumpListEntry {
+    Task {
+        title: String,
+        description: Option<String>,
+        program: Option<String>,
+        args: Option<String>,
+        icon_path: Option<String>,
+    },
+    Separator,
+}
+
+/// Copy and validate the caller's items
+unsafe fn read_jump_list_items(items: &[WryJumpListItem]) -> Result<Vec<JumpListEntry>, String> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| match item.kind {
+            WryJumpListItemKind::Separator => Ok(JumpListEntry::Separator),
+            WryJumpListItemKind::Task => Ok(JumpListEntry::Task {
+                title: c_str_to_string(item.title)
+                    .filter(|t| !t.is_empty())
+                    .ok_or_else(|| format!("Jump List task {} has no title", i))?,
+                description: c_str_to_string(item.description),
+                program: c_str_to_string(item.program),
+                args: c_str_to_string(item.args),
+                icon_path: c_str_to_string(item.icon_path),
+            }),
+        })
+        .collect()
+}
+
+/// Thumbnail button with its data copied out of the caller's memory
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+struct ThumbnailButton {
+    id: u32,
+    tooltip: Option<String>,
+    /// (pixels, width, height)
+    icon: Option<(Vec<u8>, u32, u32)>,
+    enabled: bool,
+}
+
+/// Copy and validate the caller's buttons
+unsafe fn read_thumbnail_buttons(
+    buttons: &[WryThumbnailButton],
+) -> Result<Vec<ThumbnailButton>, String> {
+    buttons
+        .iter()
+        .map(|button| {
+            let icon = if button.icon_rgba.is_null() {
+                None
+            } else {
+                let expected = (button.icon_width as usize)
+                    .checked_mul(button.icon_height as usize)
+                    .and_then(|n| n.checked_mul(4));
+                let empty = button.icon_width == 0 || button.icon_height == 0;
+                if empty || expected != Some(button.icon_len) {
+                    return Err(format!(
+                        "Thumbnail button {} icon is {} bytes, expected {}x{}x4",
+                        button.id, button.icon_len, button.icon_width, button.icon_height
+                    ));
+                }
+                let rgba = std::slice::from_raw_parts(button.icon_rgba, button.icon_len);
+                Some((rgba.to_vec(), button.icon_width, button.icon_height))
+            };
+
+            Ok(ThumbnailButton {
+                id: button.id,
+                tooltip: c_str_to_string(button.tooltip),
+                icon,
+                enabled: button.enabled,
+            })
+        })
+        .collect()
+}
+
+// ============================================================================
+// Platform Jump Lists
+// ============================================================================
+
+#[cfg(target_os = "windows")]
+mod jump_list {
+    use windows::core::{Interface, HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{PROPERTYKEY, VARIANT_TRUE};
+    use windows::Win32::Storage::EnhancedStorage::{
+        PKEY_AppUserModel_IsDestListSeparator, PKEY_Title,
+    };
+    use windows::Win32::System::Com::StructuredStorage::{PropVariantClear, PROPVARIANT};
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::System::Variant::{VT_BOOL, VT_LPWSTR};
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW,
+        SHStrDupW, ShellLink,
+    };
+
+    use super::JumpListEntry;
+
+    /// Store a string property on a shell link
+    unsafe fn set_string(
+        props: &IPropertyStore,
+        key: &PROPERTYKEY,
+        value: &str,
+    ) -> windows::core::Result<()> {
+        let mut var = PROPVARIANT::default();
+        (*var.Anonymous.Anonymous).vt = VT_LPWSTR;
+        (*var.Anonymous.Anonymous).Anonymous.pwszVal = SHStrDupW(&HSTRING::from(value))?;
+        let result = props.SetValue(key, &var);
+        let _ = PropVariantClear(&mut var);
+        result
+    }
+
+    /// Store a true boolean property on a shell link
+    unsafe fn set_true(props: &IPropertyStore, key: &PROPERTYKEY) -> windows::core::Result<()> {
+        let mut var = PROPVARIANT::default();
+        (*var.Anonymous.Anonymous).vt = VT_BOOL;
+        (*var.Anonymous.Anonymous).Anonymous.boolVal = VARIANT_TRUE;
+        props.SetValue(key, &var)
+    }
+
+    unsafe fn create_link(entry: &JumpListEntry) -> windows::core::Result<IShellLinkW> {
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        let props: IPropertyStore = link.cast()?;
+
+        match entry {
+            JumpListEntry::Separator => set_true(&props, &PKEY_AppUserModel_IsDestListSeparator)?,
+            JumpListEntry::Task {
+                title,
+                description,
+                program,
+                args,
+                icon_path,
+            } => {
+                let program = match program {
+                    Some(p) => p.clone(),
+                    None => std::env::current_exe()
+                        .map(|p| p.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                };
+                link.SetPath(&HSTRING::from(program))?;
+                if let Some(args) = args {
+                    link.SetArguments(&HSTRING::from(args))?;
+                }
+                if let Some(description) = description {
+                    link.SetDescription(&HSTRING::from(description))?;
+                }
+                if let Some(icon_path) = icon_path {
+                    link.SetIconLocation(&HSTRING::from(icon_path), 0)?;
+                }
+                set_string(&props, &PKEY_Title, title)?;
+            }
+        }
+
+        props.Commit()?;
+        Ok(link)
+    }
+
+    pub unsafe fn set(entries: &[JumpListEntry]) -> Result<(), String> {
+        let fail = |e: windows::core::Error| format!("Failed to update Jump List: {}", e);
+
+        let list: ICustomDestinationList =
+            CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER).map_err(fail)?;
+        let mut slots = 0u32;
+        let _removed: IObjectArray = list.BeginList(&mut slots).map_err(fail)?;
+
+        let collection: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)
+                .map_err(fail)?;
+        for entry in entries {
+            let link = create_link(entry).map_err(fail)?;
+            collection.AddObject(&link).map_err(fail)?;
+        }
+
+        let tasks: IObjectArray = collection.cast().map_err(fail)?;
+        list.AddUserTasks(&tasks).map_err(fail)?;
+        list.CommitList().map_err(fail)
+    }
+
+    pub unsafe fn clear() -> Result<(), String> {
+        let fail = |e: windows::core::Error| format!("Failed to clear Jump List: {}", e);
+
+        let list: ICustomDestinationList =
+            CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER).map_err(fail)?;
+        list.DeleteList(PCWSTR::null()).map_err(fail)
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod jump_list {
+    use super::JumpListEntry;
+
+    pub unsafe fn set(_entries: &[JumpListEntry]) -> Result<(), String> {
+        Err("Jump Lists are only supported on Windows".to_string())
+    }
+
+    pub unsafe fn clear() -> Result<(), String> {
+        Err("Jump Lists are only supported on Windows".to_string())
+    }
+}
+
+// ============================================================================
+// Platform Thumbnail Toolbars
+// ============================================================================
+
+#[cfg(target_os = "windows")]
+mod thumbnail_toolbar {
+    use std::cell::{Cell, RefCell};
+
+    use serde_json::json;
+    use tao::event_loop::EventLoopProxy;
+    use tao::platform::windows::WindowExtWindows;
+    use tao::window::{Window, WindowId};
+    use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::{
+        DefSubclassProc, GetWindowSubclass, ITaskbarList3, RemoveWindowSubclass,
+        SetWindowSubclass, TaskbarList, THBF_DISABLED, THBF_ENABLED, THBF_HIDDEN, THBN_CLICKED,
+        THB_FLAGS, THB_ICON, THB_TOOLTIP, THUMBBUTTON,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{DestroyIcon, HICON, WM_COMMAND, WM_NCDESTROY};
+
+    use super::{ThumbnailButton, MAX_THUMBNAIL_BUTTONS};
+    use crate::app::UserEvent;
+    use crate::events::window_id_string;
+
+    /// Identifies our subclass among any others on the window
+    const SUBCLASS_ID: usize = 0x7468_756d;
+
+    /// Per-window toolbar state, owned by the window subclass
+    ///
+    /// The taskbar only allows buttons to be added once per window, so every
+    /// slot is added up front and unused ones are hidden. Slot indices are
+    /// the native button IDs and map to the caller's IDs.
+    struct Toolbar {
+        window_id: String,
+        proxy: EventLoopProxy<UserEvent>,
+        added: Cell<bool>,
+        ids: RefCell<Vec<u32>>,
+        icons: RefCell<Vec<HICON>>,
+    }
+
+    impl Toolbar {
+        fn replace_icons(&self, icons: Vec<HICON>) {
+            for icon in self.icons.replace(icons) {
+                unsafe {
+                    let _ = DestroyIcon(icon);
+                }
+            }
+        }
+    }
+
+    unsafe extern "system" fn subclass_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+        _subclass_id: usize,
+        ref_data: usize,
+    ) -> LRESULT {
+        let toolbar = &*(ref_data as *const Toolbar);
+
+        if msg == WM_COMMAND && ((wparam.0 >> 16) & 0xffff) as u32 == THBN_CLICKED {
+            let slot = wparam.0 & 0xffff;
+            if let Some(id) = toolbar.ids.borrow().get(slot) {
+                log::debug!("Thumbnail button clicked: {}", id);
+                let event = json!({
+                    "type": "thumbnail-button",
+                    "window_id": toolbar.window_id,
+                    "id": id,
+                });
+                let _ = toolbar.proxy.send_event(UserEvent::Custom(event.to_string()));
+            }
+            return LRESULT(0);
+        }
+
+        if msg == WM_NCDESTROY {
+            let result = DefSubclassProc(hwnd, msg, wparam, lparam);
+            let _ = RemoveWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID);
+            let toolbar = Box::from_raw(ref_data as *mut Toolbar);
+            toolbar.replace_icons(Vec::new());
+            return result;
+        }
+
+        DefSubclassProc(hwnd, msg, wparam, lparam)
+    }
+
+    /// Find the window's toolbar, installing the subclass on first use
+    unsafe fn toolbar_for(
+        hwnd: HWND,
+        window_id: WindowId,
+        proxy: EventLoopProxy<UserEvent>,
+    ) -> Result<&'static Toolbar, String> {
+        let mut ref_data = 0usize;
+        if GetWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID, Some(&mut ref_data))
+            .as_bool()
+        {
+            return Ok(&*(ref_data as *const Toolbar));
+        }
+
+        let toolbar = Box::into_raw(Box::new(Toolbar {
+            window_id: window_id_string(window_id),
+            proxy,
+            added: Cell::new(false),
+            ids: RefCell::new(Vec::new()),
+            icons: RefCell::new(Vec::new()),
+        }));
+        if !SetWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID, toolbar as usize).as_bool() {
+            drop(Box::from_raw(toolbar));
+            return Err("Failed to subclass window".to_string());
+        }
+        Ok(&*toolbar)
+    }
+
+    pub unsafe fn set(
+        window: &Window,
+        window_id: WindowId,
+        proxy: EventLoopProxy<UserEvent>,
+        buttons: &[ThumbnailButton],
+    ) -> Result<(), String> {
+        let fail = |e: windows::core::Error| format!("Failed to set thumbnail buttons: {}", e);
+
+        let hwnd = HWND(window.hwnd() as *mut _);
+        let taskbar: ITaskbarList3 =
+            CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER).map_err(fail)?;
+        taskbar.HrInit().map_err(fail)?;
+
+        let toolbar = toolbar_for(hwnd, window_id, proxy)?;
+
+        let mut icons = Vec::new();
+        let mut native = [THUMBBUTTON::default(); MAX_THUMBNAIL_BUTTONS];
+        for (slot, native) in native.iter_mut().enumerate() {
+            native.dwMask = THB_FLAGS | THB_ICON | THB_TOOLTIP;
+            native.iId = slot as u32;
+
+            let Some(button) = buttons.get(slot) else {
+                native.dwFlags = THBF_HIDDEN;
+                continue;
+            };
+
+            native.dwFlags = if button.enabled {
+                THBF_ENABLED
+            } else {
+                THBF_DISABLED
+            };
+            if let Some((rgba, width, height)) = &button.icon {
+                if let Some(icon) = crate::icon::hicon_from_rgba(rgba, *width, *height, None) {
+                    native.hIcon = icon;
+                    icons.push(icon);
+                }
+            }
+            if let Some(tooltip) = &button.tooltip {
+                // Truncate, leaving room for the terminator
+                let max = native.szTip.len() - 1;
+                for (dst, src) in native.szTip.iter_mut().zip(tooltip.encode_utf16().take(max)) {
+                    *dst = src;
+                }
+            }
+        }
+
+        let result = if toolbar.added.get() {
+            taskbar.ThumbBarUpdateButtons(hwnd, &native)
+        } else {
+            taskbar.ThumbBarAddButtons(hwnd, &native)
+        };
+        if let Err(e) = result {
+            for icon in icons {
+                let _ = DestroyIcon(icon);
+            }
+            return Err(fail(e));
+        }
+
+        toolbar.added.set(true);
+        *toolbar.ids.borrow_mut() = buttons.iter().map(|b| b.id).collect();
+        toolbar.replace_icons(icons);
+        Ok(())
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod thumbnail_toolbar {
+    use tao::event_loop::EventLoopProxy;
+    use tao::window::{Window, WindowId};
+
+    use super::ThumbnailButton;
+    use crate::app::UserEvent;
+
+    pub unsafe fn set(
+        _window: &Window,
+        _window_id: WindowId,
+        _proxy: EventLoopProxy<UserEvent>,
+        _buttons: &[ThumbnailButton],
+    ) -> Result<(), String> {
+        Err("Thumbnail toolbars are only supported on Windows".to_string())
+    }
+}
+
+// ============================================================================
+// FFI Functions
+// ============================================================================
+
+/// Replace the Tasks section of the app's Jump List (Windows only)
+///
+/// Applies to the app as a whole rather than a single window. Passing zero
+/// items leaves an empty Tasks section; use `wry_jump_list_clear` to remove
+/// the Jump List entirely.
+///
+/// # Returns
+/// false on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_jump_list_set(items: *const WryJumpListItem, count: usize) -> bool {
+    let items = if count == 0 {
+        &[]
+    } else if items.is_null() {
+        set_last_error("Null Jump List items");
+        return false;
+    } else {
+        std::slice::from_raw_parts(items, count)
+    };
+
+    let entries = match read_jump_list_items(items) {
+        Ok(e) => e,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    log::debug!("Setting Jump List with {} items", entries.len());
+    match jump_list::set(&entries) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Remove the app's Jump List tasks (Windows only)
+///
+/// # Returns
+/// false on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_jump_list_clear() -> bool {
+    log::debug!("Clearing Jump List");
+    match jump_list::clear() {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+/// Set the toolbar buttons under the window's taskbar thumbnail (Windows only)
+///
+/// Takes up to 7 buttons and replaces any set before; zero buttons hides the
+/// toolbar. Call once the window is shown, as the taskbar button must exist.
+/// Clicks are delivered as app events:
+/// `{"type":"thumbnail-button","window_id":"...","id":N}`.
+///
+/// # Returns
+/// false on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_thumbnail_toolbar_set_buttons(
+    window: WryWindow,
+    buttons: *const WryThumbnailButton,
+    count: usize,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if count > MAX_THUMBNAIL_BUTTONS {
+        set_last_error(format!(
+            "At most {} thumbnail buttons are supported",
+            MAX_THUMBNAIL_BUTTONS
+        ));
+        return false;
+    }
+
+    let buttons = if count == 0 {
+        &[]
+    } else if buttons.is_null() {
+        set_last_error("Null thumbnail buttons");
+        return false;
+    } else {
+        std::slice::from_raw_parts(buttons, count)
+    };
+
+    let buttons = match read_thumbnail_buttons(buttons) {
+        Ok(b) => b,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    log::debug!("Setting {} thumbnail buttons", buttons.len());
+    let proxy = state.event_loop_proxy.clone();
+    match thumbnail_toolbar::set(&state.window, state.id, proxy, &buttons) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
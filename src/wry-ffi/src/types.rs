@@ -11,6 +11,9 @@ pub type WryApp = *mut c_void;
 /// Opaque handle to a window with webview
 pub type WryWindow = *mut c_void;
 
+/// Opaque handle for sending events to the event loop from any thread
+pub type WryEventLoopProxy = *mut c_void;
+
 /// Window creation parameters
 #[repr(C)]
 pub struct WryWindowParams {
@@ -42,6 +45,19 @@ pub struct WryWindowParams {
     pub always_on_top: bool,
     pub devtools_enabled: bool,
     pub autoplay_enabled: bool,
+
+    // Scripts run before page JavaScript on every load; null entries are skipped
+    pub init_scripts: *const *const c_char,
+    pub init_script_count: usize,
+
+    // Proxy for all webview traffic
+    pub proxy: WryProxyConfig,
+
+    // Page behavior, see the matching wry_webview_set_* functions
+    pub default_context_menu_enabled: bool,
+    pub allow_text_selection: bool,
+    pub disable_zoom: bool,
+    pub take_focus_on_click: bool,
 }
 
 impl Default for WryWindowParams {
@@ -70,6 +86,55 @@ impl Default for WryWindowParams {
             always_on_top: false,
             devtools_enabled: true,
             autoplay_enabled: false,
+            init_scripts: std::ptr::null(),
+            init_script_count: 0,
+            proxy: WryProxyConfig::default(),
+            default_context_menu_enabled: true,
+            allow_text_selection: true,
+            disable_zoom: false,
+            take_focus_on_click: true,
+        }
+    }
+}
+
+/// Kind of proxy server
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WryProxyKind {
+    /// Connect directly
+    #[default]
+    None = 0,
+    /// HTTP proxy, tunnelling with HTTP CONNECT
+    Http = 1,
+    /// Not supported by the platform webviews, window creation fails
+    Https = 2,
+    /// SOCKSv5 proxy
+    Socks5 = 3,
+}
+
+/// Proxy server used for all webview traffic
+///
+/// A `None` kind or a null or empty host disables the proxy. Credentials are
+/// not supported by the platform webviews, setting them fails window creation.
+/// On macOS this requires macOS 14 and the `mac-proxy` feature.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryProxyConfig {
+    pub kind: WryProxyKind,
+    pub host: *const c_char,
+    pub port: u16,
+    pub username: *const c_char,
+    pub password: *const c_char,
+}
+
+impl Default for WryProxyConfig {
+    fn default() -> Self {
+        Self {
+            kind: WryProxyKind::None,
+            host: std::ptr::null(),
+            port: 0,
+            username: std::ptr::null(),
+            password: std::ptr::null(),
         }
     }
 }
@@ -90,6 +155,265 @@ pub struct WryPosition {
     pub y: i32,
 }
 
+/// Point with fractional coordinates
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WryPoint {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// RGBA color with 8-bit channels
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WryColor {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+/// Discriminant of `WryEvent`, naming the `WryEventData` field that is set
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryEventKind {
+    /// Any other event, serialized in `WryEvent::json`
+    Other = 0,
+    WindowCloseRequested = 1,
+    WindowDestroyed = 2,
+    /// `size`
+    WindowResized = 3,
+    /// `position`
+    WindowMoved = 4,
+    /// `focused`
+    WindowFocused = 5,
+    /// `key`
+    KeyboardInput = 6,
+    /// `modifiers`
+    ModifiersChanged = 7,
+    /// `cursor`
+    CursorMoved = 8,
+    CursorEntered = 9,
+    CursorLeft = 10,
+    /// `mouse`
+    MouseInput = 11,
+    /// `wheel`
+    MouseWheel = 12,
+    /// `scale_factor`
+    ScaleFactorChanged = 13,
+    /// `is_dark`
+    ThemeChanged = 14,
+}
+
+/// Keyboard event; strings are the same as in `window-keyboard-input` JSON
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryKeyEvent {
+    pub pressed: bool,
+    pub repeat: bool,
+    pub physical_key: *const c_char,
+    pub logical_key: *const c_char,
+    /// Null if the key produces no text
+    pub text: *const c_char,
+}
+
+/// Mouse button of a `WryMouseEvent`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryMouseButton {
+    Left = 0,
+    Right = 1,
+    Middle = 2,
+    /// Identified by `WryMouseEvent::other_button`
+    Other = 3,
+}
+
+/// Mouse button press or release
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryMouseEvent {
+    pub pressed: bool,
+    pub button: WryMouseButton,
+    pub other_button: u16,
+}
+
+/// Mouse wheel scroll, in lines or pixels
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryWheelEvent {
+    pub is_line_delta: bool,
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Modifier keys held
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryModifiers {
+    pub shift: bool,
+    pub control: bool,
+    pub alt: bool,
+    pub super_key: bool,
+}
+
+/// Event payload, read the field named by `WryEvent::kind`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union WryEventData {
+    pub size: WrySize,
+    pub position: WryPosition,
+    pub focused: bool,
+    pub key: WryKeyEvent,
+    pub modifiers: WryModifiers,
+    pub cursor: WryPoint,
+    pub mouse: WryMouseEvent,
+    pub wheel: WryWheelEvent,
+    pub scale_factor: f64,
+    pub is_dark: bool,
+    /// Set for kinds without a payload
+    pub none: u8,
+}
+
+/// Event passed to a `TypedEventLoopCallback`
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct WryEvent {
+    pub kind: WryEventKind,
+    /// Window the event belongs to, formatted as in JSON events; null for app events
+    pub window_id: *const c_char,
+    /// Serialized event for `Other`, null otherwise
+    pub json: *const c_char,
+    pub data: WryEventData,
+}
+
+/// Screen rectangle in physical pixels
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WryRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Kind of caching applied to custom protocol responses
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryCacheControlKind {
+    /// Always call the handler
+    None = 0,
+    /// Reuse responses for `max_age_seconds`
+    MaxAgeSeconds = 1,
+    /// Reuse responses for the lifetime of the app
+    Immutable = 2,
+}
+
+/// Caching policy for a custom protocol
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryCacheControl {
+    pub kind: WryCacheControlKind,
+    /// Only used with `MaxAgeSeconds`
+    pub max_age_seconds: u32,
+}
+
+impl Default for WryCacheControl {
+    fn default() -> Self {
+        Self {
+            kind: WryCacheControlKind::None,
+            max_age_seconds: 0,
+        }
+    }
+}
+
+/// HTTP header added to custom protocol responses
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryCustomProtocolHeader {
+    pub name: *const c_char,
+    pub value: *const c_char,
+}
+
+/// Version details from `wry_version_info`
+///
+/// Every pointer is a static, null-terminated string owned by the library;
+/// do not free. Fields that cannot be determined read "unknown".
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryVersionInfo {
+    /// Version of this library, as in `wry_version`
+    pub ffi_crate_version: *const c_char,
+    /// WebView2, WebKit or WebKitGTK version reported by the engine
+    pub webview_engine_version: *const c_char,
+    /// Version of the bundled tao windowing library
+    pub tao_version: *const c_char,
+    /// Operating system name and version
+    pub os_version: *const c_char,
+    /// `WRY_FFI_BUILD_DATE` from the build environment
+    pub build_date: *const c_char,
+}
+
+/// Process memory figures from `wry_app_memory_usage`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WryMemoryInfo {
+    /// Resident set (working set) of this process
+    pub rss_bytes: u64,
+    /// Virtual address space in use by this process
+    pub virtual_bytes: u64,
+    /// Resident memory of the engine's helper processes, 0 if unknown
+    pub webview_bytes: u64,
+}
+
+/// Kind of content a window accepts drops of, see
+/// `wry_window_set_drag_accept_types`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryDragType {
+    /// Files of any type, including images
+    Files = 0,
+    /// Plain text
+    Text = 1,
+    /// Links
+    Url = 2,
+    /// Image files
+    Image = 3,
+}
+
+/// Data offered to other applications by `wry_drag_item`
+///
+/// Every field may be null or zero, but at least one of `text`, `url` and
+/// `file_paths` must be set. `image_rgba` holds `image_width *
+/// image_height * 4` bytes of straight RGBA shown under the cursor.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryDragData {
+    pub text: *const c_char,
+    pub url: *const c_char,
+    pub file_paths: *const *const c_char,
+    pub file_count: usize,
+    pub image_rgba: *const u8,
+    pub image_len: usize,
+    pub image_width: u32,
+    pub image_height: u32,
+}
+
+/// Cookie passed to `wry_webview_set_cookie`
+///
+/// `domain` and `path` may be null. An `expires_secs` of 0 makes a session
+/// cookie; otherwise it is a Unix timestamp in seconds.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryCookie {
+    pub name: *const c_char,
+    pub value: *const c_char,
+    pub domain: *const c_char,
+    pub path: *const c_char,
+    pub expires_secs: i64,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
 /// Result type for FFI operations
 #[repr(C)]
 pub struct WryResult {
@@ -142,10 +466,288 @@ pub enum WryErrorCode {
     Unknown = 255,
 }
 
+/// Event loop control flow requested by the host
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryEventLoopControlFlow {
+    /// Keep running without waiting for new events
+    Poll = 0,
+    /// Sleep until the next event arrives
+    Wait = 1,
+    /// Stop the event loop
+    Exit = 2,
+}
+
+/// System cursor shape, mirrors `tao::window::CursorIcon`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryCursorIcon {
+    Default = 0,
+    Crosshair = 1,
+    Hand = 2,
+    Arrow = 3,
+    Move = 4,
+    Text = 5,
+    Wait = 6,
+    Help = 7,
+    Progress = 8,
+    NotAllowed = 9,
+    ContextMenu = 10,
+    Cell = 11,
+    VerticalText = 12,
+    Alias = 13,
+    Copy = 14,
+    NoDrop = 15,
+    Grab = 16,
+    Grabbing = 17,
+    AllScroll = 18,
+    ZoomIn = 19,
+    ZoomOut = 20,
+    EResize = 21,
+    NResize = 22,
+    NeResize = 23,
+    NwResize = 24,
+    SResize = 25,
+    SeResize = 26,
+    SwResize = 27,
+    WResize = 28,
+    EwResize = 29,
+    NsResize = 30,
+    NeswResize = 31,
+    NwseResize = 32,
+    ColResize = 33,
+    RowResize = 34,
+}
+
+/// Window theme preference
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryWindowTheme {
+    /// Follow the system theme
+    Unspecified = 0,
+    Light = 1,
+    Dark = 2,
+}
+
+/// macOS title bar presets for `wry_window_set_titlebar_style`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryTitlebarStyle {
+    /// Standard opaque title bar
+    Default = 0,
+    /// Content extends under a transparent title bar, title text hidden
+    Hidden = 1,
+    /// Like `Hidden` with the window buttons inset from the corner
+    HiddenInset = 2,
+    /// Content extends under a transparent title bar, title text shown
+    FullSizeContent = 3,
+    /// Title bar merged with a compact toolbar
+    UnifiedCompact = 4,
+}
+
+/// What happened on an intercepted WebSocket
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryWebSocketEvent {
+    /// The page opened a connection
+    Open = 0,
+    /// The page sent a message
+    Message = 1,
+    /// The page closed the connection
+    Close = 2,
+}
+
+/// Edit menu command for `wry_webview_execute_command`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryEditorCommand {
+    Cut = 0,
+    Copy = 1,
+    Paste = 2,
+    SelectAll = 3,
+    Undo = 4,
+    Redo = 5,
+    /// Delete the selection
+    Delete = 6,
+    Bold = 7,
+    Italic = 8,
+    Underline = 9,
+}
+
+/// Window edge or corner grabbed by `wry_window_resize_begin`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryResizeDirection {
+    East = 0,
+    North = 1,
+    NorthEast = 2,
+    NorthWest = 3,
+    South = 4,
+    SouthEast = 5,
+    SouthWest = 6,
+    West = 7,
+}
+
+/// Optional feature queried with `wry_ffi_feature_supported`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryFeatureKind {
+    /// Application and window menus
+    Menu = 0,
+    /// System tray icons
+    Tray = 1,
+    /// Desktop notifications
+    Notifications = 2,
+    /// Keyboard shortcuts that work while the app is in the background
+    GlobalShortcuts = 3,
+    /// Clipboard access from the host
+    Clipboard = 4,
+    /// File system change notifications
+    FileWatcher = 5,
+    /// Transparent windows and webviews
+    Transparency = 6,
+    /// `wry_window_set_cursor_icon_rgba`
+    CustomCursor = 7,
+    /// `WryWindowParams::proxy`
+    Proxy = 8,
+    /// `wry_webview_set_cors_bypass`
+    CorsBypass = 9,
+    /// `wry_webview_get_pdf`
+    PdfExport = 10,
+    /// `wry_webview_get_snapshot`
+    Snapshot = 11,
+    /// `wry_webview_set_ssl_error_handler`
+    SslErrorHandler = 12,
+    /// `wry_webview_set_media_permissions`
+    MediaPermissions = 13,
+    /// `wry_jump_list_set` and `wry_thumbnail_toolbar_set_buttons`
+    Taskbar = 14,
+    /// Window tabs and sheets
+    WindowTabs = 15,
+}
+
+/// Page loading state of a webview
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryLoadingState {
+    /// No navigation in progress
+    Idle = 0,
+    /// A page has started loading but not finished
+    Loading = 1,
+    /// The last navigation could not be started
+    Failed = 2,
+}
+
+/// Permissions granted to web content without prompting the user
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WryMediaPermissions {
+    pub allow_microphone: bool,
+    pub allow_camera: bool,
+    pub allow_geolocation: bool,
+    pub allow_notifications: bool,
+}
+
+/// How urgently a screen reader should speak an announcement
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryAnnouncementPoliteness {
+    /// Spoken after the current speech finishes
+    Polite = 0,
+    /// Interrupts the current speech
+    Assertive = 1,
+}
+
+/// Kind of entry in a Windows Jump List
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WryJumpListItemKind {
+    /// Launches `program` with `args`
+    Task = 0,
+    /// Divider line between tasks
+    Separator = 1,
+}
+
+/// Entry in the Tasks section of a Windows Jump List
+///
+/// Only `kind` is read for separators. For tasks `title` is required; the
+/// other strings may be null, and a null `program` launches the current
+/// executable.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryJumpListItem {
+    pub kind: WryJumpListItemKind,
+    pub title: *const c_char,
+    pub description: *const c_char,
+    pub program: *const c_char,
+    pub args: *const c_char,
+    pub icon_path: *const c_char,
+}
+
+/// Button in a window's taskbar thumbnail toolbar (Windows only)
+///
+/// `tooltip` and `icon_rgba` may be null. When set, `icon_rgba` must hold
+/// exactly `icon_width * icon_height * 4` bytes. `id` is reported back in
+/// the `thumbnail-button` event when the button is clicked.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct WryThumbnailButton {
+    pub tooltip: *const c_char,
+    pub icon_rgba: *const u8,
+    pub icon_len: usize,
+    pub icon_width: u32,
+    pub icon_height: u32,
+    pub id: u32,
+    pub enabled: bool,
+}
+
 // ============================================================================
 // Callback Types
 // ============================================================================
 
+/// Called in the primary instance when another instance is launched
+pub type SingleInstanceCallback = extern "C" fn(args_json: *const c_char, user_data: *mut c_void);
+
+/// Called for each URL the app is asked to open
+pub type UrlHandler = Option<unsafe extern "C" fn(url: *const c_char, user_data: *mut c_void)>;
+
+/// Receives the JSON result of an async script, or a JSON error message if it threw
+pub type AsyncJsCallback = Option<
+    unsafe extern "C" fn(result_json: *const c_char, success: bool, user_data: *mut c_void),
+>;
+
+/// Receives exported PDF bytes, or null and 0 if the export failed
+pub type PdfCallback =
+    Option<unsafe extern "C" fn(pdf_data: *const u8, len: usize, user_data: *mut c_void)>;
+
+/// Receives a snapshot as straight RGBA pixels, or null and zeros if the capture failed
+pub type SnapshotCallback = Option<
+    unsafe extern "C" fn(
+        rgba: *const u8,
+        len: usize,
+        width: u32,
+        height: u32,
+        user_data: *mut c_void,
+    ),
+>;
+
+/// Called for TLS certificate errors, returns true to proceed with the connection
+pub type SslErrorHandler = Option<
+    unsafe extern "C" fn(url: *const c_char, error_code: i32, user_data: *mut c_void) -> bool,
+>;
+
+/// Called for activity on a WebSocket intercepted by `wry_webview_intercept_websocket`
+///
+/// `data` is the URL for `Open`, the text for `Message` and null for `Close`.
+pub type WebSocketHandler = Option<
+    unsafe extern "C" fn(
+        connection_id: u32,
+        event: WryWebSocketEvent,
+        data: *const c_char,
+        user_data: *mut c_void,
+    ),
+>;
+
 /// Called when webview sends a message to backend
 pub type WebMessageCallback = extern "C" fn(
     window: WryWindow,
@@ -183,6 +785,9 @@ pub type WindowMovedCallback =
 pub type WindowFocusCallback =
     extern "C" fn(window: WryWindow, focused: bool, user_data: *mut c_void);
 
+/// Called when a window's effective appearance switches between light and dark
+pub type AppearanceCallback = Option<unsafe extern "C" fn(is_dark: bool, user_data: *mut c_void)>;
+
 /// Called when navigation starts (return false to cancel)
 pub type NavigationCallback = extern "C" fn(
     window: WryWindow,
@@ -192,3 +797,18 @@ pub type NavigationCallback = extern "C" fn(
 
 /// Callback for UI thread invocation
 pub type InvokeCallback = extern "C" fn(user_data: *mut c_void);
+
+/// Called for each event while pumping the event loop, serialized as a JSON string
+pub type EventLoopCallback = extern "C" fn(
+    event_json: *const c_char,
+    user_data: *mut c_void,
+) -> WryEventLoopControlFlow;
+
+/// Typed alternative to `EventLoopCallback`, the event is only valid during the call
+pub type TypedEventLoopCallback = extern "C" fn(
+    event: *const WryEvent,
+    user_data: *mut c_void,
+) -> WryEventLoopControlFlow;
+
+/// Called for app-level events, serialized as a JSON string
+pub type AppEventCallback = extern "C" fn(event_json: *const c_char, user_data: *mut c_void);
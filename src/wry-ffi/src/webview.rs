@@ -2,12 +2,31 @@
 //!
 //! Navigation, script execution, and webview control.
 
-use std::ffi::c_char;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::{c_char, CString};
+use std::os::raw::c_void;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 
+use once_cell::sync::Lazy;
+
+use tao::event::{ElementState, WindowEvent};
+use tao::event_loop::EventLoopProxy;
+use tao::keyboard::ModifiersState;
+use tao::window::WindowId;
+
+use crate::app::UserEvent;
+use crate::callbacks::set_global_ssl_error_handler;
 use crate::error::{error_result, set_last_error};
 use crate::string::{c_str_to_string, string_to_c_string};
-use crate::types::{WryErrorCode, WryResult, WryWindow};
-use crate::window::get_window_state;
+use crate::types::{
+    AsyncJsCallback, PdfCallback, SnapshotCallback, SslErrorHandler, WebSocketHandler, WryCookie,
+    WryEditorCommand, WryErrorCode, WryLoadingState, WryMediaPermissions, WryResult,
+    WryWebSocketEvent, WryWindow,
+};
+use crate::window::{get_window_state, get_window_state_mut, WindowState};
 
 // ============================================================================
 // FFI Functions
@@ -16,7 +35,7 @@ use crate::window::get_window_state;
 /// Navigate to URL
 #[no_mangle]
 pub unsafe extern "C" fn wry_webview_navigate(window: WryWindow, url: *const c_char) -> WryResult {
-    let state = match get_window_state(window) {
+    let state = match get_window_state_mut(window) {
         Some(s) => s,
         None => return error_result(WryErrorCode::InvalidHandle, "Invalid window handle"),
     };
@@ -35,7 +54,10 @@ pub unsafe extern "C" fn wry_webview_navigate(window: WryWindow, url: *const c_c
 
     match webview.load_url(&url) {
         Ok(()) => WryResult::ok(),
-        Err(e) => error_result(WryErrorCode::NavigationFailed, format!("Navigation failed: {}", e)),
+        Err(e) => {
+            state.loading_state = WryLoadingState::Failed;
+            error_result(WryErrorCode::NavigationFailed, format!("Navigation failed: {}", e))
+        }
     }
 }
 
@@ -45,7 +67,7 @@ pub unsafe extern "C" fn wry_webview_load_html(
     window: WryWindow,
     html: *const c_char,
 ) -> WryResult {
-    let state = match get_window_state(window) {
+    let state = match get_window_state_mut(window) {
         Some(s) => s,
         None => return error_result(WryErrorCode::InvalidHandle, "Invalid window handle"),
     };
@@ -64,7 +86,10 @@ pub unsafe extern "C" fn wry_webview_load_html(
 
     match webview.load_html(&html) {
         Ok(()) => WryResult::ok(),
-        Err(e) => error_result(WryErrorCode::NavigationFailed, format!("Failed to load HTML: {}", e)),
+        Err(e) => {
+            state.loading_state = WryLoadingState::Failed;
+            error_result(WryErrorCode::NavigationFailed, format!("Failed to load HTML: {}", e))
+        }
     }
 }
 
@@ -97,6 +122,98 @@ pub unsafe extern "C" fn wry_webview_evaluate_script(
     }
 }
 
+/// Execute JavaScript inside the first iframe with the given `name`
+///
+/// The script runs through the frame's `eval`, so the frame must be
+/// same-origin with the main document. A missing or cross-origin frame is
+/// reported to the page console; the call itself still succeeds.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_evaluate_script_in_frame(
+    window: WryWindow,
+    frame_name: *const c_char,
+    script: *const c_char,
+) -> WryResult {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return error_result(WryErrorCode::InvalidHandle, "Invalid window handle"),
+    };
+
+    let frame_name = match c_str_to_string(frame_name) {
+        Some(s) if !s.is_empty() => s,
+        _ => return error_result(WryErrorCode::InvalidParameter, "Null or empty frame name"),
+    };
+
+    let script = match c_str_to_string(script) {
+        Some(s) => s,
+        None => return error_result(WryErrorCode::InvalidParameter, "Null or invalid script"),
+    };
+
+    log::debug!("Evaluating script in frame '{}' ({} bytes)", frame_name, script.len());
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => return error_result(WryErrorCode::WebviewCreationFailed, "No webview available"),
+    };
+
+    // Compare names in JS rather than building a selector so no escaping is needed
+    let wrapped = format!(
+        r#"(function() {{
+    var name = {};
+    var frame = Array.prototype.find.call(document.querySelectorAll('iframe'), function(f) {{ return f.name === name; }});
+    if (!frame) {{ console.error('wry: no iframe named ' + name); return; }}
+    try {{ frame.contentWindow.eval({}); }} catch (e) {{ console.error('wry: script in iframe ' + name + ' failed', e); }}
+}})();"#,
+        serde_json::Value::String(frame_name),
+        serde_json::Value::String(script)
+    );
+
+    match webview.evaluate_script(&wrapped) {
+        Ok(()) => WryResult::ok(),
+        Err(e) => error_result(WryErrorCode::ScriptError, format!("Script execution failed: {}", e)),
+    }
+}
+
+/// Deliver dropped file paths to the page's JavaScript
+///
+/// `paths_json` is a JSON array of path strings. The page receives a
+/// `wry-file-drop` event on `window` with the paths in `event.detail.paths`,
+/// e.g. after forwarding a `window-dropped-file` event from the host.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_handle_drop(
+    window: WryWindow,
+    paths_json: *const c_char,
+) -> WryResult {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return error_result(WryErrorCode::InvalidHandle, "Invalid window handle"),
+    };
+
+    let paths: Vec<String> = match c_str_to_string(paths_json).map(|j| serde_json::from_str(&j)) {
+        Some(Ok(paths)) => paths,
+        _ => {
+            return error_result(
+                WryErrorCode::InvalidParameter,
+                "Paths must be a JSON array of strings",
+            )
+        }
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => return error_result(WryErrorCode::WebviewCreationFailed, "No webview available"),
+    };
+
+    log::debug!("Delivering {} dropped path(s) to webview", paths.len());
+    let script = format!(
+        "window.dispatchEvent(new CustomEvent('wry-file-drop', {{ detail: {{ paths: {} }} }}));",
+        serde_json::Value::from(paths)
+    );
+    match webview.evaluate_script(&script) {
+        Ok(()) => WryResult::ok(),
+        Err(e) => error_result(WryErrorCode::ScriptError, format!("Failed to deliver drop: {}", e)),
+    }
+}
+
 /// Send message to JavaScript (calls window.tauri.__receive)
 ///
 /// This function is thread-safe - it dispatches via the event loop.
@@ -171,6 +288,158 @@ pub unsafe extern "C" fn wry_webview_set_zoom(window: WryWindow, zoom: f64) {
     }
 }
 
+/// Show or hide the webview, tracking the state for `wry_webview_is_visible`
+unsafe fn set_webview_visible(window: WryWindow, visible: bool) -> WryResult {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return error_result(WryErrorCode::InvalidHandle, "Invalid window handle"),
+    };
+
+    log::debug!("Setting webview visible: {}", visible);
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => return error_result(WryErrorCode::WebviewCreationFailed, "No webview available"),
+    };
+
+    match webview.set_visible(visible) {
+        Ok(()) => {
+            state.webview_visible = visible;
+            WryResult::ok()
+        }
+        Err(e) => error_result(WryErrorCode::Unknown, format!("Failed to set visibility: {}", e)),
+    }
+}
+
+/// Show the webview
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_show(window: WryWindow) -> WryResult {
+    set_webview_visible(window, true)
+}
+
+/// Hide the webview, leaving the window visible
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_hide(window: WryWindow) -> WryResult {
+    set_webview_visible(window, false)
+}
+
+/// Check if the webview is visible
+///
+/// wry has no getter, so this reports the last state set with
+/// `wry_webview_show` / `wry_webview_hide` (visible by default).
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_is_visible(window: WryWindow) -> bool {
+    match get_window_state(window) {
+        Some(state) => state.webview.is_some() && state.webview_visible,
+        None => false,
+    }
+}
+
+/// Move keyboard focus to the webview
+///
+/// # Returns
+/// false if the window has no webview or focus could not be moved
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_focus(window: WryWindow) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    log::debug!("Focusing webview");
+    match webview.focus() {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Failed to focus webview: {}", e));
+            false
+        }
+    }
+}
+
+/// Check if the webview has keyboard focus
+///
+/// Asks the web engine rather than the window, so this is false while focus
+/// is on native content beside the webview or the window is in the background.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_is_focused(window: WryWindow) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    match &state.webview {
+        Some(webview) => webview_has_focus(webview),
+        None => false,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn webview_has_focus(webview: &wry::WebView) -> bool {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetFocus;
+    use windows::Win32::UI::WindowsAndMessaging::IsChild;
+    use wry::WebViewExtWindows;
+
+    // WebView2 takes focus in browser-owned child windows of its parent
+    let mut parent = HWND::default();
+    unsafe {
+        if webview.controller().ParentWindow(&mut parent).is_err() {
+            return false;
+        }
+        let focus = GetFocus();
+        !focus.is_invalid() && IsChild(parent, focus).as_bool()
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn webview_has_focus(webview: &wry::WebView) -> bool {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use wry::WebViewExtMacOS;
+
+    let wk_webview = webview.webview();
+    unsafe {
+        let ns_window: *mut AnyObject = msg_send![&*wk_webview, window];
+        if ns_window.is_null() {
+            return false;
+        }
+        let key: bool = msg_send![ns_window, isKeyWindow];
+        let responder: *mut AnyObject = msg_send![ns_window, firstResponder];
+        if !key || responder.is_null() {
+            return false;
+        }
+
+        // The responder may be the WKWebView or one of its internal views
+        let is_view: bool = msg_send![responder, isKindOfClass: class!(NSView)];
+        if !is_view {
+            return false;
+        }
+        msg_send![responder, isDescendantOf: &*wk_webview]
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn webview_has_focus(webview: &wry::WebView) -> bool {
+    use gtk::prelude::WidgetExt;
+    use wry::WebViewExtUnix;
+
+    // True only while the toplevel is active and the webview is its focus widget
+    webview.webview().has_focus()
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+fn webview_has_focus(_webview: &wry::WebView) -> bool {
+    false
+}
+
 /// Open devtools (if enabled)
 #[no_mangle]
 pub unsafe extern "C" fn wry_webview_open_devtools(window: WryWindow) {
@@ -207,3 +476,2755 @@ pub unsafe extern "C" fn wry_webview_close_devtools(window: WryWindow) {
 
     webview.close_devtools();
 }
+
+/// Get whether the webview is currently loading a page
+///
+/// Returns `Failed` when the last navigation could not be started, until the
+/// next page load begins.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_get_loading_state(window: WryWindow) -> WryLoadingState {
+    match get_window_state(window) {
+        Some(state) => state.loading_state,
+        None => WryLoadingState::Idle,
+    }
+}
+
+/// Add a script that runs at the start of every page load
+///
+/// Scripts are registered with the engine to run as each new document is
+/// created, before any page JavaScript, and are also evaluated immediately
+/// in the current page. They run in the top-level frame only.
+///
+/// # Returns
+/// Script ID for `wry_webview_remove_init_script`, or 0 on failure
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_add_init_script(
+    window: WryWindow,
+    script: *const c_char,
+) -> u32 {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return 0,
+    };
+
+    let script = match c_str_to_string(script) {
+        Some(s) => s,
+        None => {
+            set_last_error("Null or invalid script");
+            return 0;
+        }
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return 0;
+    }
+
+    match state.add_init_script(script) {
+        Ok(id) => {
+            log::debug!("Init script {} added", id);
+            id
+        }
+        Err(e) => {
+            set_last_error(e);
+            0
+        }
+    }
+}
+
+/// Load an ES module on every page, importable as `module_name`
+///
+/// The module source is served from a Blob URL and mapped to `module_name`
+/// with an import map, so page modules can `import ... from "module_name"`.
+/// The URL is also kept in `window.__wryModules[module_name]` for dynamic
+/// `import()`. All of a window's modules share a single import map, added
+/// as each document is created so it precedes the page's own module
+/// scripts. The module is also loaded into the current page, but a page
+/// that has already run a module script ignores its import map. Pages whose
+/// Content-Security-Policy forbids `blob:` scripts will not load it.
+///
+/// # Returns
+/// Module ID for `wry_webview_remove_init_script`, or 0 on failure
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_add_preload_module(
+    window: WryWindow,
+    module_js: *const c_char,
+    module_name: *const c_char,
+) -> u32 {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return 0,
+    };
+
+    let module_js = c_str_to_string(module_js);
+    let module_name = c_str_to_string(module_name);
+    let (module_js, module_name) = match (module_js, module_name) {
+        (Some(js), Some(name)) if !name.is_empty() => (js, name),
+        _ => {
+            set_last_error("Null or invalid module source or name");
+            return 0;
+        }
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return 0;
+    }
+
+    if state.preload_modules.iter().any(|m| m.name == module_name) {
+        set_last_error(format!("Preload module '{}' was already added", module_name));
+        return 0;
+    }
+
+    // Module IDs share the init script ID space so either can be removed
+    // with wry_webview_remove_init_script
+    let id = state.next_init_script_id;
+    state.next_init_script_id += 1;
+    state.preload_modules.push(PreloadModule {
+        id,
+        name: module_name.clone(),
+        source: module_js,
+    });
+
+    if let Err(e) = update_preload_module_script(state) {
+        set_last_error(e);
+        state.preload_modules.retain(|m| m.id != id);
+        let _ = update_preload_module_script(state);
+        return 0;
+    }
+
+    log::debug!("Preload module '{}' added as {}", module_name, id);
+    id
+}
+
+/// An ES module added with `wry_webview_add_preload_module`
+#[derive(Debug, Clone)]
+pub struct PreloadModule {
+    pub id: u32,
+    /// Bare specifier the module is imported by
+    pub name: String,
+    pub source: String,
+}
+
+/// Define the given modules and map them in one import map, skipping any
+/// the page already has
+const PRELOAD_MODULES_SCRIPT: &str = r#"(function(entries) {
+    var modules = window.__wryModules = window.__wryModules || {};
+    var imports = {};
+    var urls = [];
+    entries.forEach(function(entry) {
+        if (modules[entry[0]]) return;
+        var url = URL.createObjectURL(new Blob([entry[1]], { type: 'text/javascript' }));
+        modules[entry[0]] = url;
+        imports[entry[0]] = url;
+        urls.push(url);
+    });
+    if (!urls.length) return;
+    var root = document.head || document.documentElement;
+    var anchor = root.firstChild;
+    var map = document.createElement('script');
+    map.type = 'importmap';
+    map.textContent = JSON.stringify({ imports: imports });
+    root.insertBefore(map, anchor);
+    urls.forEach(function(url) {
+        var module = document.createElement('script');
+        module.type = 'module';
+        module.src = url;
+        root.insertBefore(module, anchor);
+    });
+})"#;
+
+/// Reinstall the preload module init script for the current
+/// `state.preload_modules`
+fn update_preload_module_script(state: &mut WindowState) -> Result<(), String> {
+    if let Some(id) = state.preload_module_script.take() {
+        state.remove_init_script(id);
+    }
+    if state.preload_modules.is_empty() {
+        return Ok(());
+    }
+
+    let entries: Vec<serde_json::Value> = state
+        .preload_modules
+        .iter()
+        .map(|m| serde_json::json!([m.name, m.source]))
+        .collect();
+    let script = format!("{}({});", PRELOAD_MODULES_SCRIPT, serde_json::Value::from(entries));
+    state.preload_module_script = Some(state.add_dom_init_script(&script)?);
+    Ok(())
+}
+
+/// Stop running a script added with `wry_webview_add_init_script` or
+/// `wry_webview_add_preload_module` on later pages
+///
+/// Effects the script already had on the current page are not undone.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_remove_init_script(window: WryWindow, id: u32) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if let Some(index) = state.preload_modules.iter().position(|m| m.id == id) {
+        let module = state.preload_modules.remove(index);
+        log::debug!("Preload module '{}' removed", module.name);
+        if let Err(e) = update_preload_module_script(state) {
+            set_last_error(format!("Failed to reinstall the remaining modules: {}", e));
+            return false;
+        }
+        return true;
+    }
+
+    if !state.remove_init_script(id) {
+        set_last_error(format!("No init script with id {}", id));
+        return false;
+    }
+
+    log::debug!("Init script {} removed", id);
+    true
+}
+
+/// Relax same-origin restrictions for local development
+///
+/// Lets file:// pages read any origin and other file:// URLs. Linux only;
+/// the other platform webviews have no setting for this after creation and
+/// fail with "Not supported on this platform". Servers must still send CORS
+/// headers for cross-origin fetches from non-file pages.
+///
+/// Only available in debug builds unless the `cors-bypass` feature is enabled.
+#[cfg(any(debug_assertions, feature = "cors-bypass"))]
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_cors_bypass(window: WryWindow, bypass: bool) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    #[cfg(not(debug_assertions))]
+    if bypass {
+        log::warn!("CORS bypass enabled in a release build; do not ship this to users");
+    }
+
+    if !set_cors_bypass(webview, bypass) {
+        return false;
+    }
+    log::debug!("CORS bypass {}", if bypass { "enabled" } else { "disabled" });
+    true
+}
+
+#[cfg(all(target_os = "linux", any(debug_assertions, feature = "cors-bypass")))]
+fn set_cors_bypass(webview: &wry::WebView, bypass: bool) -> bool {
+    use webkit2gtk::{SettingsExt, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    match WebViewExt::settings(&webview.webview()) {
+        Some(settings) => {
+            settings.set_allow_universal_access_from_file_urls(bypass);
+            settings.set_allow_file_access_from_file_urls(bypass);
+            true
+        }
+        None => {
+            set_last_error("Webview has no settings");
+            false
+        }
+    }
+}
+
+#[cfg(all(not(target_os = "linux"), any(debug_assertions, feature = "cors-bypass")))]
+fn set_cors_bypass(_webview: &wry::WebView, _bypass: bool) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+/// Set how fast the text caret blinks in the webview
+///
+/// `rate_ms` is the length of a full on/off cycle. 0 stops blinking and
+/// `u32::MAX` restores the system default.
+///
+/// Linux only. This sets the GTK cursor blink settings, which are
+/// process-wide, so the rate applies to every webview and GTK widget in the
+/// process rather than just this window. WebView2 and WKWebView follow the
+/// system caret settings and cannot be overridden, so other platforms return
+/// false.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_cursor_blink_rate(
+    window: WryWindow,
+    rate_ms: u32,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    if !set_cursor_blink_rate(rate_ms) {
+        return false;
+    }
+
+    log::debug!("Cursor blink rate set to {} ms", rate_ms);
+    true
+}
+
+#[cfg(target_os = "linux")]
+fn set_cursor_blink_rate(rate_ms: u32) -> bool {
+    use gtk::prelude::GtkSettingsExt;
+
+    let settings = match gtk::Settings::default() {
+        Some(s) => s,
+        None => {
+            set_last_error("No GTK settings available");
+            return false;
+        }
+    };
+
+    if rate_ms == u32::MAX {
+        settings.reset_property("gtk-cursor-blink");
+        settings.reset_property("gtk-cursor-blink-time");
+    } else {
+        settings.set_gtk_cursor_blink(rate_ms != 0);
+        if rate_ms != 0 {
+            settings.set_gtk_cursor_blink_time(rate_ms.min(i32::MAX as u32) as i32);
+        }
+    }
+    true
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_cursor_blink_rate(_rate_ms: u32) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+/// Suppress the engine's context menu; the flag lets a later call lift it
+const BLOCK_CONTEXT_MENU_SCRIPT: &str = r#"(function() {
+    window.__wryBlockContextMenu = true;
+    if (window.__wryContextMenuHooked) return;
+    window.__wryContextMenuHooked = true;
+    document.addEventListener('contextmenu', function(e) {
+        if (window.__wryBlockContextMenu) e.preventDefault();
+    }, true);
+})();"#;
+
+/// Block or restore the default context menu, tracking the init script in `state`
+pub(crate) fn set_context_menu_blocked(
+    state: &mut WindowState,
+    blocked: bool,
+) -> Result<(), String> {
+    match (blocked, state.context_menu_script) {
+        (true, None) => {
+            let id = state.add_init_script(BLOCK_CONTEXT_MENU_SCRIPT.to_string())?;
+            state.context_menu_script = Some(id);
+        }
+        (false, Some(id)) => {
+            state.remove_init_script(id);
+            state.context_menu_script = None;
+            if let Some(webview) = &state.webview {
+                if let Err(e) = webview.evaluate_script("window.__wryBlockContextMenu = false;") {
+                    log::warn!("Failed to restore context menu: {}", e);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Enable or disable the engine's built-in right-click menu
+///
+/// Disabling it stops the page from showing Reload, Inspect and similar
+/// entries, e.g. for kiosks. Pages can still show their own menus. Also
+/// settable at creation with `WryWindowParams::default_context_menu_enabled`.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_default_context_menu_enabled(
+    window: WryWindow,
+    enabled: bool,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    if let Err(e) = set_context_menu_blocked(state, !enabled) {
+        set_last_error(e);
+        return false;
+    }
+    log::debug!("Default context menu {}", if enabled { "enabled" } else { "disabled" });
+    true
+}
+
+/// Stylesheet preventing text selection outside editable fields
+const BLOCK_TEXT_SELECTION_SCRIPT: &str = r#"(function() {
+    if (document.querySelector('style[data-wry-no-select]')) return;
+    var style = document.createElement('style');
+    style.setAttribute('data-wry-no-select', '');
+    style.textContent = 'html { -webkit-user-select: none; user-select: none; } ' +
+        'input, textarea, [contenteditable] { -webkit-user-select: text; user-select: text; }';
+    (document.head || document.documentElement).appendChild(style);
+})();"#;
+
+/// Remove the stylesheet installed by `BLOCK_TEXT_SELECTION_SCRIPT`
+const RESTORE_TEXT_SELECTION_SCRIPT: &str =
+    "document.querySelectorAll('style[data-wry-no-select]').forEach(function(s) { s.remove(); });";
+
+/// Block or restore text selection, tracking the init script in `state`
+pub(crate) fn set_text_selection_blocked(
+    state: &mut WindowState,
+    blocked: bool,
+) -> Result<(), String> {
+    match (blocked, state.text_selection_script) {
+        (true, None) => {
+            let id = state.add_dom_init_script(BLOCK_TEXT_SELECTION_SCRIPT)?;
+            state.text_selection_script = Some(id);
+        }
+        (false, Some(id)) => {
+            state.remove_init_script(id);
+            state.text_selection_script = None;
+            if let Some(webview) = &state.webview {
+                if let Err(e) = webview.evaluate_script(RESTORE_TEXT_SELECTION_SCRIPT) {
+                    log::warn!("Failed to restore text selection: {}", e);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Allow or prevent selecting text in the page
+///
+/// Disallowing selection keeps kiosk users from selecting and copying page
+/// text; input fields stay selectable so they can still be edited. Also
+/// settable at creation with `WryWindowParams::allow_text_selection`.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_allow_text_selection(
+    window: WryWindow,
+    allow: bool,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    if let Err(e) = set_text_selection_blocked(state, !allow) {
+        set_last_error(e);
+        return false;
+    }
+    log::debug!("Text selection {}", if allow { "allowed" } else { "prevented" });
+    true
+}
+
+/// Viewport and touch settings that stop pinch, double-tap and Ctrl zooming
+const DISABLE_ZOOM_SCRIPT: &str = r#"(function() {
+    if (window.__wryZoomDisabled) return;
+    window.__wryZoomDisabled = true;
+    function apply() {
+        var head = document.head || document.documentElement;
+        if (!head || document.querySelector('style[data-wry-no-zoom]')) return;
+        var meta = document.querySelector('meta[name="viewport"]');
+        if (!meta) {
+            meta = document.createElement('meta');
+            meta.setAttribute('name', 'viewport');
+            head.appendChild(meta);
+        }
+        meta.setAttribute('content',
+            'width=device-width,initial-scale=1,maximum-scale=1,user-scalable=no');
+        var style = document.createElement('style');
+        style.setAttribute('data-wry-no-zoom', '');
+        style.textContent = 'html { touch-action: pan-x pan-y; }';
+        head.appendChild(style);
+    }
+    apply();
+    document.addEventListener('DOMContentLoaded', apply);
+    document.addEventListener('wheel', function(e) {
+        if (e.ctrlKey) e.preventDefault();
+    }, { passive: false, capture: true });
+    document.addEventListener('keydown', function(e) {
+        if ((e.ctrlKey || e.metaKey) && ['+', '-', '=', '0'].indexOf(e.key) !== -1) {
+            e.preventDefault();
+        }
+    }, true);
+    document.addEventListener('gesturestart', function(e) { e.preventDefault(); }, true);
+})();"#;
+
+/// Install the zoom blocker once, tracking the init script in `state`
+pub(crate) fn disable_zoom(state: &mut WindowState) -> Result<(), String> {
+    if state.zoom_script.is_none() {
+        let id = state.add_init_script(DISABLE_ZOOM_SCRIPT.to_string())?;
+        state.zoom_script = Some(id);
+    }
+    Ok(())
+}
+
+/// Stop the user from zooming the page
+///
+/// Blocks pinch and double-tap zoom with a fixed viewport and
+/// `touch-action: pan-x pan-y` (touch scrolling still works), and cancels
+/// Ctrl+wheel and Ctrl/Cmd with +, - or 0. `wry_webview_set_zoom` still
+/// works. Lasts for the life of the webview; also settable at creation with
+/// `WryWindowParams::disable_zoom`.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_disable_zoom(window: WryWindow) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    if let Err(e) = disable_zoom(state) {
+        set_last_error(e);
+        return false;
+    }
+    log::debug!("Page zoom disabled");
+    true
+}
+
+/// Cancel clicks on `target="_blank"` links and report their URL over IPC
+const BLOCK_LINK_OPENING_SCRIPT: &str = r#"(function() {
+    window.__wryBlockLinkOpening = true;
+    if (window.__wryLinkOpeningHooked) return;
+    window.__wryLinkOpeningHooked = true;
+    document.addEventListener('click', function(e) {
+        if (!window.__wryBlockLinkOpening || e.defaultPrevented || !e.target.closest) return;
+        var link = e.target.closest('a[target="_blank"]');
+        if (!link || !link.href) return;
+        e.preventDefault();
+        if (window.ipc) window.ipc.postMessage(JSON.stringify({ __wryLinkBlocked: link.href }));
+    }, true);
+})();"#;
+
+/// Start of the IPC message reporting a blocked link
+const BLOCKED_LINK_PREFIX: &str = "{\"__wryLinkBlocked\":";
+
+/// Forward a link blocked by `wry_webview_set_allow_link_opening` to the app
+/// event callback
+///
+/// Returns false if `body` is not a blocked link report, so it should be
+/// passed on to the message callback.
+pub fn handle_blocked_link(
+    window_id: WindowId,
+    body: &str,
+    proxy: &EventLoopProxy<UserEvent>,
+) -> bool {
+    if !body.starts_with(BLOCKED_LINK_PREFIX) {
+        return false;
+    }
+
+    let message: serde_json::Value = match serde_json::from_str(body) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    if let Some(url) = message["__wryLinkBlocked"].as_str() {
+        log::debug!("Blocked link opening in window {:?}: {}", window_id, url);
+        let json = serde_json::json!({
+            "type": "webview-link-blocked",
+            "window_id": crate::events::window_id_string(window_id),
+            "url": url,
+        });
+        let _ = proxy.send_event(UserEvent::Custom(json.to_string()));
+    }
+    true
+}
+
+/// Allow or prevent `target="_blank"` links from opening a new window
+///
+/// When prevented, clicks on such links are cancelled and reported to the
+/// app event callback as
+/// `{"type":"webview-link-blocked","window_id":"...","url":"..."}`, so the
+/// host can navigate this webview or open the URL itself. Only link clicks
+/// are covered, not `window.open` calls from scripts.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_allow_link_opening(
+    window: WryWindow,
+    allow: bool,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    match (allow, state.link_opening_script) {
+        (false, None) => match state.add_init_script(BLOCK_LINK_OPENING_SCRIPT.to_string()) {
+            Ok(id) => state.link_opening_script = Some(id),
+            Err(e) => {
+                set_last_error(e);
+                return false;
+            }
+        },
+        (true, Some(id)) => {
+            if let Err(e) = webview.evaluate_script("window.__wryBlockLinkOpening = false;") {
+                log::warn!("Failed to restore link opening: {}", e);
+            }
+            state.remove_init_script(id);
+            state.link_opening_script = None;
+        }
+        _ => {}
+    }
+
+    log::debug!("Link opening {}", if allow { "allowed" } else { "prevented" });
+    true
+}
+
+/// Report clicks over IPC so focus can be handed back to the window
+const NO_FOCUS_ON_CLICK_SCRIPT: &str = r#"(function() {
+    window.__wryNoFocusOnClick = true;
+    if (window.__wryFocusOnClickHooked) return;
+    window.__wryFocusOnClickHooked = true;
+    document.addEventListener('mousedown', function() {
+        if (window.__wryNoFocusOnClick && window.ipc) {
+            window.ipc.postMessage('{"__wryFocusClick":true}');
+        }
+    }, true);
+})();"#;
+
+/// IPC message sent by `NO_FOCUS_ON_CLICK_SCRIPT`
+const FOCUS_CLICK_MESSAGE: &str = "{\"__wryFocusClick\":true}";
+
+/// Return focus to the window after a click when the webview should not
+/// take it
+///
+/// Returns false if `body` is not a click report, so it should be passed
+/// on to the message callback.
+pub fn handle_focus_click(
+    window_id: WindowId,
+    body: &str,
+    proxy: &EventLoopProxy<UserEvent>,
+) -> bool {
+    if body != FOCUS_CLICK_MESSAGE {
+        return false;
+    }
+    let _ = proxy.send_event(UserEvent::FocusParent(window_id));
+    true
+}
+
+/// Let clicks focus the webview or keep focus on the window, tracking the
+/// init script in `state`
+pub(crate) fn set_take_focus_on_click(
+    state: &mut WindowState,
+    take_focus: bool,
+) -> Result<(), String> {
+    match (take_focus, state.focus_on_click_script) {
+        (false, None) => {
+            let id = state.add_init_script(NO_FOCUS_ON_CLICK_SCRIPT.to_string())?;
+            state.focus_on_click_script = Some(id);
+        }
+        (true, Some(id)) => {
+            state.remove_init_script(id);
+            state.focus_on_click_script = None;
+            if let Some(webview) = &state.webview {
+                if let Err(e) = webview.evaluate_script("window.__wryNoFocusOnClick = false;") {
+                    log::warn!("Failed to restore focus on click: {}", e);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Choose whether clicking the webview gives it keyboard focus
+///
+/// When disabled, focus is handed back to the window right after each
+/// mouse press, so the click still reaches the page but key presses keep
+/// going to the window. Useful where a webview sits beside native content
+/// that should stay focused. Also settable at creation with
+/// `WryWindowParams::take_focus_on_click`.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_take_focus_on_click(
+    window: WryWindow,
+    take_focus: bool,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    if let Err(e) = set_take_focus_on_click(state, take_focus) {
+        set_last_error(e);
+        return false;
+    }
+    log::debug!("Take focus on click: {}", take_focus);
+    true
+}
+
+/// Set a handler deciding whether to proceed past TLS certificate errors
+///
+/// Intended for self-signed certificates during development. The handler
+/// gets the failing URL and a platform-specific error code: the
+/// `GTlsCertificateFlags` bits on Linux, the `COREWEBVIEW2_WEB_ERROR_STATUS`
+/// on Windows. Returning true trusts the certificate for its host and
+/// retries the load. Pass a null handler to reject errors again.
+///
+/// Not supported on macOS, where wry owns the navigation delegate that
+/// receives certificate challenges.
+///
+/// # Returns
+/// false if the platform hook could not be installed
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_ssl_error_handler(
+    window: WryWindow,
+    handler: SslErrorHandler,
+    user_data: *mut c_void,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    if !state.ssl_error_hook_installed {
+        if let Err(e) = install_ssl_error_hook(webview, state.id) {
+            set_last_error(e);
+            return false;
+        }
+        state.ssl_error_hook_installed = true;
+    }
+
+    state.callbacks.set_window_ptr(window);
+    set_global_ssl_error_handler(state.id, handler, user_data);
+    log::debug!("SSL error handler set for window {:?}", state.id);
+    true
+}
+
+#[cfg(target_os = "linux")]
+fn install_ssl_error_hook(webview: &wry::WebView, window_id: WindowId) -> Result<(), String> {
+    use webkit2gtk::{WebContextExt, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    webview
+        .webview()
+        .connect_load_failed_with_tls_errors(move |webview, uri, certificate, errors| {
+            if !crate::callbacks::invoke_ssl_error_handler(window_id, uri, errors.bits() as i32) {
+                return false;
+            }
+
+            let host = uri.parse::<http::Uri>().ok().and_then(|u| u.host().map(str::to_string));
+            match (webview.context(), host) {
+                (Some(context), Some(host)) => {
+                    log::warn!("Allowing untrusted certificate for {}", host);
+                    context.allow_tls_certificate_for_host(certificate, &host);
+                    webview.load_uri(uri);
+                    true
+                }
+                _ => false,
+            }
+        });
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn install_ssl_error_hook(webview: &wry::WebView, window_id: WindowId) -> Result<(), String> {
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        ICoreWebView2_14, COREWEBVIEW2_SERVER_CERTIFICATE_ERROR_ACTION_ALWAYS_ALLOW,
+        COREWEBVIEW2_WEB_ERROR_STATUS,
+    };
+    use webview2_com::{take_pwstr, ServerCertificateErrorDetectedEventHandler};
+    use windows::core::{Interface, PWSTR};
+    use wry::WebViewExtWindows;
+
+    let core: ICoreWebView2_14 = webview
+        .webview()
+        .cast()
+        .map_err(|e| format!("WebView2 runtime does not report certificate errors: {}", e))?;
+
+    let handler = ServerCertificateErrorDetectedEventHandler::create(Box::new(move |_, args| {
+        let Some(args) = args else { return Ok(()) };
+
+        unsafe {
+            let mut uri = PWSTR::null();
+            args.RequestUri(&mut uri)?;
+            let uri = take_pwstr(uri);
+            let mut status = COREWEBVIEW2_WEB_ERROR_STATUS::default();
+            args.ErrorStatus(&mut status)?;
+
+            if crate::callbacks::invoke_ssl_error_handler(window_id, &uri, status.0) {
+                log::warn!("Allowing untrusted certificate for {}", uri);
+                args.SetAction(COREWEBVIEW2_SERVER_CERTIFICATE_ERROR_ACTION_ALWAYS_ALLOW)?;
+            }
+        }
+        Ok(())
+    }));
+
+    let mut token = 0i64;
+    unsafe { core.add_ServerCertificateErrorDetected(&handler, &mut token) }
+        .map_err(|e| format!("Failed to add certificate error handler: {}", e))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn install_ssl_error_hook(_webview: &wry::WebView, _window_id: WindowId) -> Result<(), String> {
+    Err("SSL error handling is not supported on this platform".to_string())
+}
+
+// ============================================================================
+// Document-Start Scripts
+// ============================================================================
+
+/// Registration state of a WebView2 document-created script
+#[cfg(target_os = "windows")]
+enum DocumentScriptId {
+    /// WebView2 has not reported the script's ID yet
+    Pending,
+    Added(String),
+    Removed,
+}
+
+/// Script registered with the engine to run as each document is created
+#[cfg(target_os = "windows")]
+pub struct DocumentScript(Rc<std::cell::RefCell<DocumentScriptId>>);
+
+/// Script registered with the engine to run as each document is created
+#[cfg(target_os = "macos")]
+pub struct DocumentScript(objc2::rc::Retained<objc2::runtime::AnyObject>);
+
+/// Script registered with the engine to run as each document is created
+#[cfg(target_os = "linux")]
+pub struct DocumentScript(webkit2gtk::UserScript);
+
+/// Script registered with the engine to run as each document is created
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub struct DocumentScript;
+
+#[cfg(target_os = "windows")]
+pub(crate) fn add_document_script(
+    webview: &wry::WebView,
+    source: &str,
+) -> Result<DocumentScript, String> {
+    use webview2_com::AddScriptToExecuteOnDocumentCreatedCompletedHandler;
+    use windows::core::HSTRING;
+    use wry::WebViewExtWindows;
+
+    let core = webview.webview();
+    let id = Rc::new(std::cell::RefCell::new(DocumentScriptId::Pending));
+    let pending = id.clone();
+    let remover = core.clone();
+    let on_added = Box::new(move |result: windows::core::Result<()>, script_id: String| {
+        result?;
+        // Removed before WebView2 reported the ID, so remove it now
+        if matches!(*pending.borrow(), DocumentScriptId::Removed) {
+            unsafe { remover.RemoveScriptToExecuteOnDocumentCreated(&HSTRING::from(script_id)) }
+        } else {
+            *pending.borrow_mut() = DocumentScriptId::Added(script_id);
+            Ok(())
+        }
+    });
+    let handler = AddScriptToExecuteOnDocumentCreatedCompletedHandler::create(on_added);
+
+    unsafe { core.AddScriptToExecuteOnDocumentCreated(&HSTRING::from(source), &handler) }
+        .map_err(|e| format!("Failed to add document script: {}", e))?;
+    Ok(DocumentScript(id))
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn remove_document_script(webview: &wry::WebView, script: &DocumentScript) {
+    use windows::core::HSTRING;
+    use wry::WebViewExtWindows;
+
+    if let DocumentScriptId::Added(id) = script.0.replace(DocumentScriptId::Removed) {
+        let removed =
+            unsafe { webview.webview().RemoveScriptToExecuteOnDocumentCreated(&HSTRING::from(id)) };
+        if let Err(e) = removed {
+            log::warn!("Failed to remove document script: {}", e);
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn add_document_script(
+    webview: &wry::WebView,
+    source: &str,
+) -> Result<DocumentScript, String> {
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+    use wry::WebViewExtMacOS;
+
+    // WKUserScriptInjectionTimeAtDocumentStart
+    const AT_DOCUMENT_START: isize = 0;
+
+    let wk_webview = webview.webview();
+    unsafe {
+        let source = NSString::from_str(source);
+        let script: Allocated<AnyObject> = msg_send![class!(WKUserScript), alloc];
+        let script: Option<Retained<AnyObject>> = msg_send![
+            script,
+            initWithSource: &*source,
+            injectionTime: AT_DOCUMENT_START,
+            forMainFrameOnly: true
+        ];
+        let script = script.ok_or("Failed to create user script")?;
+
+        let configuration: *mut AnyObject = msg_send![&*wk_webview, configuration];
+        let controller: *mut AnyObject = msg_send![configuration, userContentController];
+        let _: () = msg_send![controller, addUserScript: &*script];
+        Ok(DocumentScript(script))
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn remove_document_script(webview: &wry::WebView, script: &DocumentScript) {
+    use objc2::msg_send;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::NSArray;
+    use wry::WebViewExtMacOS;
+
+    let wk_webview = webview.webview();
+    unsafe {
+        let configuration: *mut AnyObject = msg_send![&*wk_webview, configuration];
+        let controller: *mut AnyObject = msg_send![configuration, userContentController];
+
+        // WebKit can only remove every user script, so re-add the others,
+        // including wry's own
+        let scripts: Retained<NSArray<AnyObject>> = msg_send![controller, userScripts];
+        let _: () = msg_send![controller, removeAllUserScripts];
+        for other in scripts.iter() {
+            if !std::ptr::eq(&*other, &*script.0) {
+                let _: () = msg_send![controller, addUserScript: &*other];
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn add_document_script(
+    webview: &wry::WebView,
+    source: &str,
+) -> Result<DocumentScript, String> {
+    use webkit2gtk::{
+        UserContentInjectedFrames, UserContentManagerExt, UserScript, UserScriptInjectionTime,
+        WebViewExt,
+    };
+    use wry::WebViewExtUnix;
+
+    let manager = webview
+        .webview()
+        .user_content_manager()
+        .ok_or("Webview has no user content manager")?;
+    let script = UserScript::new(
+        source,
+        UserContentInjectedFrames::TopFrame,
+        UserScriptInjectionTime::Start,
+        &[],
+        &[],
+    );
+    manager.add_script(&script);
+    Ok(DocumentScript(script))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn remove_document_script(webview: &wry::WebView, script: &DocumentScript) {
+    use webkit2gtk::{UserContentManagerExt, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    if let Some(manager) = webview.webview().user_content_manager() {
+        manager.remove_script(&script.0);
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub(crate) fn add_document_script(
+    _webview: &wry::WebView,
+    _source: &str,
+) -> Result<DocumentScript, String> {
+    Err("Init scripts are not supported on this platform".to_string())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+pub(crate) fn remove_document_script(_webview: &wry::WebView, _script: &DocumentScript) {}
+
+// ============================================================================
+// Async Scripts
+// ============================================================================
+
+/// Host callback waiting for an async script result
+struct AsyncJsCall {
+    callback:
+        unsafe extern "C" fn(result_json: *const c_char, success: bool, user_data: *mut c_void),
+    user_data: usize,
+}
+
+/// Async scripts still running, by the window running them and call ID
+static ASYNC_JS_CALLS: Lazy<Mutex<HashMap<(WindowId, u64), AsyncJsCall>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_ASYNC_JS_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Start of the IPC message carrying an async script result
+const ASYNC_JS_RESULT_PREFIX: &str = "{\"__wryAsyncResult\":";
+
+/// Deliver an async script result posted over IPC by `window_id`
+///
+/// Only calls started in that window are completed, so one page cannot
+/// settle another window's scripts. Returns false if `body` is not an async
+/// script result, so it should be passed on to the message callback.
+pub fn handle_async_js_result(window_id: WindowId, body: &str) -> bool {
+    if !body.starts_with(ASYNC_JS_RESULT_PREFIX) {
+        return false;
+    }
+
+    let message: serde_json::Value = match serde_json::from_str(body) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    let call = message["__wryAsyncResult"]
+        .as_u64()
+        .and_then(|id| ASYNC_JS_CALLS.lock().unwrap().remove(&(window_id, id)));
+    let call = match call {
+        Some(c) => c,
+        None => {
+            log::warn!("Async script result for an unknown call in window {:?}", window_id);
+            return true;
+        }
+    };
+
+    let success = message["ok"].as_bool().unwrap_or(false);
+    let result = if success { &message["value"] } else { &message["error"] };
+    if let Ok(json) = CString::new(result.to_string()) {
+        unsafe { (call.callback)(json.as_ptr(), success, call.user_data as *mut c_void) };
+    }
+    true
+}
+
+/// Fail every async script still running in a window
+///
+/// Called when the window's page is replaced or the window is destroyed,
+/// since those scripts can no longer post a result. Each callback gets
+/// `reason` as a JSON string with `success` false.
+pub fn cancel_async_js_calls(window_id: WindowId, reason: &str) {
+    // Callbacks may start new calls, so run them without holding the lock
+    let calls: Vec<AsyncJsCall> = {
+        let mut pending = ASYNC_JS_CALLS.lock().unwrap();
+        let ids: Vec<_> = pending.keys().filter(|(w, _)| *w == window_id).copied().collect();
+        ids.iter().filter_map(|key| pending.remove(key)).collect()
+    };
+    if calls.is_empty() {
+        return;
+    }
+
+    log::debug!("Cancelling {} async scripts in window {:?}", calls.len(), window_id);
+    let json = match CString::new(serde_json::Value::String(reason.to_string()).to_string()) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    for call in calls {
+        unsafe { (call.callback)(json.as_ptr(), false, call.user_data as *mut c_void) };
+    }
+}
+
+/// Run `script` as the body of an async function and report its result
+///
+/// `args_json` is parsed and bound to a local `args`; pass null for
+/// `null`. The callback runs later on the UI thread with the resolved value
+/// as JSON, or with the rejection message as a JSON string and `success`
+/// false. Values JSON cannot represent become `null`. If the page navigates
+/// away or the window is destroyed before the script settles, the callback
+/// gets that reason with `success` false.
+///
+/// # Returns
+/// false if the script could not be started, in which case the callback is
+/// never called
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_call_async_javascript(
+    window: WryWindow,
+    script: *const c_char,
+    args_json: *const c_char,
+    callback: AsyncJsCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let callback = match callback {
+        Some(cb) => cb,
+        None => {
+            set_last_error("Null callback");
+            return false;
+        }
+    };
+
+    let script = match c_str_to_string(script) {
+        Some(s) => s,
+        None => {
+            set_last_error("Null or invalid script");
+            return false;
+        }
+    };
+
+    let args = match c_str_to_string(args_json) {
+        None => serde_json::Value::Null,
+        Some(json) => match serde_json::from_str(&json) {
+            Ok(v) => v,
+            Err(e) => {
+                set_last_error(format!("Invalid args JSON: {}", e));
+                return false;
+            }
+        },
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    let id = NEXT_ASYNC_JS_ID.fetch_add(1, Ordering::Relaxed);
+    let wrapped = format!(
+        r#"(async function() {{
+    const args = {args};
+{script}
+}})().then(function(value) {{
+    window.ipc.postMessage(JSON.stringify({{ __wryAsyncResult: {id}, ok: true, value: value === undefined ? null : value }}));
+}}).catch(function(e) {{
+    window.ipc.postMessage(JSON.stringify({{ __wryAsyncResult: {id}, ok: false, error: String(e) }}));
+}});"#
+    );
+
+    ASYNC_JS_CALLS.lock().unwrap().insert(
+        (state.id, id),
+        AsyncJsCall {
+            callback,
+            user_data: user_data as usize,
+        },
+    );
+
+    log::debug!("Calling async script {} ({} bytes)", id, script.len());
+    if let Err(e) = webview.evaluate_script(&wrapped) {
+        ASYNC_JS_CALLS.lock().unwrap().remove(&(state.id, id));
+        set_last_error(format!("Script execution failed: {}", e));
+        return false;
+    }
+    true
+}
+
+// ============================================================================
+// Page Export
+// ============================================================================
+
+/// Host callback waiting for exported PDF data
+#[derive(Clone, Copy)]
+struct PdfRequest {
+    callback: unsafe extern "C" fn(pdf_data: *const u8, len: usize, user_data: *mut c_void),
+    user_data: usize,
+}
+
+impl PdfRequest {
+    /// Hand the PDF bytes to the host, or null on failure
+    fn deliver(self, pdf: Option<&[u8]>) {
+        let (data, len) = match pdf {
+            Some(bytes) => (bytes.as_ptr(), bytes.len()),
+            None => (std::ptr::null(), 0),
+        };
+        unsafe { (self.callback)(data, len, self.user_data as *mut c_void) };
+    }
+}
+
+/// Export the current page as a PDF
+///
+/// The callback runs later on the UI thread with the PDF bytes, which are
+/// only valid until it returns. If the export fails after starting, the
+/// callback gets null and 0. Requires macOS 11 or a WebView2 runtime with
+/// `PrintToPdfStream`; on Linux the page is printed to a temporary file.
+///
+/// # Returns
+/// false if the export could not be started, in which case the callback
+/// is never called
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_get_pdf(
+    window: WryWindow,
+    callback: PdfCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let callback = match callback {
+        Some(cb) => cb,
+        None => {
+            set_last_error("Null PDF callback");
+            return false;
+        }
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    let request = PdfRequest {
+        callback,
+        user_data: user_data as usize,
+    };
+
+    log::debug!("Exporting page as PDF");
+    match export_pdf(webview, request) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn export_pdf(webview: &wry::WebView, request: PdfRequest) -> Result<(), String> {
+    use objc2::runtime::AnyObject;
+    use objc2::{msg_send, sel};
+    use wry::WebViewExtMacOS;
+
+    let wk_webview = webview.webview();
+    unsafe {
+        let supported: bool = msg_send![
+            &*wk_webview,
+            respondsToSelector: sel!(createPDFWithConfiguration:completionHandler:)
+        ];
+        if !supported {
+            return Err("PDF export requires macOS 11 or later".to_string());
+        }
+
+        let handler = block2::RcBlock::new(move |data: *mut AnyObject, _error: *mut AnyObject| {
+            if data.is_null() {
+                log::error!("PDF export failed");
+                request.deliver(None);
+                return;
+            }
+            let bytes: *const u8 = msg_send![data, bytes];
+            let len: usize = msg_send![data, length];
+            request.deliver(Some(std::slice::from_raw_parts(bytes, len)));
+        });
+
+        let _: () = msg_send![
+            &*wk_webview,
+            createPDFWithConfiguration: std::ptr::null_mut::<AnyObject>(),
+            completionHandler: &*handler
+        ];
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn export_pdf(webview: &wry::WebView, request: PdfRequest) -> Result<(), String> {
+    use webview2_com::Microsoft::Web::WebView2::Win32::ICoreWebView2_16;
+    use webview2_com::PrintToPdfStreamCompletedHandler;
+    use windows::core::Interface;
+    use windows::Win32::System::Com::IStream;
+    use wry::WebViewExtWindows;
+
+    /// Read a stream to the end
+    unsafe fn read_stream(stream: &IStream) -> Option<Vec<u8>> {
+        let mut data = Vec::new();
+        let mut chunk = vec![0u8; 64 * 1024];
+        loop {
+            let mut read = 0u32;
+            stream
+                .Read(chunk.as_mut_ptr().cast(), chunk.len() as u32, Some(&mut read))
+                .ok()
+                .ok()?;
+            if read == 0 {
+                return Some(data);
+            }
+            data.extend_from_slice(&chunk[..read as usize]);
+        }
+    }
+
+    let core: ICoreWebView2_16 = webview
+        .webview()
+        .cast()
+        .map_err(|e| format!("WebView2 runtime does not support PDF export: {}", e))?;
+
+    let handler = PrintToPdfStreamCompletedHandler::create(Box::new(move |result, stream| {
+        let pdf = match (result, stream) {
+            (Ok(()), Some(stream)) => unsafe { read_stream(&stream) },
+            _ => None,
+        };
+        if pdf.is_none() {
+            log::error!("PDF export failed");
+        }
+        request.deliver(pdf.as_deref());
+        Ok(())
+    }));
+
+    unsafe { core.PrintToPdfStream(None, &handler) }
+        .map_err(|e| format!("Failed to start PDF export: {}", e))
+}
+
+#[cfg(target_os = "linux")]
+fn export_pdf(webview: &wry::WebView, request: PdfRequest) -> Result<(), String> {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use gtk::glib;
+    use webkit2gtk::{PrintOperation, PrintOperationExt};
+    use wry::WebViewExtUnix;
+
+    static NEXT_EXPORT: AtomicU32 = AtomicU32::new(0);
+
+    // WebKitGTK can only print to files, so go through a temporary one
+    let path = std::env::temp_dir().join(format!(
+        "wry-ffi-{}-{}.pdf",
+        std::process::id(),
+        NEXT_EXPORT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let uri = glib::filename_to_uri(&path, None)
+        .map_err(|e| format!("Invalid PDF export path {}: {}", path.display(), e))?;
+
+    let settings = gtk::PrintSettings::new();
+    settings.set_printer("Print to File");
+    settings.set(gtk::PRINT_SETTINGS_OUTPUT_FILE_FORMAT, Some("pdf"));
+    settings.set(gtk::PRINT_SETTINGS_OUTPUT_URI, Some(&uri));
+
+    let operation = PrintOperation::new(&webview.webview());
+    operation.set_print_settings(&settings);
+
+    let failed = Rc::new(Cell::new(false));
+    let failed_flag = failed.clone();
+    operation.connect_failed(move |_, e| {
+        log::error!("PDF export failed: {}", e);
+        failed_flag.set(true);
+    });
+    operation.connect_finished(move |_| {
+        let pdf = if failed.get() {
+            None
+        } else {
+            std::fs::read(&path)
+                .map_err(|e| log::error!("Failed to read {}: {}", path.display(), e))
+                .ok()
+        };
+        let _ = std::fs::remove_file(&path);
+        request.deliver(pdf.as_deref());
+    });
+
+    operation.print();
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn export_pdf(_webview: &wry::WebView, _request: PdfRequest) -> Result<(), String> {
+    Err("PDF export is not supported on this platform".to_string())
+}
+
+/// Host callback waiting for snapshot pixels
+#[derive(Clone, Copy)]
+struct SnapshotRequest {
+    callback: unsafe extern "C" fn(
+        rgba: *const u8,
+        len: usize,
+        width: u32,
+        height: u32,
+        user_data: *mut c_void,
+    ),
+    user_data: usize,
+}
+
+impl SnapshotRequest {
+    /// Hand the pixels (width, height, rgba) to the host, or null on failure
+    fn deliver(self, image: Option<(u32, u32, Vec<u8>)>) {
+        let user_data = self.user_data as *mut c_void;
+        match image {
+            Some((width, height, rgba)) => unsafe {
+                (self.callback)(rgba.as_ptr(), rgba.len(), width, height, user_data)
+            },
+            None => unsafe { (self.callback)(std::ptr::null(), 0, 0, 0, user_data) },
+        }
+    }
+}
+
+/// Undo premultiplied alpha in place
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub(crate) fn unpremultiply(rgba: &mut [u8]) {
+    for px in rgba.chunks_exact_mut(4) {
+        let alpha = px[3] as u16;
+        if alpha != 0 && alpha != 255 {
+            for channel in &mut px[..3] {
+                *channel = (*channel as u16 * 255 / alpha).min(255) as u8;
+            }
+        }
+    }
+}
+
+/// Copy a cairo ARGB32 or RGB24 surface into straight RGBA
+#[cfg(target_os = "linux")]
+pub(crate) fn surface_to_rgba(surface: gtk::cairo::Surface) -> Option<(u32, u32, Vec<u8>)> {
+    use gtk::cairo::{Format, ImageSurface};
+
+    let surface = ImageSurface::try_from(surface).ok()?;
+    let opaque = match surface.format() {
+        Format::ARgb32 => false,
+        Format::Rgb24 => true,
+        _ => return None,
+    };
+    let width = surface.width() as usize;
+    let height = surface.height() as usize;
+    let stride = surface.stride() as usize;
+
+    // Pixels are native-endian u32s: BGRA bytes on little-endian
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    surface
+        .with_data(|data| {
+            for row in data.chunks(stride).take(height) {
+                for px in row[..width * 4].chunks_exact(4) {
+                    let argb = u32::from_ne_bytes([px[0], px[1], px[2], px[3]]);
+                    let [a, r, g, b] = argb.to_be_bytes();
+                    rgba.extend_from_slice(&[r, g, b, if opaque { 255 } else { a }]);
+                }
+            }
+        })
+        .ok()?;
+    unpremultiply(&mut rgba);
+    Some((width as u32, height as u32, rgba))
+}
+
+/// Capture the visible webview content as RGBA pixels
+///
+/// The callback runs later on the UI thread with `width * height * 4` bytes
+/// of straight RGBA, row by row from the top left. The buffer is freed
+/// when the callback returns, so copy anything you need to keep. If the
+/// capture fails after starting, the callback gets null and zeros.
+///
+/// # Returns
+/// false if the capture could not be started, in which case the callback
+/// is never called
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_get_snapshot(
+    window: WryWindow,
+    callback: SnapshotCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let callback = match callback {
+        Some(cb) => cb,
+        None => {
+            set_last_error("Null snapshot callback");
+            return false;
+        }
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    let request = SnapshotRequest {
+        callback,
+        user_data: user_data as usize,
+    };
+
+    log::debug!("Capturing webview snapshot");
+    match capture_snapshot(webview, request) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn capture_snapshot(webview: &wry::WebView, request: SnapshotRequest) -> Result<(), String> {
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::{NSPoint, NSRect, NSSize, NSString};
+    use wry::WebViewExtMacOS;
+
+    /// Draw an NSImage into an RGBA bitmap at its full pixel size
+    unsafe fn image_to_rgba(image: *mut AnyObject) -> Option<(u32, u32, Vec<u8>)> {
+        let reps: *mut AnyObject = msg_send![image, representations];
+        let rep: *mut AnyObject = msg_send![reps, firstObject];
+        if rep.is_null() {
+            return None;
+        }
+        let width: isize = msg_send![rep, pixelsWide];
+        let height: isize = msg_send![rep, pixelsHigh];
+        if width <= 0 || height <= 0 {
+            return None;
+        }
+
+        let color_space = NSString::from_str("NSDeviceRGBColorSpace");
+        let bitmap: Allocated<AnyObject> = msg_send![class!(NSBitmapImageRep), alloc];
+        let bitmap: Option<Retained<AnyObject>> = msg_send![
+            bitmap,
+            initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>(),
+            pixelsWide: width,
+            pixelsHigh: height,
+            bitsPerSample: 8isize,
+            samplesPerPixel: 4isize,
+            hasAlpha: true,
+            isPlanar: false,
+            colorSpaceName: &*color_space,
+            bytesPerRow: width * 4,
+            bitsPerPixel: 32isize
+        ];
+        let bitmap = bitmap?;
+
+        let context: *mut AnyObject = msg_send![
+            class!(NSGraphicsContext),
+            graphicsContextWithBitmapImageRep: &*bitmap
+        ];
+        if context.is_null() {
+            return None;
+        }
+        let _: () = msg_send![class!(NSGraphicsContext), saveGraphicsState];
+        let _: () = msg_send![class!(NSGraphicsContext), setCurrentContext: context];
+        let rect = NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(width as f64, height as f64));
+        let _: () = msg_send![image, drawInRect: rect];
+        let _: () = msg_send![class!(NSGraphicsContext), restoreGraphicsState];
+
+        let data: *const u8 = msg_send![&*bitmap, bitmapData];
+        if data.is_null() {
+            return None;
+        }
+        let len = width as usize * height as usize * 4;
+        let mut rgba = std::slice::from_raw_parts(data, len).to_vec();
+        unpremultiply(&mut rgba);
+        Some((width as u32, height as u32, rgba))
+    }
+
+    let wk_webview = webview.webview();
+    unsafe {
+        let handler = block2::RcBlock::new(move |image: *mut AnyObject, _error: *mut AnyObject| {
+            let pixels = if image.is_null() {
+                None
+            } else {
+                image_to_rgba(image)
+            };
+            if pixels.is_none() {
+                log::error!("Webview snapshot failed");
+            }
+            request.deliver(pixels);
+        });
+
+        let _: () = msg_send![
+            &*wk_webview,
+            takeSnapshotWithConfiguration: std::ptr::null_mut::<AnyObject>(),
+            completionHandler: &*handler
+        ];
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn capture_snapshot(webview: &wry::WebView, request: SnapshotRequest) -> Result<(), String> {
+    use webview2_com::CapturePreviewCompletedHandler;
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        COREWEBVIEW2_CAPTURE_PREVIEW_IMAGE_FORMAT_PNG as FORMAT_PNG,
+    };
+    use windows::Win32::Foundation::HGLOBAL;
+    use windows::Win32::System::Com::StructuredStorage::CreateStreamOnHGlobal;
+    use windows::Win32::System::Com::STREAM_SEEK_SET;
+    use wry::WebViewExtWindows;
+
+    let stream = unsafe { CreateStreamOnHGlobal(HGLOBAL::default(), true) }
+        .map_err(|e| format!("Failed to create snapshot stream: {}", e))?;
+
+    // WebView2 only captures encoded images, so decode the PNG afterwards
+    let png = stream.clone();
+    let handler = CapturePreviewCompletedHandler::create(Box::new(move |result| {
+        let pixels = result
+            .and_then(|()| unsafe { png.Seek(0, STREAM_SEEK_SET, None) })
+            .map_err(|e| e.to_string())
+            .and_then(|()| crate::icon::decode_image_stream(&png));
+        if let Err(e) = &pixels {
+            log::error!("Webview snapshot failed: {}", e);
+        }
+        request.deliver(pixels.ok());
+        Ok(())
+    }));
+
+    unsafe { webview.webview().CapturePreview(FORMAT_PNG, &stream, &handler) }
+        .map_err(|e| format!("Failed to start snapshot: {}", e))
+}
+
+#[cfg(target_os = "linux")]
+fn capture_snapshot(webview: &wry::WebView, request: SnapshotRequest) -> Result<(), String> {
+    use gtk::gio::Cancellable;
+    use webkit2gtk::{SnapshotOptions, SnapshotRegion, WebViewExt};
+    use wry::WebViewExtUnix;
+
+    webview.webview().snapshot(
+        SnapshotRegion::Visible,
+        SnapshotOptions::NONE,
+        None::<&Cancellable>,
+        move |result| {
+            let pixels = match result {
+                Ok(surface) => surface_to_rgba(surface),
+                Err(e) => {
+                    log::error!("Webview snapshot failed: {}", e);
+                    None
+                }
+            };
+            request.deliver(pixels);
+        },
+    );
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn capture_snapshot(_webview: &wry::WebView, _request: SnapshotRequest) -> Result<(), String> {
+    Err("Snapshots are not supported on this platform".to_string())
+}
+
+// ============================================================================
+// Permissions
+// ============================================================================
+
+/// Answer web content permission requests without prompting
+///
+/// Microphone, camera, geolocation and notification requests are granted
+/// if allowed in `permissions` and denied otherwise. Other requests keep
+/// their default handling. Can be called again to change the grants.
+///
+/// Not supported on macOS, where wry owns the UI delegate that receives
+/// permission requests.
+///
+/// # Returns
+/// false if the platform hook could not be installed
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_media_permissions(
+    window: WryWindow,
+    permissions: WryMediaPermissions,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    log::debug!("Setting media permissions: {:?}", permissions);
+
+    if let Some(current) = &state.media_permissions {
+        current.set(permissions);
+        return true;
+    }
+
+    let current = Rc::new(Cell::new(permissions));
+    if let Err(e) = install_permission_hook(webview, current.clone()) {
+        set_last_error(e);
+        return false;
+    }
+    state.media_permissions = Some(current);
+    true
+}
+
+#[cfg(target_os = "linux")]
+fn install_permission_hook(
+    webview: &wry::WebView,
+    permissions: Rc<Cell<WryMediaPermissions>>,
+) -> Result<(), String> {
+    use gtk::glib::{Cast, ObjectExt};
+    use webkit2gtk::{
+        GeolocationPermissionRequest, NotificationPermissionRequest, PermissionRequestExt,
+        UserMediaPermissionRequest, UserMediaPermissionRequestExt, WebViewExt,
+    };
+    use wry::WebViewExtUnix;
+
+    webview.webview().connect_permission_request(move |_, request| {
+        let permissions = permissions.get();
+        let allowed = if let Some(media) = request.downcast_ref::<UserMediaPermissionRequest>() {
+            (!media.is_for_audio_device() || permissions.allow_microphone)
+                && (!media.is_for_video_device() || permissions.allow_camera)
+        } else if request.is::<GeolocationPermissionRequest>() {
+            permissions.allow_geolocation
+        } else if request.is::<NotificationPermissionRequest>() {
+            permissions.allow_notifications
+        } else {
+            return false;
+        };
+
+        if allowed {
+            request.allow();
+        } else {
+            request.deny();
+        }
+        true
+    });
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn install_permission_hook(
+    webview: &wry::WebView,
+    permissions: Rc<Cell<WryMediaPermissions>>,
+) -> Result<(), String> {
+    use webview2_com::Microsoft::Web::WebView2::Win32::{
+        COREWEBVIEW2_PERMISSION_KIND, COREWEBVIEW2_PERMISSION_KIND_CAMERA,
+        COREWEBVIEW2_PERMISSION_KIND_GEOLOCATION, COREWEBVIEW2_PERMISSION_KIND_MICROPHONE,
+        COREWEBVIEW2_PERMISSION_KIND_NOTIFICATIONS, COREWEBVIEW2_PERMISSION_STATE_ALLOW,
+        COREWEBVIEW2_PERMISSION_STATE_DENY,
+    };
+    use webview2_com::PermissionRequestedEventHandler;
+    use wry::WebViewExtWindows;
+
+    let handler = PermissionRequestedEventHandler::create(Box::new(move |_, args| {
+        let Some(args) = args else { return Ok(()) };
+
+        unsafe {
+            let mut kind = COREWEBVIEW2_PERMISSION_KIND::default();
+            args.PermissionKind(&mut kind)?;
+
+            let permissions = permissions.get();
+            let allowed = match kind {
+                COREWEBVIEW2_PERMISSION_KIND_MICROPHONE => permissions.allow_microphone,
+                COREWEBVIEW2_PERMISSION_KIND_CAMERA => permissions.allow_camera,
+                COREWEBVIEW2_PERMISSION_KIND_GEOLOCATION => permissions.allow_geolocation,
+                COREWEBVIEW2_PERMISSION_KIND_NOTIFICATIONS => permissions.allow_notifications,
+                _ => return Ok(()),
+            };
+
+            args.SetState(if allowed {
+                COREWEBVIEW2_PERMISSION_STATE_ALLOW
+            } else {
+                COREWEBVIEW2_PERMISSION_STATE_DENY
+            })
+        }
+    }));
+
+    let mut token = 0i64;
+    unsafe { webview.webview().add_PermissionRequested(&handler, &mut token) }
+        .map_err(|e| format!("Failed to add permission handler: {}", e))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows")))]
+fn install_permission_hook(
+    _webview: &wry::WebView,
+    _permissions: Rc<Cell<WryMediaPermissions>>,
+) -> Result<(), String> {
+    Err("Media permissions are not supported on this platform".to_string())
+}
+
+// ============================================================================
+// Cookies
+// ============================================================================
+
+/// Add or replace a cookie in the webview's cookie store
+///
+/// Uses the native store, so `http_only` cookies can be set, e.g. a session
+/// token obtained by the host during single sign-on.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_cookie(
+    window: WryWindow,
+    cookie: *const WryCookie,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    if cookie.is_null() {
+        set_last_error("Null cookie");
+        return false;
+    }
+    let cookie = &*cookie;
+
+    let (name, value) = match (c_str_to_string(cookie.name), c_str_to_string(cookie.value)) {
+        (Some(name), Some(value)) if !name.is_empty() => (name, value),
+        _ => {
+            set_last_error("Cookie name and value are required");
+            return false;
+        }
+    };
+
+    let mut builder = wry::cookie::Cookie::build((name, value))
+        .secure(cookie.secure)
+        .http_only(cookie.http_only);
+    if let Some(domain) = c_str_to_string(cookie.domain) {
+        builder = builder.domain(domain);
+    }
+    if let Some(path) = c_str_to_string(cookie.path) {
+        builder = builder.path(path);
+    }
+    if cookie.expires_secs != 0 {
+        match wry::cookie::time::OffsetDateTime::from_unix_timestamp(cookie.expires_secs) {
+            Ok(expires) => builder = builder.expires(expires),
+            Err(e) => {
+                set_last_error(format!("Invalid cookie expiry: {}", e));
+                return false;
+            }
+        }
+    }
+
+    let cookie = builder.build();
+    log::debug!("Setting cookie: {}", cookie.name());
+    match webview.set_cookie(&cookie) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Failed to set cookie: {}", e));
+            false
+        }
+    }
+}
+
+/// Get the webview's cookies as a JSON array
+///
+/// Each entry has `name`, `value`, `domain`, `path`, `expires_secs` (null
+/// for session cookies), `secure` and `http_only`. `domain` limits the list
+/// to cookies for that domain and its subdomains; null returns every
+/// cookie. Returns null on failure. Caller must free with wry_string_free.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_get_cookies(
+    window: WryWindow,
+    domain: *const c_char,
+) -> *mut c_char {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return std::ptr::null_mut(),
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return std::ptr::null_mut();
+        }
+    };
+
+    let cookies = match webview.cookies() {
+        Ok(cookies) => cookies,
+        Err(e) => {
+            set_last_error(format!("Failed to get cookies: {}", e));
+            return std::ptr::null_mut();
+        }
+    };
+
+    let filter = c_str_to_string(domain).map(|d| d.trim_start_matches('.').to_ascii_lowercase());
+    let list: Vec<serde_json::Value> = cookies
+        .iter()
+        .filter(|cookie| match (&filter, cookie.domain()) {
+            (None, _) => true,
+            (Some(filter), Some(domain)) => {
+                let domain = domain.trim_start_matches('.').to_ascii_lowercase();
+                domain == *filter || domain.ends_with(&format!(".{}", filter))
+            }
+            (Some(_), None) => false,
+        })
+        .map(|cookie| {
+            serde_json::json!({
+                "name": cookie.name(),
+                "value": cookie.value(),
+                "domain": cookie.domain(),
+                "path": cookie.path(),
+                "expires_secs": cookie.expires_datetime().map(|t| t.unix_timestamp()),
+                "secure": cookie.secure().unwrap_or(false),
+                "http_only": cookie.http_only().unwrap_or(false),
+            })
+        })
+        .collect();
+
+    string_to_c_string(&serde_json::Value::from(list).to_string())
+}
+
+// ============================================================================
+// Local Storage
+// ============================================================================
+
+/// Read a `localStorage` item of the current page
+///
+/// The callback gets the value as a JSON string, or `null` if the key is
+/// not set; `success` is false if the page has no storage access (e.g. a
+/// `data:` URL). Same call rules as `wry_webview_call_async_javascript`.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_get_local_storage_item(
+    window: WryWindow,
+    key: *const c_char,
+    callback: AsyncJsCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let key = match c_str_to_string(key) {
+        Some(k) => k,
+        None => {
+            set_last_error("Null or invalid key");
+            return false;
+        }
+    };
+    let args = match CString::new(serde_json::json!({ "key": key }).to_string()) {
+        Ok(args) => args,
+        Err(_) => {
+            set_last_error("Key contains a null byte");
+            return false;
+        }
+    };
+
+    wry_webview_call_async_javascript(
+        window,
+        c"return localStorage.getItem(args.key);".as_ptr(),
+        args.as_ptr(),
+        callback,
+        user_data,
+    )
+}
+
+/// Run a `localStorage` update on the current page
+unsafe fn update_local_storage(window: WryWindow, script: &str) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    let script = format!(
+        "try {{ {} }} catch (e) {{ console.warn('localStorage unavailable:', e); }}",
+        script
+    );
+    match webview.evaluate_script(&script) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Script execution failed: {}", e));
+            false
+        }
+    }
+}
+
+/// Set a `localStorage` item of the current page
+///
+/// Storage is per origin, so the page must already be loaded. Failures
+/// inside the page, such as a full quota, are only logged to its console.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_set_local_storage_item(
+    window: WryWindow,
+    key: *const c_char,
+    value: *const c_char,
+) -> bool {
+    let (key, value) = match (c_str_to_string(key), c_str_to_string(value)) {
+        (Some(k), Some(v)) => (k, v),
+        _ => {
+            set_last_error("Null or invalid key or value");
+            return false;
+        }
+    };
+
+    log::debug!("Setting localStorage item: {}", key);
+    update_local_storage(
+        window,
+        &format!(
+            "localStorage.setItem({}, {});",
+            serde_json::Value::String(key),
+            serde_json::Value::String(value)
+        ),
+    )
+}
+
+/// Remove every `localStorage` item of the current page's origin
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_clear_local_storage(window: WryWindow) -> bool {
+    log::debug!("Clearing localStorage");
+    update_local_storage(window, "localStorage.clear();")
+}
+
+// ============================================================================
+// Editing
+// ============================================================================
+
+/// Run an Edit menu command on the webview's focused element or selection
+///
+/// For menu items, which take focus away from the page on some platforms.
+/// Uses the engine's own editing commands on Linux and the responder chain
+/// on macOS, falling back to `document.execCommand`. On Windows only the
+/// fallback is available, and Chromium ignores `Paste` there.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_execute_command(
+    window: WryWindow,
+    command: WryEditorCommand,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    log::debug!("Executing editor command: {:?}", command);
+    if execute_native_command(webview, command) {
+        return true;
+    }
+
+    let name = match command {
+        WryEditorCommand::Cut => "cut",
+        WryEditorCommand::Copy => "copy",
+        WryEditorCommand::Paste => "paste",
+        WryEditorCommand::SelectAll => "selectAll",
+        WryEditorCommand::Undo => "undo",
+        WryEditorCommand::Redo => "redo",
+        WryEditorCommand::Delete => "delete",
+        WryEditorCommand::Bold => "bold",
+        WryEditorCommand::Italic => "italic",
+        WryEditorCommand::Underline => "underline",
+    };
+    match webview.evaluate_script(&format!("document.execCommand('{}');", name)) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Script execution failed: {}", e));
+            false
+        }
+    }
+}
+
+/// Run `command` through the engine, returning false if it has no native
+/// equivalent
+#[cfg(target_os = "linux")]
+fn execute_native_command(webview: &wry::WebView, command: WryEditorCommand) -> bool {
+    use webkit2gtk::WebViewExt;
+    use wry::WebViewExtUnix;
+
+    // WebCore editor command names
+    let name = match command {
+        WryEditorCommand::Cut => "Cut",
+        WryEditorCommand::Copy => "Copy",
+        WryEditorCommand::Paste => "Paste",
+        WryEditorCommand::SelectAll => "SelectAll",
+        WryEditorCommand::Undo => "Undo",
+        WryEditorCommand::Redo => "Redo",
+        WryEditorCommand::Delete => "Delete",
+        WryEditorCommand::Bold => "ToggleBold",
+        WryEditorCommand::Italic => "ToggleItalic",
+        WryEditorCommand::Underline => "ToggleUnderline",
+    };
+    webview.webview().execute_editing_command(name);
+    true
+}
+
+#[cfg(target_os = "macos")]
+fn execute_native_command(webview: &wry::WebView, command: WryEditorCommand) -> bool {
+    use objc2::runtime::{AnyObject, Bool, Sel};
+    use objc2::{msg_send, sel};
+    use wry::WebViewExtMacOS;
+
+    let action: Sel = match command {
+        WryEditorCommand::Cut => sel!(cut:),
+        WryEditorCommand::Copy => sel!(copy:),
+        WryEditorCommand::Paste => sel!(paste:),
+        WryEditorCommand::SelectAll => sel!(selectAll:),
+        WryEditorCommand::Undo => sel!(undo:),
+        WryEditorCommand::Redo => sel!(redo:),
+        WryEditorCommand::Delete => sel!(delete:),
+        _ => return false,
+    };
+
+    let wk_webview = webview.webview();
+    // Walks the responder chain from the webview, like a menu item would
+    let handled: Bool = unsafe {
+        msg_send![&*wk_webview, tryToPerform: action, with: std::ptr::null_mut::<AnyObject>()]
+    };
+    handled.as_bool()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn execute_native_command(_webview: &wry::WebView, _command: WryEditorCommand) -> bool {
+    false
+}
+
+// ============================================================================
+// WebSocket Interception
+// ============================================================================
+
+/// Replace `WebSocket` with a stand-in that talks to the host over IPC for
+/// URLs matching `window.__wryWebSocketPattern`
+const WEBSOCKET_INTERCEPT_SCRIPT: &str = r#"(function() {
+    if (window.__wryNativeWebSocket) return;
+    var Native = window.__wryNativeWebSocket = window.WebSocket;
+    var sockets = {};
+    var nextId = 1;
+    function post(id, event, data) {
+        window.ipc.postMessage(JSON.stringify({ __wryWebSocket: id, event: event, data: data }));
+    }
+    class WryWebSocket extends EventTarget {
+        constructor(url) {
+            super();
+            this.url = url;
+            this.readyState = 0;
+            this.protocol = '';
+            this.extensions = '';
+            this.bufferedAmount = 0;
+            this.binaryType = 'blob';
+            this.onopen = this.onmessage = this.onerror = this.onclose = null;
+            this._id = nextId++;
+            sockets[this._id] = this;
+            post(this._id, 'open', url);
+            var self = this;
+            setTimeout(function() {
+                if (self.readyState !== 0) return;
+                self.readyState = 1;
+                self._fire(new Event('open'));
+            }, 0);
+        }
+        send(data) {
+            if (this.readyState !== 1) {
+                throw new DOMException('WebSocket is not open', 'InvalidStateError');
+            }
+            post(this._id, 'message', String(data));
+        }
+        close(code, reason) {
+            if (this.readyState >= 2) return;
+            post(this._id, 'close', null);
+            this._closed(code, reason);
+        }
+        _closed(code, reason) {
+            this.readyState = 3;
+            delete sockets[this._id];
+            this._fire(new CloseEvent('close',
+                { code: code || 1000, reason: reason || '', wasClean: true }));
+        }
+        _fire(event) {
+            var handler = this['on' + event.type];
+            if (typeof handler === 'function') handler.call(this, event);
+            this.dispatchEvent(event);
+        }
+    }
+    window.WebSocket = function(url, protocols) {
+        var resolved = new URL(url, location.href).href;
+        if (window.__wryWebSocketPattern && window.__wryWebSocketPattern.test(resolved)) {
+            return new WryWebSocket(resolved);
+        }
+        return new Native(url, protocols);
+    };
+    window.WebSocket.prototype = Native.prototype;
+    ['CONNECTING', 'OPEN', 'CLOSING', 'CLOSED'].forEach(function(name, value) {
+        window.WebSocket[name] = WryWebSocket[name] = WryWebSocket.prototype[name] = value;
+    });
+    window.__wryWebSocketDeliver = function(id, data) {
+        var socket = sockets[id];
+        if (socket) socket._fire(new MessageEvent('message', { data: data, origin: socket.url }));
+    };
+    window.__wryWebSocketClose = function(id) {
+        var socket = sockets[id];
+        if (socket) socket._closed(1000, '');
+    };
+})();"#;
+
+/// Start of the IPC message carrying intercepted WebSocket activity
+const WEBSOCKET_PREFIX: &str = "{\"__wryWebSocket\":";
+
+/// Pass WebSocket activity posted over IPC to the interception handler
+///
+/// Returns false if `body` is not WebSocket activity, so it should be
+/// passed on to the message callback.
+pub fn handle_websocket_message(window_id: WindowId, body: &str) -> bool {
+    if !body.starts_with(WEBSOCKET_PREFIX) {
+        return false;
+    }
+
+    let message: serde_json::Value = match serde_json::from_str(body) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    let id = match message["__wryWebSocket"].as_u64() {
+        Some(id) => id as u32,
+        None => return false,
+    };
+    let event = match message["event"].as_str() {
+        Some("open") => WryWebSocketEvent::Open,
+        Some("message") => WryWebSocketEvent::Message,
+        Some("close") => WryWebSocketEvent::Close,
+        _ => return false,
+    };
+
+    crate::callbacks::invoke_websocket_handler(window_id, id, event, message["data"].as_str());
+    true
+}
+
+/// Glob to an anchored JavaScript regex source, escaping everything but `*`
+fn websocket_pattern_regex(pattern: &str) -> String {
+    let regex = pattern
+        .split('*')
+        .map(|part| {
+            part.chars().fold(String::new(), |mut out, c| {
+                if "\\^$.|?+()[]{}".contains(c) {
+                    out.push('\\');
+                }
+                out.push(c);
+                out
+            })
+        })
+        .collect::<Vec<_>>()
+        .join(".*");
+    format!("^{}$", regex)
+}
+
+/// Handle the page's WebSocket connections to matching URLs in the host
+///
+/// For IPC over the WebSocket API without running a local server. After
+/// this call, `new WebSocket(url)` for a URL matching `url_pattern` (`*`
+/// matches any run of characters, e.g. `ws://app.local/*`) returns a
+/// stand-in socket. The handler is told when the page opens, sends on or
+/// closes one; reply with `wry_webview_websocket_send` and close from the
+/// host side with `wry_webview_websocket_close`. Other URLs get a real
+/// WebSocket. Text messages only; binary data is sent as its string form.
+///
+/// Applies to connections opened after the call, and from the start of
+/// later pages. A null handler stops interception.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_intercept_websocket(
+    window: WryWindow,
+    url_pattern: *const c_char,
+    handler: WebSocketHandler,
+    user_data: *mut c_void,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    if let Some(id) = state.websocket_script.take() {
+        state.remove_init_script(id);
+        if let Some(webview) = &state.webview {
+            if let Err(e) = webview.evaluate_script("window.__wryWebSocketPattern = null;") {
+                log::warn!("Failed to stop WebSocket interception: {}", e);
+            }
+        }
+    }
+    crate::callbacks::set_global_websocket_handler(state.id, handler, user_data);
+
+    if handler.is_none() {
+        log::debug!("WebSocket interception stopped");
+        return true;
+    }
+
+    let pattern = match c_str_to_string(url_pattern) {
+        Some(p) if !p.is_empty() => p,
+        _ => {
+            set_last_error("Null or empty URL pattern");
+            crate::callbacks::set_global_websocket_handler(state.id, None, std::ptr::null_mut());
+            return false;
+        }
+    };
+
+    let script = format!(
+        "{}\nwindow.__wryWebSocketPattern = new RegExp({});",
+        WEBSOCKET_INTERCEPT_SCRIPT,
+        serde_json::Value::String(websocket_pattern_regex(&pattern))
+    );
+    match state.add_init_script(script) {
+        Ok(id) => state.websocket_script = Some(id),
+        Err(e) => {
+            crate::callbacks::set_global_websocket_handler(state.id, None, std::ptr::null_mut());
+            set_last_error(e);
+            return false;
+        }
+    }
+
+    log::debug!("Intercepting WebSockets matching {}", pattern);
+    true
+}
+
+/// Deliver a text message to an intercepted WebSocket
+///
+/// The page receives it as a `message` event. Returns false if the script
+/// could not be run; unknown or closed connections are ignored by the page.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_websocket_send(
+    window: WryWindow,
+    connection_id: u32,
+    message: *const c_char,
+) -> bool {
+    let message = match c_str_to_string(message) {
+        Some(m) => m,
+        None => {
+            set_last_error("Null or invalid message");
+            return false;
+        }
+    };
+
+    run_websocket_script(
+        window,
+        &format!(
+            "window.__wryWebSocketDeliver && window.__wryWebSocketDeliver({}, {});",
+            connection_id,
+            serde_json::Value::String(message)
+        ),
+    )
+}
+
+/// Close an intercepted WebSocket from the host side
+///
+/// The page receives a clean `close` event; the handler is not called.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_websocket_close(
+    window: WryWindow,
+    connection_id: u32,
+) -> bool {
+    run_websocket_script(
+        window,
+        &format!(
+            "window.__wryWebSocketClose && window.__wryWebSocketClose({});",
+            connection_id
+        ),
+    )
+}
+
+unsafe fn run_websocket_script(window: WryWindow, script: &str) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => {
+            set_last_error("No webview available");
+            return false;
+        }
+    };
+
+    match webview.evaluate_script(script) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Script execution failed: {}", e));
+            false
+        }
+    }
+}
+
+// ============================================================================
+// Hotkeys
+// ============================================================================
+
+/// A key combination registered with `wry_webview_register_hotkey`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hotkey {
+    pub id: u32,
+    /// `KeyboardEvent.code` name, e.g. "KeyK" or "MediaPlayPause"
+    pub code: String,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl Hotkey {
+    fn from_json(id: u32, json: &str) -> Result<Self, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("Invalid hotkey JSON: {}", e))?;
+        let code = value["code"]
+            .as_str()
+            .filter(|c| !c.is_empty())
+            .ok_or("Hotkey JSON needs a string \"code\" field")?;
+        let flag = |name: &str| value[name].as_bool().unwrap_or(false);
+        Ok(Self {
+            id,
+            code: code.to_string(),
+            ctrl: flag("ctrl"),
+            shift: flag("shift"),
+            alt: flag("alt"),
+            meta: flag("meta"),
+        })
+    }
+
+    /// Whether a press of `code` with `modifiers` held triggers this hotkey
+    fn matches(&self, code: &str, modifiers: ModifiersState) -> bool {
+        self.code == code
+            && self.ctrl == modifiers.control_key()
+            && self.shift == modifiers.shift_key()
+            && self.alt == modifiers.alt_key()
+            && self.meta == modifiers.super_key()
+    }
+}
+
+/// Dispatch `wry-hotkey` for page key presses matching `window.__wryHotkeys`
+const HOTKEY_SCRIPT: &str = r#"(function() {
+    if (window.__wryHotkeysHooked) return;
+    window.__wryHotkeysHooked = true;
+    window.__wryDispatchHotkey = function(id) {
+        window.dispatchEvent(new CustomEvent('wry-hotkey', { detail: { id: id } }));
+    };
+    document.addEventListener('keydown', function(e) {
+        if (e.repeat) return;
+        (window.__wryHotkeys || []).forEach(function(k) {
+            if (k.code === e.code && k.ctrl === e.ctrlKey && k.shift === e.shiftKey
+                && k.alt === e.altKey && k.meta === e.metaKey) {
+                window.__wryDispatchHotkey(k.id);
+            }
+        });
+    }, true);
+})();"#;
+
+/// Reinstall the hotkey init script for the current `state.hotkeys`
+fn update_hotkey_script(state: &mut WindowState) -> Result<(), String> {
+    if let Some(id) = state.hotkey_script.take() {
+        state.remove_init_script(id);
+    }
+    if state.hotkeys.is_empty() {
+        if let Some(webview) = &state.webview {
+            if let Err(e) = webview.evaluate_script("window.__wryHotkeys = [];") {
+                log::warn!("Failed to clear hotkeys: {}", e);
+            }
+        }
+        return Ok(());
+    }
+
+    let hotkeys: Vec<serde_json::Value> = state
+        .hotkeys
+        .iter()
+        .map(|k| {
+            serde_json::json!({
+                "id": k.id,
+                "code": k.code,
+                "ctrl": k.ctrl,
+                "shift": k.shift,
+                "alt": k.alt,
+                "meta": k.meta,
+            })
+        })
+        .collect();
+    let hotkeys = serde_json::Value::from(hotkeys);
+    let script = format!("window.__wryHotkeys = {};\n{}", hotkeys, HOTKEY_SCRIPT);
+    state.hotkey_script = Some(state.add_init_script(script)?);
+    Ok(())
+}
+
+/// Fire hotkeys matching a key press the window received while its page
+/// did not have focus
+pub(crate) fn dispatch_hotkeys(state: &WindowState, event: &WindowEvent) {
+    let key_event = match event {
+        WindowEvent::KeyboardInput { event, .. } => event,
+        _ => return,
+    };
+    if key_event.state != ElementState::Pressed || key_event.repeat || state.hotkeys.is_empty() {
+        return;
+    }
+    let webview = match &state.webview {
+        Some(wv) => wv,
+        None => return,
+    };
+
+    let code = format!("{:?}", key_event.physical_key);
+    for hotkey in &state.hotkeys {
+        if hotkey.matches(&code, state.modifiers) {
+            // A focused page already saw the key through its own listener
+            let script = format!(
+                "if (!document.hasFocus() && window.__wryDispatchHotkey) \
+                 window.__wryDispatchHotkey({});",
+                hotkey.id
+            );
+            if let Err(e) = webview.evaluate_script(&script) {
+                log::warn!("Failed to dispatch hotkey {}: {}", hotkey.id, e);
+            }
+        }
+    }
+}
+
+/// Register a keyboard shortcut scoped to a webview's window
+///
+/// `key_json` describes the combination, e.g.
+/// `{"code":"KeyK","ctrl":true,"shift":false,"alt":false,"meta":false}`,
+/// where `code` is a `KeyboardEvent.code` name and missing modifiers are
+/// false. While the window is in the foreground the shortcut fires whether
+/// or not the page has focus, as a `wry-hotkey` event on `window` with
+/// `detail.id` set to `id`. Registering an existing `id` replaces it.
+/// `id` must be non-zero, since `wry_hotkey_match_event` returns 0 for
+/// events that match no hotkey.
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_register_hotkey(
+    window: WryWindow,
+    key_json: *const c_char,
+    id: u32,
+) -> bool {
+    if id == 0 {
+        set_last_error("Hotkey id must be non-zero");
+        return false;
+    }
+
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    let json = match c_str_to_string(key_json) {
+        Some(j) => j,
+        None => {
+            set_last_error("Null or invalid hotkey JSON");
+            return false;
+        }
+    };
+    let hotkey = match Hotkey::from_json(id, &json) {
+        Ok(h) => h,
+        Err(e) => {
+            set_last_error(e);
+            return false;
+        }
+    };
+
+    // Lets wry_hotkey_match_event find the window from its id
+    state.callbacks.set_window_ptr(window);
+
+    log::debug!("Registering webview hotkey {}: {:?}", id, hotkey);
+    let previous = state.hotkeys.clone();
+    state.hotkeys.retain(|k| k.id != id);
+    state.hotkeys.push(hotkey);
+    if let Err(e) = update_hotkey_script(state) {
+        set_last_error(e);
+        state.hotkeys = previous;
+        let _ = update_hotkey_script(state);
+        return false;
+    }
+    true
+}
+
+/// Remove a shortcut added with `wry_webview_register_hotkey`
+///
+/// # Returns
+/// false if no hotkey with `id` is registered
+#[no_mangle]
+pub unsafe extern "C" fn wry_webview_unregister_hotkey(window: WryWindow, id: u32) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let count = state.hotkeys.len();
+    state.hotkeys.retain(|k| k.id != id);
+    if state.hotkeys.len() == count {
+        set_last_error(format!("Hotkey {} is not registered", id));
+        return false;
+    }
+
+    if let Err(e) = update_hotkey_script(state) {
+        set_last_error(format!("Failed to reinstall the remaining hotkeys: {}", e));
+        return false;
+    }
+    true
+}
+
+/// Look up the hotkey a `window-keyboard-input` event triggers
+///
+/// For hosts handling keys in the event loop callback: pass the event JSON
+/// and get back the id given to `wry_webview_register_hotkey` for that
+/// window, matched with the modifiers currently held. Returns 0 when no
+/// hotkey matches, for other event types, and for key releases and
+/// repeats, so 0 should not be used as a hotkey id. Call on the UI thread.
+#[no_mangle]
+pub unsafe extern "C" fn wry_hotkey_match_event(event_json: *const c_char) -> u32 {
+    let json = match c_str_to_string(event_json) {
+        Some(j) => j,
+        None => {
+            set_last_error("Null or invalid event JSON");
+            return 0;
+        }
+    };
+    let event: serde_json::Value = match serde_json::from_str(&json) {
+        Ok(v) => v,
+        Err(e) => {
+            set_last_error(format!("Failed to parse event JSON: {}", e));
+            return 0;
+        }
+    };
+
+    if event["type"] != "window-keyboard-input"
+        || event["state"] != "Pressed"
+        || event["repeat"].as_bool().unwrap_or(false)
+    {
+        return 0;
+    }
+    let (window_id, code) = match (event["window_id"].as_str(), event["physical_key"].as_str()) {
+        (Some(w), Some(c)) => (w, c),
+        _ => return 0,
+    };
+
+    let state = match crate::callbacks::find_window(window_id).and_then(|w| get_window_state(w)) {
+        Some(s) => s,
+        None => return 0,
+    };
+    state
+        .hotkeys
+        .iter()
+        .find(|k| k.matches(code, state.modifiers))
+        .map_or(0, |k| k.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn websocket_pattern_regex_turns_stars_into_wildcards() {
+        assert_eq!(websocket_pattern_regex("ws://app/*"), "^ws://app/.*$");
+        assert_eq!(websocket_pattern_regex("*://*/socket"), "^.*://.*/socket$");
+        assert_eq!(websocket_pattern_regex("*"), "^.*$");
+    }
+
+    #[test]
+    fn websocket_pattern_regex_escapes_metacharacters() {
+        assert_eq!(
+            websocket_pattern_regex("ws://app.local/"),
+            r"^ws://app\.local/$"
+        );
+        assert_eq!(
+            websocket_pattern_regex(r"a^b$c|d?e+f(g)h[i]j{k}l\m"),
+            r"^a\^b\$c\|d\?e\+f\(g\)h\[i\]j\{k\}l\\m$"
+        );
+    }
+
+    #[test]
+    fn websocket_pattern_regex_keeps_plain_text_and_escapes_beside_stars() {
+        assert_eq!(
+            websocket_pattern_regex("ws://host:8080/a-b_c"),
+            "^ws://host:8080/a-b_c$"
+        );
+        assert_eq!(
+            websocket_pattern_regex("ws://*.app/?id=*"),
+            r"^ws://.*\.app/\?id=.*$"
+        );
+    }
+
+    #[test]
+    fn hotkey_from_json_reads_each_modifier() {
+        let hotkey = Hotkey::from_json(
+            7,
+            r#"{"code":"KeyK","ctrl":true,"shift":false,"alt":true,"meta":false}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            hotkey,
+            Hotkey {
+                id: 7,
+                code: "KeyK".to_string(),
+                ctrl: true,
+                shift: false,
+                alt: true,
+                meta: false,
+            }
+        );
+    }
+
+    #[test]
+    fn hotkey_from_json_defaults_missing_modifiers_to_false() {
+        let hotkey = Hotkey::from_json(1, r#"{"code":"F5"}"#).unwrap();
+        assert!(!hotkey.ctrl && !hotkey.shift && !hotkey.alt && !hotkey.meta);
+    }
+
+    #[test]
+    fn hotkey_from_json_does_not_accept_modifier_aliases() {
+        // Only the DOM modifier names are read, so accelerator-style
+        // aliases must not silently turn into Ctrl or Meta
+        let hotkey =
+            Hotkey::from_json(1, r#"{"code":"KeyS","CmdOrCtrl":true,"control":true}"#).unwrap();
+        assert!(!hotkey.ctrl);
+        assert!(!hotkey.meta);
+    }
+
+    #[test]
+    fn hotkey_from_json_rejects_bad_input() {
+        assert!(Hotkey::from_json(1, "not json").is_err());
+        assert!(Hotkey::from_json(1, r#"{"ctrl":true}"#).is_err());
+        assert!(Hotkey::from_json(1, r#"{"code":""}"#).is_err());
+        assert!(Hotkey::from_json(1, r#"{"code":3}"#).is_err());
+    }
+
+    #[test]
+    fn hotkey_matches_ctrl_and_meta_separately() {
+        let ctrl = Hotkey::from_json(1, r#"{"code":"KeyK","ctrl":true}"#).unwrap();
+        let meta = Hotkey::from_json(2, r#"{"code":"KeyK","meta":true}"#).unwrap();
+
+        assert!(ctrl.matches("KeyK", ModifiersState::CONTROL));
+        assert!(!ctrl.matches("KeyK", ModifiersState::SUPER));
+        assert!(meta.matches("KeyK", ModifiersState::SUPER));
+        assert!(!meta.matches("KeyK", ModifiersState::CONTROL));
+    }
+
+    #[test]
+    fn hotkey_matches_requires_exact_modifiers_and_code() {
+        let hotkey = Hotkey::from_json(1, r#"{"code":"KeyK","ctrl":true}"#).unwrap();
+
+        assert!(!hotkey.matches("KeyK", ModifiersState::empty()));
+        assert!(!hotkey.matches("KeyK", ModifiersState::CONTROL | ModifiersState::SHIFT));
+        assert!(!hotkey.matches("KeyJ", ModifiersState::CONTROL));
+    }
+}
@@ -3,20 +3,37 @@
 //! Creates and manages windows with associated webviews.
 
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::ffi::CStr;
 use std::os::raw::c_void;
+use std::rc::Rc;
+use std::sync::Arc;
 
 use http::{Response, StatusCode};
 use tao::dpi::{LogicalPosition, LogicalSize};
 use tao::event_loop::EventLoopProxy;
+use tao::keyboard::ModifiersState;
 use tao::window::{Window, WindowBuilder, WindowId};
-use wry::{WebView, WebViewBuilder};
+use wry::{ProxyConfig, ProxyEndpoint, WebView, WebViewBuilder};
 
 use crate::app::{AppState, UserEvent};
+use crate::appearance::WindowAppearanceObserver;
 use crate::callbacks::WindowCallbacks;
+use crate::cursor::CustomCursor;
 use crate::error::set_last_error;
+use crate::protocol::{
+    apply_cors_headers, apply_default_headers, cors_preflight_response, DefaultHeaders,
+    ProtocolCache,
+};
 use crate::string::c_str_to_string;
-use crate::types::{WryApp, WryWindow, WryWindowParams};
+use crate::types::{
+    WryApp, WryLoadingState, WryMediaPermissions, WryProxyConfig, WryProxyKind, WryWindow,
+    WryWindowParams,
+};
+use crate::webview::{
+    add_document_script, remove_document_script, DocumentScript, Hotkey, PreloadModule,
+};
+use crate::window_ops::GestureMonitor;
 
 /// State for a single window
 pub struct WindowState {
@@ -30,6 +47,70 @@ pub struct WindowState {
     pub callbacks: WindowCallbacks,
     /// Event loop proxy for thread-safe operations
     pub event_loop_proxy: EventLoopProxy<UserEvent>,
+    /// Scripts added after creation, registered with the engine by ID
+    pub init_scripts: Vec<(u32, DocumentScript)>,
+    /// Next ID handed out by `wry_webview_add_init_script`
+    pub next_init_script_id: u32,
+    /// Init script installed by `wry_webview_set_default_context_menu_enabled`
+    pub context_menu_script: Option<u32>,
+    /// Init script installed by `wry_webview_set_allow_text_selection`
+    pub text_selection_script: Option<u32>,
+    /// Init script installed by `wry_webview_disable_zoom`
+    pub zoom_script: Option<u32>,
+    /// Init script installed by `wry_webview_set_allow_link_opening`
+    pub link_opening_script: Option<u32>,
+    /// Init script installed by `wry_webview_set_take_focus_on_click`
+    pub focus_on_click_script: Option<u32>,
+    /// Init script installed by `wry_webview_intercept_websocket`
+    pub websocket_script: Option<u32>,
+    /// Init script installed by `wry_window_set_drag_accept_types`
+    pub drag_accept_script: Option<u32>,
+    /// Init script installed by `wry_window_set_content_insets`
+    pub content_insets_script: Option<u32>,
+    /// Window whose webview gets this window's key events, set by
+    /// `wry_window_set_keyboard_input_redirect`
+    pub key_redirect_target: Option<WindowId>,
+    /// Modifier keys last reported to this window
+    pub modifiers: ModifiersState,
+    /// Shortcuts added with `wry_webview_register_hotkey`
+    pub hotkeys: Vec<Hotkey>,
+    /// Init script carrying `hotkeys` to the page
+    pub hotkey_script: Option<u32>,
+    /// Modules added with `wry_webview_add_preload_module`
+    pub preload_modules: Vec<PreloadModule>,
+    /// Init script defining `preload_modules` under one import map
+    pub preload_module_script: Option<u32>,
+    /// Headers added to custom protocol responses, shared with the handlers
+    pub default_headers: DefaultHeaders,
+    /// Updated from page load events and navigation failures
+    pub loading_state: WryLoadingState,
+    /// Set once the first page has finished loading
+    pub initial_load_finished: bool,
+    /// Cursor set by `wry_window_set_cursor_icon_rgba`, kept alive while shown
+    pub custom_cursor: Option<CustomCursor>,
+    /// Last value passed to `wry_window_set_always_on_bottom`
+    pub always_on_bottom: bool,
+    /// Last value passed to `wry_window_set_content_protected`
+    pub content_protected: bool,
+    /// Last value passed to `wry_window_set_focusable`
+    pub focusable: bool,
+    /// Last visibility set with `wry_webview_show` / `wry_webview_hide`
+    pub webview_visible: bool,
+    /// Last value passed to `wry_window_set_touch_enabled`, touch events are
+    /// dropped while false
+    pub touch_enabled: bool,
+    /// Last value passed to `wry_window_set_pinch_zoom_enabled`
+    pub pinch_zoom_enabled: bool,
+    /// Last value passed to `wry_window_set_rotate_gesture_enabled`
+    pub rotate_gesture_enabled: bool,
+    /// Event monitor reporting the enabled gestures, removed on drop
+    pub gesture_monitor: Option<GestureMonitor>,
+    /// Observer feeding the appearance callback, removed on drop
+    pub appearance_observer: Option<WindowAppearanceObserver>,
+    /// Whether the platform TLS error hook has been connected to the webview
+    pub ssl_error_hook_installed: bool,
+    /// Permissions read by the platform permission hook, None until it is connected
+    pub media_permissions: Option<Rc<Cell<WryMediaPermissions>>>,
 }
 
 impl WindowState {
@@ -44,6 +125,59 @@ impl WindowState {
         let _ = self.event_loop_proxy.send_event(UserEvent::DestroyWindow(self.id));
     }
 
+    /// Add a runtime init script, evaluating it in the current page
+    ///
+    /// The script is registered to run as each later document is created,
+    /// before the page's own scripts. Returns its ID, which is never 0.
+    pub fn add_init_script(&mut self, script: String) -> Result<u32, String> {
+        let webview = self.webview.as_ref().ok_or("No webview available")?;
+        let document_script = add_document_script(webview, &script)?;
+        if let Err(e) = webview.evaluate_script(&script) {
+            log::error!("Failed to evaluate init script in current page: {}", e);
+        }
+
+        let id = self.next_init_script_id;
+        self.next_init_script_id += 1;
+        self.init_scripts.push((id, document_script));
+        Ok(id)
+    }
+
+    /// Add a runtime init script that needs the document element
+    ///
+    /// Document-start scripts can run before the parser has created
+    /// `<html>`, so `script` is held back until it exists, which is still
+    /// ahead of any page script.
+    pub fn add_dom_init_script(&mut self, script: &str) -> Result<u32, String> {
+        self.add_init_script(format!(
+            r#"(function() {{
+    var run = function() {{
+{}
+    }};
+    if (document.documentElement) return run();
+    new MutationObserver(function(_, observer) {{
+        if (!document.documentElement) return;
+        observer.disconnect();
+        run();
+    }}).observe(document, {{ childList: true }});
+}})();"#,
+            script
+        ))
+    }
+
+    /// Remove a runtime init script, returns false if the ID is unknown
+    pub fn remove_init_script(&mut self, id: u32) -> bool {
+        let index = match self.init_scripts.iter().position(|(script_id, _)| *script_id == id) {
+            Some(i) => i,
+            None => return false,
+        };
+
+        let (_, document_script) = self.init_scripts.remove(index);
+        if let Some(webview) = &self.webview {
+            remove_document_script(webview, &document_script);
+        }
+        true
+    }
+
     /// Send a message to the webview via event loop (thread-safe)
     pub fn send_message(&self, message: String) {
         let _ = self.event_loop_proxy.send_event(UserEvent::WebViewMessage {
@@ -76,6 +210,8 @@ pub unsafe extern "C" fn wry_window_create(
         return std::ptr::null_mut();
     }
 
+    crate::app::warn_if_not_main_thread("wry_window_create");
+
     log::info!("wry_window_create called");
     let state = &mut *(app as *mut AppState);
     let params = &*params;
@@ -89,6 +225,15 @@ pub unsafe extern "C" fn wry_window_create(
         }
     };
 
+    // Rejected up front so no window is left behind without its proxy
+    let proxy_config = match proxy_config(&params.proxy) {
+        Ok(p) => p,
+        Err(e) => {
+            set_last_error(e);
+            return std::ptr::null_mut();
+        }
+    };
+
     // Build window
     let title = c_str_to_string(params.title).unwrap_or_else(|| "Untitled".to_string());
     log::debug!("Creating window with title: {}", title);
@@ -141,30 +286,99 @@ pub unsafe extern "C" fn wry_window_create(
     let window_id = window.id();
     log::debug!("Window created with id: {:?}", window_id);
 
+    #[cfg(target_os = "windows")]
+    crate::appearance::watch_window_settings(&window, state.event_loop_proxy.clone());
+
     // Build webview with IPC handler wired to callbacks
     let proxy = state.event_loop_proxy.clone();
 
     // Clone the custom protocols for the webview
     // We need to clone the HashMap since we can't borrow state during window creation
     let protocols: Vec<_> = state.custom_protocols.iter()
-        .map(|(scheme, handler)| (scheme.clone(), handler.callback, handler.user_data))
+        .map(|(scheme, handler)| ProtocolInfo {
+            scheme: scheme.clone(),
+            callback: handler.callback,
+            user_data: handler.user_data,
+            cache: handler.cache.clone(),
+        })
         .collect();
 
     log::info!("Passing {} custom protocol(s) to webview", protocols.len());
-    for (scheme, _, _) in &protocols {
-        log::debug!("  - scheme: {}", scheme);
+    for protocol in &protocols {
+        log::debug!("  - scheme: {}", protocol.scheme);
     }
 
-    let webview = create_webview_for_window(&window, params, window_id, proxy.clone(), &protocols);
-
-    let window_state = Box::new(WindowState {
+    let default_headers = DefaultHeaders::default();
+    let webview = create_webview_for_window(
+        &window,
+        params,
+        window_id,
+        proxy.clone(),
+        &protocols,
+        &default_headers,
+        proxy_config,
+    );
+
+    let mut window_state = Box::new(WindowState {
         id: window_id,
         window,
         webview,
         callbacks: WindowCallbacks::new(window_id),
         event_loop_proxy: proxy,
+        init_scripts: Vec::new(),
+        next_init_script_id: 1,
+        context_menu_script: None,
+        text_selection_script: None,
+        zoom_script: None,
+        link_opening_script: None,
+        focus_on_click_script: None,
+        websocket_script: None,
+        content_insets_script: None,
+        drag_accept_script: None,
+        key_redirect_target: None,
+        modifiers: ModifiersState::empty(),
+        hotkeys: Vec::new(),
+        hotkey_script: None,
+        preload_modules: Vec::new(),
+        preload_module_script: None,
+        default_headers,
+        loading_state: WryLoadingState::Idle,
+        initial_load_finished: false,
+        custom_cursor: None,
+        always_on_bottom: false,
+        content_protected: false,
+        focusable: true,
+        webview_visible: true,
+        touch_enabled: true,
+        pinch_zoom_enabled: false,
+        rotate_gesture_enabled: false,
+        gesture_monitor: None,
+        appearance_observer: None,
+        ssl_error_hook_installed: false,
+        media_permissions: None,
     });
 
+    if !params.default_context_menu_enabled {
+        if let Err(e) = crate::webview::set_context_menu_blocked(&mut window_state, true) {
+            log::warn!("Failed to disable the default context menu: {}", e);
+        }
+    }
+    if !params.allow_text_selection {
+        if let Err(e) = crate::webview::set_text_selection_blocked(&mut window_state, true) {
+            log::warn!("Failed to prevent text selection: {}", e);
+        }
+    }
+    if params.disable_zoom {
+        if let Err(e) = crate::webview::disable_zoom(&mut window_state) {
+            log::warn!("Failed to disable zoom: {}", e);
+        }
+    }
+    if !params.take_focus_on_click {
+        if let Err(e) = crate::webview::set_take_focus_on_click(&mut window_state, false) {
+            log::warn!("Failed to keep focus off the webview on click: {}", e);
+        }
+    }
+
     // Get pointer before moving into hashmap
     let ptr = window_state.as_ptr();
 
@@ -174,32 +388,139 @@ pub unsafe extern "C" fn wry_window_create(
     ptr
 }
 
-/// Protocol info tuple: (scheme, callback, user_data)
-type ProtocolInfo = (String, crate::types::CustomProtocolCallback, *mut c_void);
+/// Protocol info copied out of the app state for webview creation
+#[derive(Clone)]
+struct ProtocolInfo {
+    scheme: String,
+    callback: crate::types::CustomProtocolCallback,
+    user_data: *mut c_void,
+    cache: Option<Arc<ProtocolCache>>,
+}
+
+/// Convert the FFI proxy settings, None if no proxy is configured
+unsafe fn proxy_config(config: &WryProxyConfig) -> Result<Option<ProxyConfig>, String> {
+    if config.kind == WryProxyKind::None {
+        return Ok(None);
+    }
+    let host = match c_str_to_string(config.host).filter(|h| !h.is_empty()) {
+        Some(h) => h,
+        None => return Ok(None),
+    };
+
+    if !config.username.is_null() || !config.password.is_null() {
+        return Err("Proxy credentials are not supported".to_string());
+    }
+
+    let endpoint = ProxyEndpoint {
+        host,
+        port: config.port.to_string(),
+    };
+    log::info!("Using {:?} proxy {}:{}", config.kind, endpoint.host, endpoint.port);
+    match config.kind {
+        WryProxyKind::Http => Ok(Some(ProxyConfig::Http(endpoint))),
+        WryProxyKind::Socks5 => Ok(Some(ProxyConfig::Socks5(endpoint))),
+        kind => Err(format!("Unsupported proxy kind: {:?}", kind)),
+    }
+}
+
+/// WebView2 arguments opening a DevTools Protocol port
+///
+/// Setting arguments replaces wry's defaults, so they are repeated here.
+#[cfg(target_os = "windows")]
+fn browser_args_with_debugging_port(
+    params: &WryWindowParams,
+    proxy_config: Option<&ProxyConfig>,
+    port: u16,
+) -> String {
+    let mut args = String::from("--disable-features=msWebOOUI,msPdfOOUI,msSmartScreenProtection");
+    if params.autoplay_enabled {
+        args.push_str(" --autoplay-policy=no-user-gesture-required");
+    }
+    match proxy_config {
+        Some(ProxyConfig::Http(endpoint)) => {
+            args.push_str(&format!(" --proxy-server=http://{}:{}", endpoint.host, endpoint.port));
+        }
+        Some(ProxyConfig::Socks5(endpoint)) => {
+            args.push_str(&format!(" --proxy-server=socks5://{}:{}", endpoint.host, endpoint.port));
+        }
+        None => {}
+    }
+    args.push_str(&format!(" --remote-debugging-port={}", port));
+    args
+}
 
 /// Create a webview for a window
 fn create_webview_for_window(
     window: &Window,
     params: &WryWindowParams,
     window_id: WindowId,
-    _proxy: EventLoopProxy<UserEvent>,
+    proxy: EventLoopProxy<UserEvent>,
     protocols: &[ProtocolInfo],
+    default_headers: &DefaultHeaders,
+    proxy_config: Option<ProxyConfig>,
 ) -> Option<WebView> {
     let mut builder = WebViewBuilder::new();
 
     // Inject the JavaScript bridge as an initialization script
     builder = builder.with_initialization_script(crate::bridge::BRIDGE_SCRIPT);
 
+    // Caller-provided init scripts run after the bridge so they can use it
+    if !params.init_scripts.is_null() {
+        let scripts =
+            unsafe { std::slice::from_raw_parts(params.init_scripts, params.init_script_count) };
+        for script in scripts.iter().filter_map(|&s| unsafe { c_str_to_string(s) }) {
+            builder = builder.with_initialization_script(&script);
+        }
+    }
+
     // Register custom protocols BEFORE setting URL
     // Use async protocol handler - required for WebKitGTK to work properly
-    for (scheme, callback, user_data) in protocols.iter().cloned() {
+    for protocol in protocols.iter().cloned() {
+        let ProtocolInfo {
+            scheme,
+            callback,
+            user_data,
+            cache,
+        } = protocol;
         log::info!("Registering custom protocol '{}' with webview (async)", scheme);
+        let default_headers = default_headers.clone();
         builder = builder.with_asynchronous_custom_protocol(
             scheme.clone(),
             move |_webview_id, request, responder| {
                 let uri = request.uri().to_string();
                 log::info!("Custom protocol request: {}", uri);
 
+                if let Some(preflight) = cors_preflight_response(&scheme, &request) {
+                    log::debug!("Answered CORS preflight: {}", uri);
+                    responder.respond(preflight.map(|_| Cow::Borrowed(&[] as &[u8])));
+                    return;
+                }
+
+                let respond = |response: Response<Cow<'static, [u8]>>| {
+                    let response = apply_default_headers(response, &default_headers);
+                    responder.respond(apply_cors_headers(response, &scheme, &request));
+                };
+
+                // Only GET and HEAD responses are safe to reuse
+                let cache =
+                    cache.as_ref().filter(|_| ProtocolCache::caches_method(request.method()));
+
+                // Serve from cache without a round-trip to the handler
+                if let Some(cache) = cache {
+                    if let Some((body, mime_type)) = cache.get(&uri) {
+                        log::debug!("Protocol cache hit: {}", uri);
+                        respond(
+                            Response::builder()
+                                .status(StatusCode::OK)
+                                .header("Content-Type", mime_type)
+                                .header("Cache-Control", cache.header_value())
+                                .body(Cow::Owned(body))
+                                .unwrap(),
+                        );
+                        return;
+                    }
+                }
+
                 // Prepare output variables
                 let mut out_data: *const u8 = std::ptr::null();
                 let mut out_len: usize = 0;
@@ -210,7 +531,7 @@ fn create_webview_for_window(
                     Ok(s) => s,
                     Err(_) => {
                         log::error!("Failed to convert URI to CString");
-                        responder.respond(
+                        respond(
                             Response::builder()
                                 .status(StatusCode::INTERNAL_SERVER_ERROR)
                                 .body(Cow::Borrowed(&[] as &[u8]))
@@ -232,7 +553,7 @@ fn create_webview_for_window(
 
                 if !handled || out_data.is_null() {
                     log::debug!("Protocol handler returned not handled for: {}", uri);
-                    responder.respond(
+                    respond(
                         Response::builder()
                             .status(StatusCode::NOT_FOUND)
                             .body(Cow::Borrowed(&[] as &[u8]))
@@ -253,13 +574,18 @@ fn create_webview_for_window(
 
                 log::info!("Protocol response: {} bytes, mime: {}", body.len(), mime_type);
 
-                responder.respond(
-                    Response::builder()
-                        .status(StatusCode::OK)
-                        .header("Content-Type", mime_type)
-                        .body(Cow::Owned(body))
-                        .unwrap(),
-                );
+                let status = StatusCode::OK;
+                let mut response = Response::builder()
+                    .status(status)
+                    .header("Content-Type", mime_type.as_str());
+
+                if let Some(cache) = cache {
+                    cache.insert(&uri, status, &body, &mime_type);
+                    response = response.header("Cache-Control", cache.header_value());
+                }
+
+                let response = response.body(Cow::Owned(body)).unwrap();
+                respond(response);
             },
         );
     }
@@ -281,6 +607,10 @@ fn create_webview_for_window(
         builder = builder.with_user_agent(&ua);
     }
 
+    if let Some(proxy) = proxy_config.clone() {
+        builder = builder.with_proxy_config(proxy);
+    }
+
     // Enable devtools in debug or if explicitly requested
     #[cfg(debug_assertions)]
     {
@@ -295,21 +625,52 @@ fn create_webview_for_window(
         builder = builder.with_transparent(true);
     }
 
+    builder = builder.with_autoplay(params.autoplay_enabled);
+
+    #[cfg(target_os = "windows")]
+    if let Some(port) = crate::app::remote_debugging_port() {
+        use wry::WebViewBuilderExtWindows;
+        builder = builder.with_additional_browser_args(browser_args_with_debugging_port(
+            params,
+            proxy_config.as_ref(),
+            port,
+        ));
+    }
+
     // Add IPC handler for messages from JavaScript
     // Note: We can't directly access callbacks here since WindowState doesn't exist yet.
     // The IPC messages need to be routed through a registry or the event loop.
     // For now, we log and the actual callback invocation happens via the callbacks system.
     let wid = window_id;
+    let ipc_proxy = proxy.clone();
     builder = builder.with_ipc_handler(move |req| {
         let body = req.body();
         log::debug!("IPC message received from window {:?}: {}", wid, body);
+        if crate::webview::handle_async_js_result(wid, body)
+            || crate::webview::handle_blocked_link(wid, body, &ipc_proxy)
+            || crate::webview::handle_websocket_message(wid, body)
+            || crate::webview::handle_focus_click(wid, body, &ipc_proxy)
+        {
+            return;
+        }
         // Callback invocation is handled by WindowCallbacks which has access to the window pointer
         // The C# side needs to set up callbacks that will be invoked
         // For direct callback access, we'd need a global callback registry
         crate::callbacks::invoke_message_callback(wid, body);
     });
 
+    // Report page loads through the event loop so state can be updated there
+    builder = builder.with_on_page_load_handler(move |event, url| {
+        let _ = proxy.send_event(UserEvent::PageLoad {
+            window_id,
+            finished: matches!(event, wry::PageLoadEvent::Finished),
+            url,
+        });
+    });
+
     // Build the webview
+    crate::app::mark_webview_created();
+
     #[cfg(not(target_os = "linux"))]
     let result = builder.build(window);
 
@@ -3,14 +3,21 @@
 //! Functions for controlling window visibility, size, position, and state.
 
 use std::ffi::c_char;
+use std::os::raw::c_void;
 
 use tao::dpi::{LogicalPosition, LogicalSize};
-use tao::window::Fullscreen;
+use tao::event::{ElementState, WindowEvent};
+use tao::keyboard::Key;
+use tao::window::{CursorIcon, Fullscreen, ResizeDirection, Theme, WindowId};
 
+use crate::app::AppState;
 use crate::error::set_last_error;
 use crate::string::{c_str_to_string, string_to_c_string};
-use crate::types::{WryPosition, WrySize, WryWindow};
-use crate::window::get_window_state;
+use crate::types::{
+    SnapshotCallback, WryColor, WryCursorIcon, WryDragType, WryPoint, WryPosition,
+    WryResizeDirection, WrySize, WryTitlebarStyle, WryWindow, WryWindowTheme,
+};
+use crate::window::{get_window_state, get_window_state_mut};
 
 // ============================================================================
 // Visibility
@@ -142,6 +149,79 @@ pub unsafe extern "C" fn wry_window_get_position(window: WryWindow) -> WryPositi
     }
 }
 
+/// Convert a point in the window's client area to screen coordinates
+///
+/// `local` is in logical pixels from the top left of the client area, the
+/// same units as page coordinates in the webview. `out` receives physical
+/// screen pixels.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_convert_point_to_screen(
+    window: WryWindow,
+    local: WryPoint,
+    out: *mut WryPoint,
+) -> bool {
+    if out.is_null() {
+        set_last_error("Null output pointer");
+        return false;
+    }
+
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let origin = match state.window.inner_position() {
+        Ok(pos) => pos,
+        Err(e) => {
+            set_last_error(format!("Window position unavailable: {}", e));
+            return false;
+        }
+    };
+
+    let scale = state.window.scale_factor();
+    *out = WryPoint {
+        x: origin.x as f64 + local.x * scale,
+        y: origin.y as f64 + local.y * scale,
+    };
+    true
+}
+
+/// Convert a point in screen coordinates to the window's client area
+///
+/// The inverse of `wry_window_convert_point_to_screen`: `screen` is in
+/// physical pixels and `out` receives logical client-area pixels.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_convert_point_from_screen(
+    window: WryWindow,
+    screen: WryPoint,
+    out: *mut WryPoint,
+) -> bool {
+    if out.is_null() {
+        set_last_error("Null output pointer");
+        return false;
+    }
+
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let origin = match state.window.inner_position() {
+        Ok(pos) => pos,
+        Err(e) => {
+            set_last_error(format!("Window position unavailable: {}", e));
+            return false;
+        }
+    };
+
+    let scale = state.window.scale_factor();
+    *out = WryPoint {
+        x: (screen.x - origin.x as f64) / scale,
+        y: (screen.y - origin.y as f64) / scale,
+    };
+    true
+}
+
 // ============================================================================
 // Window State
 // ============================================================================
@@ -182,6 +262,32 @@ pub unsafe extern "C" fn wry_window_unmaximize(window: WryWindow) {
     state.window.set_maximized(false);
 }
 
+/// Restore window to its normal state, neither minimized nor maximized
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_restore(window: WryWindow) {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return,
+    };
+
+    log::debug!("Restoring window");
+    state.window.set_minimized(false);
+    state.window.set_maximized(false);
+}
+
+/// Maximize window, or unmaximize it if already maximized
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_toggle_maximize(window: WryWindow) {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return,
+    };
+
+    let maximized = state.window.is_maximized();
+    log::debug!("Toggling maximize: {}", !maximized);
+    state.window.set_maximized(!maximized);
+}
+
 /// Set fullscreen mode
 #[no_mangle]
 pub unsafe extern "C" fn wry_window_set_fullscreen(window: WryWindow, fullscreen: bool) {
@@ -200,6 +306,79 @@ pub unsafe extern "C" fn wry_window_set_fullscreen(window: WryWindow, fullscreen
     }
 }
 
+/// Keep window below all other windows
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_always_on_bottom(window: WryWindow, always_on_bottom: bool) {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return,
+    };
+
+    log::debug!("Setting always on bottom: {}", always_on_bottom);
+    state.window.set_always_on_bottom(always_on_bottom);
+    state.always_on_bottom = always_on_bottom;
+}
+
+/// Check if window is kept below all other windows
+///
+/// tao has no getter, so this reports the last value set.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_is_always_on_bottom(window: WryWindow) -> bool {
+    match get_window_state(window) {
+        Some(state) => state.always_on_bottom,
+        None => false,
+    }
+}
+
+/// Prevent the window contents from being captured by screenshots or
+/// screen recording (Windows and macOS only)
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_content_protected(window: WryWindow, protected: bool) {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return,
+    };
+
+    log::debug!("Setting content protection: {}", protected);
+    state.window.set_content_protection(protected);
+    state.content_protected = protected;
+}
+
+/// Check if window content protection is enabled
+///
+/// tao has no getter, so this reports the last value set.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_is_content_protected(window: WryWindow) -> bool {
+    match get_window_state(window) {
+        Some(state) => state.content_protected,
+        None => false,
+    }
+}
+
+/// Set whether the window can receive keyboard focus
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_focusable(window: WryWindow, focusable: bool) {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return,
+    };
+
+    log::debug!("Setting focusable: {}", focusable);
+    state.window.set_focusable(focusable);
+    state.focusable = focusable;
+}
+
+/// Check if the window can receive keyboard focus
+///
+/// tao has no getter, so this reports the last value set (true by default).
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_is_focusable(window: WryWindow) -> bool {
+    match get_window_state(window) {
+        Some(state) => state.focusable,
+        None => false,
+    }
+}
+
 /// Focus window
 #[no_mangle]
 pub unsafe extern "C" fn wry_window_focus(window: WryWindow) {
@@ -212,6 +391,75 @@ pub unsafe extern "C" fn wry_window_focus(window: WryWindow) {
     state.window.set_focus();
 }
 
+/// Raise window above the app's other windows
+///
+/// Unlike always-on-top this is a one-off reordering.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_bring_to_front(window: WryWindow) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Bringing window to front");
+    bring_to_front(&state.window)
+}
+
+#[cfg(target_os = "macos")]
+fn bring_to_front(window: &tao::window::Window) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use tao::platform::macos::WindowExtMacOS;
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    if ns_window.is_null() {
+        return false;
+    }
+    unsafe {
+        let _: () = msg_send![ns_window, makeKeyAndOrderFront: std::ptr::null_mut::<AnyObject>()];
+    }
+    true
+}
+
+#[cfg(target_os = "windows")]
+fn bring_to_front(window: &tao::window::Window) -> bool {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::BringWindowToTop;
+
+    let hwnd = HWND(window.hwnd() as *mut _);
+    match unsafe { BringWindowToTop(hwnd) } {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("BringWindowToTop failed: {}", e));
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn bring_to_front(window: &tao::window::Window) -> bool {
+    use gtk::prelude::*;
+    use tao::platform::unix::WindowExtUnix;
+
+    match window.gtk_window().window() {
+        Some(gdk_window) => {
+            gdk_window.raise();
+            true
+        }
+        None => {
+            set_last_error("Window is not realized");
+            false
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn bring_to_front(_window: &tao::window::Window) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
 /// Close window
 ///
 /// This function is thread-safe - it dispatches via the event loop.
@@ -226,3 +474,1682 @@ pub unsafe extern "C" fn wry_window_close(window: WryWindow) {
     // Request destruction via event loop (thread-safe, actually removes window)
     state.request_destroy();
 }
+
+/// Show or hide the individual title bar buttons (macOS only)
+///
+/// Hidden buttons are removed from the title bar, unlike disabled ones which
+/// stay visible but greyed out.
+///
+/// # Returns
+/// false on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_window_buttons_visibility(
+    window: WryWindow,
+    close: bool,
+    minimize: bool,
+    zoom: bool,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!(
+        "Setting window buttons visible: close = {}, minimize = {}, zoom = {}",
+        close,
+        minimize,
+        zoom
+    );
+    set_window_buttons_visibility(&state.window, close, minimize, zoom)
+}
+
+#[cfg(target_os = "macos")]
+fn set_window_buttons_visibility(
+    window: &tao::window::Window,
+    close: bool,
+    minimize: bool,
+    zoom: bool,
+) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use tao::platform::macos::WindowExtMacOS;
+
+    // NSWindowCloseButton, NSWindowMiniaturizeButton, NSWindowZoomButton
+    const BUTTONS: [usize; 3] = [0, 1, 2];
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    if ns_window.is_null() {
+        return false;
+    }
+
+    for (kind, visible) in BUTTONS.into_iter().zip([close, minimize, zoom]) {
+        unsafe {
+            let button: *mut AnyObject = msg_send![ns_window, standardWindowButton: kind];
+            if !button.is_null() {
+                let _: () = msg_send![button, setHidden: !visible];
+            }
+        }
+    }
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_window_buttons_visibility(
+    _window: &tao::window::Window,
+    _close: bool,
+    _minimize: bool,
+    _zoom: bool,
+) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// System Move and Resize
+// ============================================================================
+
+/// Move the window with the mouse until the button is released
+///
+/// Hands the drag to the window manager, for custom title bars. Must be
+/// called while the left mouse button is held, typically from a mousedown
+/// handler in the page; otherwise it logs a warning and returns false.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_drag_begin(window: WryWindow) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if !left_mouse_button_down(&state.window) {
+        log::warn!("wry_window_drag_begin called without the left mouse button pressed");
+        set_last_error("Left mouse button is not pressed");
+        return false;
+    }
+
+    match state.window.drag_window() {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Failed to start window drag: {}", e));
+            false
+        }
+    }
+}
+
+/// Resize the window from `direction` with the mouse until the button is released
+///
+/// Same calling rules as `wry_window_drag_begin`. Not supported on macOS.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_resize_begin(
+    window: WryWindow,
+    direction: WryResizeDirection,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if !left_mouse_button_down(&state.window) {
+        log::warn!("wry_window_resize_begin called without the left mouse button pressed");
+        set_last_error("Left mouse button is not pressed");
+        return false;
+    }
+
+    let direction = match direction {
+        WryResizeDirection::East => ResizeDirection::East,
+        WryResizeDirection::North => ResizeDirection::North,
+        WryResizeDirection::NorthEast => ResizeDirection::NorthEast,
+        WryResizeDirection::NorthWest => ResizeDirection::NorthWest,
+        WryResizeDirection::South => ResizeDirection::South,
+        WryResizeDirection::SouthEast => ResizeDirection::SouthEast,
+        WryResizeDirection::SouthWest => ResizeDirection::SouthWest,
+        WryResizeDirection::West => ResizeDirection::West,
+    };
+
+    match state.window.drag_resize_window(direction) {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Failed to start window resize: {}", e));
+            false
+        }
+    }
+}
+
+// The press usually lands on the webview rather than the tao window, so ask
+// the OS for the button state instead of tracking window mouse events
+
+#[cfg(target_os = "macos")]
+pub(crate) fn left_mouse_button_down(_window: &tao::window::Window) -> bool {
+    use objc2::{class, msg_send};
+
+    let buttons: usize = unsafe { msg_send![class!(NSEvent), pressedMouseButtons] };
+    buttons & 1 != 0
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn left_mouse_button_down(_window: &tao::window::Window) -> bool {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_LBUTTON, VK_RBUTTON};
+    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_SWAPBUTTON};
+
+    // GetAsyncKeyState reports physical buttons, so follow a swapped primary button
+    let primary = if unsafe { GetSystemMetrics(SM_SWAPBUTTON) } != 0 {
+        VK_RBUTTON
+    } else {
+        VK_LBUTTON
+    };
+    (unsafe { GetAsyncKeyState(primary.0 as i32) } as u16) & 0x8000 != 0
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn left_mouse_button_down(window: &tao::window::Window) -> bool {
+    use gtk::gdk::prelude::SeatExt;
+    use gtk::gdk::ModifierType;
+    use gtk::prelude::WidgetExt;
+    use tao::platform::unix::WindowExtUnix;
+
+    let gtk_window = window.gtk_window();
+    let (gdk_window, pointer) = match (
+        gtk_window.window(),
+        gtk_window.display().default_seat().and_then(|seat| seat.pointer()),
+    ) {
+        (Some(w), Some(p)) => (w, p),
+        _ => return false,
+    };
+    let (_, _, _, mask) = gdk_window.device_position(&pointer);
+    mask.contains(ModifierType::BUTTON1_MASK)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub(crate) fn left_mouse_button_down(_window: &tao::window::Window) -> bool {
+    true
+}
+
+// ============================================================================
+// Theme
+// ============================================================================
+
+/// Set the preferred window theme, or follow the system with `Unspecified`
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_theme(window: WryWindow, theme: WryWindowTheme) {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return,
+    };
+
+    log::debug!("Setting theme: {:?}", theme);
+    state.window.set_theme(theme_from_ffi(theme));
+}
+
+/// Get the theme the window is actually using
+///
+/// Never returns `Unspecified`; when following the system this is the
+/// resolved system theme.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_get_theme(window: WryWindow) -> WryWindowTheme {
+    match get_window_state(window) {
+        Some(state) => theme_to_ffi(state.window.theme()),
+        None => WryWindowTheme::Light,
+    }
+}
+
+fn theme_from_ffi(theme: WryWindowTheme) -> Option<Theme> {
+    match theme {
+        WryWindowTheme::Unspecified => None,
+        WryWindowTheme::Light => Some(Theme::Light),
+        WryWindowTheme::Dark => Some(Theme::Dark),
+    }
+}
+
+fn theme_to_ffi(theme: Theme) -> WryWindowTheme {
+    match theme {
+        Theme::Dark => WryWindowTheme::Dark,
+        _ => WryWindowTheme::Light,
+    }
+}
+
+// ============================================================================
+// Title Bar
+// ============================================================================
+
+/// Apply a title bar preset (macOS only)
+///
+/// Each preset sets the full-size content style, title bar transparency,
+/// title visibility and toolbar together, replacing any earlier preset.
+/// With the full-size presets the page should leave room for the window
+/// buttons in its top-left corner.
+///
+/// # Returns
+/// false on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_titlebar_style(
+    window: WryWindow,
+    style: WryTitlebarStyle,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Setting titlebar style: {:?}", style);
+    set_titlebar_style(&state.window, style)
+}
+
+#[cfg(target_os = "macos")]
+fn set_titlebar_style(window: &tao::window::Window, style: WryTitlebarStyle) -> bool {
+    use objc2::rc::{Allocated, Retained};
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+    use tao::platform::macos::WindowExtMacOS;
+
+    // NSWindowStyleMaskFullSizeContentView
+    const FULL_SIZE_CONTENT_VIEW: usize = 1 << 15;
+    // NSWindowTitleVisible, NSWindowTitleHidden
+    const TITLE_VISIBLE: isize = 0;
+    const TITLE_HIDDEN: isize = 1;
+    // NSWindowToolbarStyleAutomatic, NSWindowToolbarStyleUnified,
+    // NSWindowToolbarStyleUnifiedCompact
+    const TOOLBAR_AUTOMATIC: isize = 0;
+    const TOOLBAR_UNIFIED: isize = 3;
+    const TOOLBAR_UNIFIED_COMPACT: isize = 4;
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    if ns_window.is_null() {
+        return false;
+    }
+
+    // (full size content, transparent title bar, title hidden, toolbar style)
+    let (full_size, transparent, title_hidden, toolbar) = match style {
+        WryTitlebarStyle::Default => (false, false, false, None),
+        WryTitlebarStyle::Hidden => (true, true, true, None),
+        WryTitlebarStyle::HiddenInset => (true, true, true, Some(TOOLBAR_UNIFIED)),
+        WryTitlebarStyle::FullSizeContent => (true, true, false, None),
+        WryTitlebarStyle::UnifiedCompact => (false, false, false, Some(TOOLBAR_UNIFIED_COMPACT)),
+    };
+
+    unsafe {
+        let mask: usize = msg_send![ns_window, styleMask];
+        let mask = if full_size {
+            mask | FULL_SIZE_CONTENT_VIEW
+        } else {
+            mask & !FULL_SIZE_CONTENT_VIEW
+        };
+        let _: () = msg_send![ns_window, setStyleMask: mask];
+        let _: () = msg_send![ns_window, setTitlebarAppearsTransparent: transparent];
+        let visibility = if title_hidden { TITLE_HIDDEN } else { TITLE_VISIBLE };
+        let _: () = msg_send![ns_window, setTitleVisibility: visibility];
+
+        // An empty toolbar is what makes the title bar taller and insets the buttons
+        match toolbar {
+            Some(toolbar_style) => {
+                let current: *mut AnyObject = msg_send![ns_window, toolbar];
+                if current.is_null() {
+                    let identifier = NSString::from_str("wry-titlebar");
+                    let toolbar: Allocated<AnyObject> = msg_send![class!(NSToolbar), alloc];
+                    let toolbar: Option<Retained<AnyObject>> =
+                        msg_send![toolbar, initWithIdentifier: &*identifier];
+                    let toolbar = match toolbar {
+                        Some(t) => t,
+                        None => {
+                            set_last_error("Failed to create toolbar");
+                            return false;
+                        }
+                    };
+                    let _: () = msg_send![ns_window, setToolbar: &*toolbar];
+                }
+                set_toolbar_style(ns_window, toolbar_style);
+            }
+            None => {
+                let _: () = msg_send![ns_window, setToolbar: std::ptr::null_mut::<AnyObject>()];
+                set_toolbar_style(ns_window, TOOLBAR_AUTOMATIC);
+            }
+        }
+    }
+    true
+}
+
+/// Set `NSWindow.toolbarStyle`, which only exists on macOS 11+
+#[cfg(target_os = "macos")]
+unsafe fn set_toolbar_style(ns_window: *mut objc2::runtime::AnyObject, style: isize) {
+    use objc2::{msg_send, sel};
+
+    let supported: bool = msg_send![ns_window, respondsToSelector: sel!(setToolbarStyle:)];
+    if supported {
+        let _: () = msg_send![ns_window, setToolbarStyle: style];
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_titlebar_style(_window: &tao::window::Window, _style: WryTitlebarStyle) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// Shadow
+// ============================================================================
+
+/// Set the color of the window drop shadow (macOS only)
+///
+/// AppKit draws the window shadow itself and has no API, public or private,
+/// that changes its color, so only an alpha of 0 is supported, which removes
+/// the shadow. Any other color fails with the last error set.
+///
+/// # Returns
+/// false for visible colors, and on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_shadow_color(
+    window: WryWindow,
+    color: *const WryColor,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if color.is_null() {
+        set_last_error("Null shadow color");
+        return false;
+    }
+
+    let color = *color;
+    log::debug!("Setting window shadow color: {:?}", color);
+    set_shadow_color(&state.window, color)
+}
+
+#[cfg(target_os = "macos")]
+fn set_shadow_color(window: &tao::window::Window, color: WryColor) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use tao::platform::macos::WindowExtMacOS;
+
+    if color.a != 0 {
+        set_last_error("Custom shadow colors are not supported by AppKit");
+        return false;
+    }
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    if ns_window.is_null() {
+        return false;
+    }
+
+    unsafe {
+        let _: () = msg_send![ns_window, setHasShadow: false];
+    }
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_shadow_color(_window: &tao::window::Window, _color: WryColor) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// Tabs
+// ============================================================================
+
+/// Group the window into a native tab bar (macOS only)
+///
+/// Sets the window's tabbing identifier and merges it into the tab bar of any
+/// open window with the same identifier.
+///
+/// # Returns
+/// false on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_tab_group_identifier(
+    window: WryWindow,
+    group_id: *const c_char,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let group_id = match c_str_to_string(group_id) {
+        Some(id) if !id.is_empty() => id,
+        _ => {
+            set_last_error("Null or empty tab group identifier");
+            return false;
+        }
+    };
+
+    log::debug!("Setting tab group identifier: {}", group_id);
+    set_tab_group(&state.window, &group_id)
+}
+
+/// Select the tab at `index` in the window's tab bar (macOS only)
+///
+/// # Returns
+/// false if the window is not in a tab bar, the index is out of range, or on
+/// other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_select_tab_at_index(window: WryWindow, index: usize) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Selecting tab {}", index);
+    select_tab(&state.window, index)
+}
+
+#[cfg(target_os = "macos")]
+fn set_tab_group(window: &tao::window::Window, group_id: &str) -> bool {
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use objc2_foundation::NSString;
+    use tao::platform::macos::WindowExtMacOS;
+
+    // NSWindowTabbingModePreferred, NSWindowAbove
+    const TABBING_MODE_PREFERRED: isize = 1;
+    const ORDERED_ABOVE: isize = 1;
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    if ns_window.is_null() {
+        return false;
+    }
+
+    let identifier = NSString::from_str(group_id);
+    unsafe {
+        let _: () = msg_send![ns_window, setTabbingIdentifier: &*identifier];
+        let _: () = msg_send![ns_window, setTabbingMode: TABBING_MODE_PREFERRED];
+
+        // Join the first other window already using this identifier
+        let app: *mut AnyObject = msg_send![class!(NSApplication), sharedApplication];
+        let windows: *mut AnyObject = msg_send![app, windows];
+        let count: usize = msg_send![windows, count];
+        for i in 0..count {
+            let other: *mut AnyObject = msg_send![windows, objectAtIndex: i];
+            if other == ns_window {
+                continue;
+            }
+            let other_id: *mut NSString = msg_send![other, tabbingIdentifier];
+            if !other_id.is_null() && *other_id == *identifier {
+                let _: () = msg_send![other, addTabbedWindow: ns_window, ordered: ORDERED_ABOVE];
+                break;
+            }
+        }
+    }
+    true
+}
+
+#[cfg(target_os = "macos")]
+fn select_tab(window: &tao::window::Window, index: usize) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use tao::platform::macos::WindowExtMacOS;
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    if ns_window.is_null() {
+        return false;
+    }
+
+    unsafe {
+        let tabs: *mut AnyObject = msg_send![ns_window, tabbedWindows];
+        if tabs.is_null() {
+            set_last_error("Window is not in a tab group");
+            return false;
+        }
+        let count: usize = msg_send![tabs, count];
+        if index >= count {
+            set_last_error(format!("Tab index {} out of range ({} tabs)", index, count));
+            return false;
+        }
+        let tab: *mut AnyObject = msg_send![tabs, objectAtIndex: index];
+        let _: () = msg_send![tab, makeKeyAndOrderFront: std::ptr::null_mut::<AnyObject>()];
+    }
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_tab_group(_window: &tao::window::Window, _group_id: &str) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+#[cfg(not(target_os = "macos"))]
+fn select_tab(_window: &tao::window::Window, _index: usize) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// Sheets
+// ============================================================================
+
+/// Attach `sheet` to `parent` as a modal sheet (macOS only)
+///
+/// The sheet slides down from the parent's title bar and blocks input to
+/// the parent until `wry_window_end_sheet` is called. `sheet` must be a
+/// separate window created with `wry_window_create`.
+///
+/// # Returns
+/// false if the parent already has a sheet, or on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_begin_sheet(parent: WryWindow, sheet: WryWindow) -> bool {
+    if parent == sheet {
+        set_last_error("A window cannot be its own sheet");
+        return false;
+    }
+
+    let parent_state = match get_window_state(parent) {
+        Some(s) => s,
+        None => return false,
+    };
+    let sheet_state = match get_window_state(sheet) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Beginning sheet on window {:?}", parent_state.id);
+    begin_sheet(&parent_state.window, &sheet_state.window)
+}
+
+/// Dismiss the sheet attached with `wry_window_begin_sheet` (macOS only)
+///
+/// The sheet window is hidden but not destroyed.
+///
+/// # Returns
+/// false if the window has no sheet, or on other platforms
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_end_sheet(parent: WryWindow) -> bool {
+    let state = match get_window_state(parent) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Ending sheet on window {:?}", state.id);
+    end_sheet(&state.window)
+}
+
+#[cfg(target_os = "macos")]
+fn begin_sheet(parent: &tao::window::Window, sheet: &tao::window::Window) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use tao::platform::macos::WindowExtMacOS;
+
+    let parent = parent.ns_window() as *mut AnyObject;
+    let sheet = sheet.ns_window() as *mut AnyObject;
+    if parent.is_null() || sheet.is_null() {
+        return false;
+    }
+
+    unsafe {
+        let attached: *mut AnyObject = msg_send![parent, attachedSheet];
+        if !attached.is_null() {
+            set_last_error("Window already has a sheet");
+            return false;
+        }
+        let _: () = msg_send![
+            parent,
+            beginSheet: sheet,
+            completionHandler: std::ptr::null_mut::<AnyObject>()
+        ];
+    }
+    true
+}
+
+#[cfg(target_os = "macos")]
+fn end_sheet(parent: &tao::window::Window) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use tao::platform::macos::WindowExtMacOS;
+
+    let parent = parent.ns_window() as *mut AnyObject;
+    if parent.is_null() {
+        return false;
+    }
+
+    unsafe {
+        let sheet: *mut AnyObject = msg_send![parent, attachedSheet];
+        if sheet.is_null() {
+            set_last_error("Window has no sheet");
+            return false;
+        }
+        let _: () = msg_send![parent, endSheet: sheet];
+    }
+    true
+}
+
+#[cfg(not(target_os = "macos"))]
+fn begin_sheet(_parent: &tao::window::Window, _sheet: &tao::window::Window) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+#[cfg(not(target_os = "macos"))]
+fn end_sheet(_parent: &tao::window::Window) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// Cursor
+// ============================================================================
+
+/// Set the cursor shape shown over the window
+///
+/// The webview sets its own cursor as the pointer moves over page content
+/// (e.g. a text cursor over text), which may replace this shape.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_cursor_icon(window: WryWindow, icon: WryCursorIcon) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Setting cursor icon: {:?}", icon);
+    state.window.set_cursor_icon(cursor_icon(icon));
+    state.custom_cursor = None;
+    true
+}
+
+fn cursor_icon(icon: WryCursorIcon) -> CursorIcon {
+    match icon {
+        WryCursorIcon::Default => CursorIcon::Default,
+        WryCursorIcon::Crosshair => CursorIcon::Crosshair,
+        WryCursorIcon::Hand => CursorIcon::Hand,
+        WryCursorIcon::Arrow => CursorIcon::Arrow,
+        WryCursorIcon::Move => CursorIcon::Move,
+        WryCursorIcon::Text => CursorIcon::Text,
+        WryCursorIcon::Wait => CursorIcon::Wait,
+        WryCursorIcon::Help => CursorIcon::Help,
+        WryCursorIcon::Progress => CursorIcon::Progress,
+        WryCursorIcon::NotAllowed => CursorIcon::NotAllowed,
+        WryCursorIcon::ContextMenu => CursorIcon::ContextMenu,
+        WryCursorIcon::Cell => CursorIcon::Cell,
+        WryCursorIcon::VerticalText => CursorIcon::VerticalText,
+        WryCursorIcon::Alias => CursorIcon::Alias,
+        WryCursorIcon::Copy => CursorIcon::Copy,
+        WryCursorIcon::NoDrop => CursorIcon::NoDrop,
+        WryCursorIcon::Grab => CursorIcon::Grab,
+        WryCursorIcon::Grabbing => CursorIcon::Grabbing,
+        WryCursorIcon::AllScroll => CursorIcon::AllScroll,
+        WryCursorIcon::ZoomIn => CursorIcon::ZoomIn,
+        WryCursorIcon::ZoomOut => CursorIcon::ZoomOut,
+        WryCursorIcon::EResize => CursorIcon::EResize,
+        WryCursorIcon::NResize => CursorIcon::NResize,
+        WryCursorIcon::NeResize => CursorIcon::NeResize,
+        WryCursorIcon::NwResize => CursorIcon::NwResize,
+        WryCursorIcon::SResize => CursorIcon::SResize,
+        WryCursorIcon::SeResize => CursorIcon::SeResize,
+        WryCursorIcon::SwResize => CursorIcon::SwResize,
+        WryCursorIcon::WResize => CursorIcon::WResize,
+        WryCursorIcon::EwResize => CursorIcon::EwResize,
+        WryCursorIcon::NsResize => CursorIcon::NsResize,
+        WryCursorIcon::NeswResize => CursorIcon::NeswResize,
+        WryCursorIcon::NwseResize => CursorIcon::NwseResize,
+        WryCursorIcon::ColResize => CursorIcon::ColResize,
+        WryCursorIcon::RowResize => CursorIcon::RowResize,
+    }
+}
+
+// ============================================================================
+// Keyboard Input
+// ============================================================================
+
+/// Forward the window's key events to a webview as DOM keyboard events
+///
+/// For when the window has focus but the webview does not, such as a child
+/// webview on Windows that lost focus. Each key press and release received
+/// by `window` is dispatched as a `keydown`/`keyup` on the focused element of
+/// `webview`'s page. `webview` may be another window, or null for the
+/// window's own webview. Key events that already reach the page are not
+/// filtered, so only enable this while the webview is unfocused.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_keyboard_input_redirect(
+    window: WryWindow,
+    webview: WryWindow,
+    enabled: bool,
+) -> bool {
+    let target = if webview.is_null() {
+        None
+    } else {
+        match get_window_state(webview) {
+            Some(s) if s.webview.is_some() => Some(s.id),
+            Some(_) => {
+                set_last_error("No webview available");
+                return false;
+            }
+            None => return false,
+        }
+    };
+
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if enabled {
+        let target = target.unwrap_or(state.id);
+        if target == state.id && state.webview.is_none() {
+            set_last_error("No webview available");
+            return false;
+        }
+        state.key_redirect_target = Some(target);
+    } else {
+        state.key_redirect_target = None;
+    }
+
+    log::debug!("Keyboard input redirect: {:?}", state.key_redirect_target);
+    true
+}
+
+/// Dispatch a window's key events to its redirect target, see
+/// `wry_window_set_keyboard_input_redirect`
+pub(crate) fn redirect_keyboard_input(
+    state: &mut AppState,
+    window_id: WindowId,
+    event: &WindowEvent,
+) {
+    let window_state = match state.windows.get_mut(&window_id) {
+        Some(ws) => ws,
+        None => return,
+    };
+
+    let key_event = match event {
+        WindowEvent::ModifiersChanged(modifiers) => {
+            window_state.modifiers = *modifiers;
+            return;
+        }
+        WindowEvent::KeyboardInput { event, .. } => event,
+        _ => return,
+    };
+
+    let target = match window_state.key_redirect_target {
+        Some(id) => id,
+        None => return,
+    };
+    let modifiers = window_state.modifiers;
+
+    let webview = match state.windows.get(&target).and_then(|s| s.webview.as_ref()) {
+        Some(wv) => wv,
+        None => return,
+    };
+
+    let key = match &key_event.logical_key {
+        Key::Character(ch) => ch.to_string(),
+        Key::Space => " ".to_string(),
+        Key::Super => "Meta".to_string(),
+        Key::Unidentified(_) => "Unidentified".to_string(),
+        other => format!("{:?}", other),
+    };
+    let init = serde_json::json!({
+        "key": key,
+        "code": format!("{:?}", key_event.physical_key),
+        "repeat": key_event.repeat,
+        "shiftKey": modifiers.shift_key(),
+        "ctrlKey": modifiers.control_key(),
+        "altKey": modifiers.alt_key(),
+        "metaKey": modifiers.super_key(),
+        "bubbles": true,
+        "cancelable": true,
+    });
+    let kind = match key_event.state {
+        ElementState::Pressed => "keydown",
+        _ => "keyup",
+    };
+
+    let script = format!(
+        "(document.activeElement || document.body || document).dispatchEvent(\
+         new KeyboardEvent('{}', {}));",
+        kind, init
+    );
+    if let Err(e) = webview.evaluate_script(&script) {
+        log::warn!("Failed to redirect key event: {}", e);
+    }
+}
+
+// ============================================================================
+// Input Method
+// ============================================================================
+
+/// Place the input method candidate window
+///
+/// `x` and `y` are logical coordinates relative to the top left of the
+/// window's client area, typically the bottom left of the text cursor.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_ime_position(window: WryWindow, x: f64, y: f64) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Setting IME position: ({}, {})", x, y);
+    state.window.set_ime_position(LogicalPosition::new(x, y));
+    true
+}
+
+/// Allow or disable input methods for the window and its webview
+///
+/// Windows only.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_ime_allowed(window: WryWindow, allowed: bool) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Setting IME allowed: {}", allowed);
+    set_ime_allowed(&state.window, allowed)
+}
+
+#[cfg(target_os = "windows")]
+fn set_ime_allowed(window: &tao::window::Window, allowed: bool) -> bool {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Input::Ime::{
+        ImmAssociateContextEx, HIMC, IACE_CHILDREN, IACE_DEFAULT,
+    };
+
+    // Children included so the webview's own windows follow
+    let hwnd = HWND(window.hwnd() as *mut _);
+    let flags = if allowed {
+        IACE_DEFAULT | IACE_CHILDREN
+    } else {
+        IACE_CHILDREN
+    };
+    if unsafe { ImmAssociateContextEx(hwnd, HIMC::default(), flags) }.as_bool() {
+        true
+    } else {
+        set_last_error("ImmAssociateContextEx failed");
+        false
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn set_ime_allowed(_window: &tao::window::Window, _allowed: bool) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// Touch
+// ============================================================================
+
+/// Enable or disable touch input for the window
+///
+/// Touches are reported to the window event callback as
+/// `{"type":"window-touch","window_id":"...","touches":[{"id":N,"x":...,
+/// "y":...,"phase":"started"}]}`, one touch per event. tao only reports
+/// touches on Windows, where they are enabled by default. While disabled no
+/// touch events are reported. On macOS this also sets `acceptsTouchEvents`
+/// on the content view, and on Linux the GDK touch event mask.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_touch_enabled(window: WryWindow, enabled: bool) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Setting touch enabled: {}", enabled);
+    if !set_touch_enabled(&state.window, enabled) {
+        return false;
+    }
+    state.touch_enabled = enabled;
+    true
+}
+
+#[cfg(target_os = "macos")]
+fn set_touch_enabled(window: &tao::window::Window, enabled: bool) -> bool {
+    use objc2::msg_send;
+    use objc2::runtime::{AnyObject, Bool};
+    use tao::platform::macos::WindowExtMacOS;
+
+    let ns_view = window.ns_view() as *mut AnyObject;
+    if ns_view.is_null() {
+        set_last_error("Window has no content view");
+        return false;
+    }
+    unsafe {
+        let _: () = msg_send![ns_view, setAcceptsTouchEvents: Bool::new(enabled)];
+    }
+    true
+}
+
+#[cfg(target_os = "windows")]
+fn set_touch_enabled(window: &tao::window::Window, enabled: bool) -> bool {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::Input::Touch::{
+        RegisterTouchWindow, UnregisterTouchWindow, TWF_WANTPALM,
+    };
+
+    let hwnd = HWND(window.hwnd() as *mut _);
+    let result = unsafe {
+        if enabled {
+            RegisterTouchWindow(hwnd, TWF_WANTPALM)
+        } else {
+            UnregisterTouchWindow(hwnd)
+        }
+    };
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            set_last_error(format!("Failed to change touch registration: {}", e));
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_touch_enabled(window: &tao::window::Window, enabled: bool) -> bool {
+    use gtk::gdk::EventMask;
+    use gtk::prelude::*;
+    use tao::platform::unix::WindowExtUnix;
+
+    match window.gtk_window().window() {
+        Some(gdk_window) => {
+            let mut events = gdk_window.events();
+            events.set(EventMask::TOUCH_MASK, enabled);
+            gdk_window.set_events(events);
+            true
+        }
+        None => {
+            set_last_error("Window is not realized");
+            false
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn set_touch_enabled(_window: &tao::window::Window, _enabled: bool) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// Gestures
+// ============================================================================
+
+/// Local event monitor watching a window's trackpad gestures
+#[cfg(target_os = "macos")]
+pub struct GestureMonitor(objc2::rc::Retained<objc2::runtime::AnyObject>);
+
+/// Local event monitor watching a window's trackpad gestures
+#[cfg(not(target_os = "macos"))]
+pub struct GestureMonitor;
+
+#[cfg(target_os = "macos")]
+impl Drop for GestureMonitor {
+    fn drop(&mut self) {
+        use objc2::{class, msg_send};
+
+        unsafe {
+            let _: () = msg_send![class!(NSEvent), removeMonitor: &*self.0];
+        }
+    }
+}
+
+/// Report trackpad pinch gestures over the window
+///
+/// Each step is sent to the window event callback as
+/// `{"type":"window-pinch-zoom","window_id":"...","scale":1.05,"phase":"moved"}`,
+/// where `scale` is the change since the previous step. The webview still
+/// sees the gesture. macOS only.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_pinch_zoom_enabled(
+    window: WryWindow,
+    enabled: bool,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Setting pinch zoom events: {}", enabled);
+    state.pinch_zoom_enabled = enabled;
+    update_gesture_monitor(state)
+}
+
+/// Report trackpad rotate gestures over the window
+///
+/// Each step is sent to the window event callback as
+/// `{"type":"window-rotate","window_id":"...","rotation":2.5,"phase":"moved"}`,
+/// where `rotation` is the change in degrees since the previous step,
+/// counterclockwise. macOS only.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_rotate_gesture_enabled(
+    window: WryWindow,
+    enabled: bool,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    log::debug!("Setting rotate gesture events: {}", enabled);
+    state.rotate_gesture_enabled = enabled;
+    update_gesture_monitor(state)
+}
+
+#[cfg(target_os = "macos")]
+fn update_gesture_monitor(state: &mut crate::window::WindowState) -> bool {
+    use block2::RcBlock;
+    use objc2::rc::Retained;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send};
+    use tao::platform::macos::WindowExtMacOS;
+
+    use crate::app::UserEvent;
+    use crate::events::window_id_string;
+
+    // NSEventType values, also the bit index in NSEventMask
+    const ROTATE: usize = 18;
+    const MAGNIFY: usize = 30;
+
+    state.gesture_monitor = None;
+    let mut mask: u64 = 0;
+    if state.pinch_zoom_enabled {
+        mask |= 1 << MAGNIFY;
+    }
+    if state.rotate_gesture_enabled {
+        mask |= 1 << ROTATE;
+    }
+    if mask == 0 {
+        return true;
+    }
+
+    let ns_window = state.window.ns_window() as usize;
+    let window_id = state.id;
+    let proxy = state.event_loop_proxy.clone();
+
+    let handler = RcBlock::new(move |event: *mut AnyObject| -> *mut AnyObject {
+        unsafe {
+            let event_window: *mut AnyObject = msg_send![event, window];
+            if event_window as usize != ns_window {
+                return event;
+            }
+
+            let kind: usize = msg_send![event, type];
+            let phase = gesture_phase_str(msg_send![event, phase]);
+            let value = match kind {
+                MAGNIFY => {
+                    let magnification: f64 = msg_send![event, magnification];
+                    serde_json::json!({
+                        "type": "window-pinch-zoom",
+                        "window_id": window_id_string(window_id),
+                        "scale": 1.0 + magnification,
+                        "phase": phase,
+                    })
+                }
+                ROTATE => {
+                    let rotation: f32 = msg_send![event, rotation];
+                    serde_json::json!({
+                        "type": "window-rotate",
+                        "window_id": window_id_string(window_id),
+                        "rotation": rotation,
+                        "phase": phase,
+                    })
+                }
+                _ => return event,
+            };
+
+            let _ = proxy.send_event(UserEvent::WindowJson {
+                window_id,
+                json: value.to_string(),
+            });
+            event
+        }
+    });
+
+    let monitor: Option<Retained<AnyObject>> = unsafe {
+        msg_send![
+            class!(NSEvent),
+            addLocalMonitorForEventsMatchingMask: mask,
+            handler: &*handler
+        ]
+    };
+    match monitor {
+        Some(monitor) => {
+            state.gesture_monitor = Some(GestureMonitor(monitor));
+            true
+        }
+        None => {
+            set_last_error("Failed to add gesture event monitor");
+            false
+        }
+    }
+}
+
+/// Name of an NSEventPhase, matching the touch event phases
+#[cfg(target_os = "macos")]
+fn gesture_phase_str(phase: usize) -> &'static str {
+    match phase {
+        // NSEventPhaseBegan, NSEventPhaseMayBegin
+        1 | 32 => "started",
+        // NSEventPhaseEnded
+        8 => "ended",
+        // NSEventPhaseCancelled
+        16 => "cancelled",
+        _ => "moved",
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn update_gesture_monitor(_state: &mut crate::window::WindowState) -> bool {
+    set_last_error("Not supported on this platform");
+    false
+}
+
+// ============================================================================
+// Safe Area
+// ============================================================================
+
+/// Clear the inset variables set by `wry_window_set_content_insets`
+const CLEAR_CONTENT_INSETS_SCRIPT: &str = r#"(function() {
+    var style = document.documentElement && document.documentElement.style;
+    if (!style) return;
+    ['top', 'right', 'bottom', 'left'].forEach(function(side) {
+        style.removeProperty('--wry-safe-area-inset-' + side);
+    });
+})();"#;
+
+/// Tell the page which edges of the window are covered
+///
+/// For content under the menu bar or camera housing in full screen, or
+/// under custom title bar controls. Pages cannot set `env(safe-area-inset-*)`,
+/// so the insets (in CSS pixels) are exposed as the custom properties
+/// `--wry-safe-area-inset-top`, `-right`, `-bottom` and `-left` on the root
+/// element, e.g. `padding-top: var(--wry-safe-area-inset-top, 0px)`. They
+/// persist across navigations. All zeros removes them.
+///
+/// On macOS 11 and later the insets are also set as the content view's
+/// `additionalSafeAreaInsets`, so native safe-area layout agrees with the page.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_content_insets(
+    window: WryWindow,
+    top: f64,
+    right: f64,
+    bottom: f64,
+    left: f64,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    let insets = [top, right, bottom, left];
+    if insets.iter().any(|v| !v.is_finite() || *v < 0.0) {
+        set_last_error("Insets must be finite and non-negative");
+        return false;
+    }
+
+    if let Some(id) = state.content_insets_script.take() {
+        state.remove_init_script(id);
+        if let Some(webview) = &state.webview {
+            if let Err(e) = webview.evaluate_script(CLEAR_CONTENT_INSETS_SCRIPT) {
+                log::warn!("Failed to clear content insets: {}", e);
+            }
+        }
+    }
+
+    if insets.iter().any(|v| *v > 0.0) {
+        let script = format!(
+            "(function() {{
+    var style = document.documentElement && document.documentElement.style;
+    if (!style) return;
+    style.setProperty('--wry-safe-area-inset-top', '{}px');
+    style.setProperty('--wry-safe-area-inset-right', '{}px');
+    style.setProperty('--wry-safe-area-inset-bottom', '{}px');
+    style.setProperty('--wry-safe-area-inset-left', '{}px');
+}})();",
+            top, right, bottom, left
+        );
+        match state.add_dom_init_script(&script) {
+            Ok(id) => state.content_insets_script = Some(id),
+            Err(e) => {
+                set_last_error(e);
+                return false;
+            }
+        }
+    }
+
+    set_native_content_insets(&state.window, top, right, bottom, left);
+
+    log::debug!("Content insets: {} {} {} {}", top, right, bottom, left);
+    true
+}
+
+#[cfg(target_os = "macos")]
+fn set_native_content_insets(
+    window: &tao::window::Window,
+    top: f64,
+    right: f64,
+    bottom: f64,
+    left: f64,
+) {
+    use objc2::runtime::AnyObject;
+    use objc2::{msg_send, sel};
+    use objc2_foundation::NSEdgeInsets;
+    use tao::platform::macos::WindowExtMacOS;
+
+    let ns_view = window.ns_view() as *mut AnyObject;
+    if ns_view.is_null() {
+        return;
+    }
+
+    unsafe {
+        // NSView gained additionalSafeAreaInsets in macOS 11
+        let supported: bool =
+            msg_send![ns_view, respondsToSelector: sel!(setAdditionalSafeAreaInsets:)];
+        if !supported {
+            return;
+        }
+        let insets = NSEdgeInsets { top, left, bottom, right };
+        let _: () = msg_send![ns_view, setAdditionalSafeAreaInsets: insets];
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn set_native_content_insets(
+    _window: &tao::window::Window,
+    _top: f64,
+    _right: f64,
+    _bottom: f64,
+    _left: f64,
+) {
+}
+
+// ============================================================================
+// Drop Targets
+// ============================================================================
+
+/// Reject drags carrying none of `window.__wryDragAccept` and outline the
+/// page while an accepted one is over it
+const DRAG_ACCEPT_SCRIPT: &str = r#"(function() {
+    if (window.__wryDragAcceptHooked) return;
+    window.__wryDragAcceptHooked = true;
+    var style = document.createElement('style');
+    style.setAttribute('data-wry-drag-accept', '');
+    style.textContent =
+        'html[data-wry-drag-over] { outline: 3px solid Highlight; outline-offset: -3px; }';
+    document.documentElement.appendChild(style);
+
+    function kindOf(item) {
+        if (item.kind === 'file') return item.type.indexOf('image/') === 0 ? 'image' : 'files';
+        if (item.type === 'text/uri-list') return 'url';
+        if (item.type === 'text/plain') return 'text';
+        return null;
+    }
+    function accepted(e) {
+        var accept = window.__wryDragAccept;
+        var items = (e.dataTransfer && e.dataTransfer.items) || [];
+        for (var i = 0; i < items.length; i++) {
+            var kind = kindOf(items[i]);
+            if (kind && accept.indexOf(kind) !== -1) return true;
+            if (kind === 'image' && accept.indexOf('files') !== -1) return true;
+        }
+        return false;
+    }
+    function setOver(over) {
+        if (over) document.documentElement.setAttribute('data-wry-drag-over', '');
+        else document.documentElement.removeAttribute('data-wry-drag-over');
+    }
+
+    ['dragenter', 'dragover'].forEach(function(type) {
+        document.addEventListener(type, function(e) {
+            if (!window.__wryDragAccept) return;
+            if (accepted(e)) {
+                setOver(true);
+                return;
+            }
+            e.preventDefault();
+            e.stopPropagation();
+            e.dataTransfer.dropEffect = 'none';
+            setOver(false);
+        }, true);
+    });
+    document.addEventListener('dragleave', function(e) {
+        if (!e.relatedTarget) setOver(false);
+    }, true);
+    document.addEventListener('drop', function(e) {
+        setOver(false);
+        if (window.__wryDragAccept && !accepted(e)) {
+            e.preventDefault();
+            e.stopPropagation();
+        }
+    }, true);
+})();"#;
+
+/// Limit what can be dropped on the window's page
+///
+/// While a drag carrying at least one accepted type is over the page it is
+/// outlined using the system highlight color; other drags show the
+/// no-drop cursor and are not delivered to the page. `Files` also accepts
+/// images. A count of 0 accepts everything again. The page is restricted
+/// rather than the native window, since drops land in the webview.
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_set_drag_accept_types(
+    window: WryWindow,
+    types: *const WryDragType,
+    count: usize,
+) -> bool {
+    let state = match get_window_state_mut(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if state.webview.is_none() {
+        set_last_error("No webview available");
+        return false;
+    }
+
+    if types.is_null() && count > 0 {
+        set_last_error("Null drag types");
+        return false;
+    }
+
+    let types = if count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(types, count)
+    };
+    let mut names: Vec<&str> = types
+        .iter()
+        .map(|t| match t {
+            WryDragType::Files => "files",
+            WryDragType::Text => "text",
+            WryDragType::Url => "url",
+            WryDragType::Image => "image",
+        })
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+
+    if let Some(id) = state.drag_accept_script.take() {
+        state.remove_init_script(id);
+        if let Some(webview) = &state.webview {
+            if let Err(e) = webview.evaluate_script("window.__wryDragAccept = null;") {
+                log::warn!("Failed to clear drag accept types: {}", e);
+            }
+        }
+    }
+
+    if !names.is_empty() {
+        let script = format!(
+            "window.__wryDragAccept = {};\n{}",
+            serde_json::Value::from(names.clone()),
+            DRAG_ACCEPT_SCRIPT
+        );
+        match state.add_dom_init_script(&script) {
+            Ok(id) => state.drag_accept_script = Some(id),
+            Err(e) => {
+                set_last_error(e);
+                return false;
+            }
+        }
+    }
+
+    log::debug!("Drag accept types: {:?}", names);
+    true
+}
+
+// ============================================================================
+// Window Snapshot
+// ============================================================================
+
+/// Capture the window's own pixels, including its frame, as RGBA
+///
+/// Other windows overlapping it are not included. The callback is called
+/// before this returns with `width * height * 4` bytes of straight RGBA,
+/// row by row from the top left, valid only during the call. Uses
+/// `CGWindowListCreateImage` on macOS, which needs the screen recording
+/// permission, `PrintWindow` on Windows, cropped to the visible frame
+/// without the invisible resize borders, and renders the GTK window into
+/// an image on Linux, where server-side title bars are not included.
+///
+/// # Returns
+/// false if the capture failed, in which case the callback is not called
+#[no_mangle]
+pub unsafe extern "C" fn wry_window_snapshot(
+    window: WryWindow,
+    callback: SnapshotCallback,
+    user_data: *mut c_void,
+) -> bool {
+    let state = match get_window_state(window) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let callback = match callback {
+        Some(cb) => cb,
+        None => {
+            set_last_error("Null snapshot callback");
+            return false;
+        }
+    };
+
+    log::debug!("Capturing window snapshot");
+    match capture_window(&state.window) {
+        Ok((width, height, rgba)) => {
+            callback(rgba.as_ptr(), rgba.len(), width, height, user_data);
+            true
+        }
+        Err(e) => {
+            set_last_error(e);
+            false
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn capture_window(window: &tao::window::Window) -> Result<(u32, u32, Vec<u8>), String> {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use objc2_foundation::{NSPoint, NSRect, NSSize};
+    use tao::platform::macos::WindowExtMacOS;
+
+    const LIST_OPTION_INCLUDING_WINDOW: u32 = 1 << 3;
+    const IMAGE_BOUNDS_IGNORE_FRAMING: u32 = 1 << 0;
+    const ALPHA_PREMULTIPLIED_LAST: u32 = 1;
+    const BYTE_ORDER_32_BIG: u32 = 4 << 12;
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGWindowListCreateImage(
+            bounds: NSRect,
+            list_option: u32,
+            window_id: u32,
+            image_option: u32,
+        ) -> *mut c_void;
+        fn CGImageGetWidth(image: *mut c_void) -> usize;
+        fn CGImageGetHeight(image: *mut c_void) -> usize;
+        fn CGImageRelease(image: *mut c_void);
+        fn CGColorSpaceCreateDeviceRGB() -> *mut c_void;
+        fn CGColorSpaceRelease(space: *mut c_void);
+        fn CGBitmapContextCreate(
+            data: *mut c_void,
+            width: usize,
+            height: usize,
+            bits_per_component: usize,
+            bytes_per_row: usize,
+            space: *mut c_void,
+            bitmap_info: u32,
+        ) -> *mut c_void;
+        fn CGContextDrawImage(context: *mut c_void, rect: NSRect, image: *mut c_void);
+        fn CGContextRelease(context: *mut c_void);
+    }
+
+    let ns_window = window.ns_window() as *mut AnyObject;
+    let window_number: isize = unsafe { msg_send![ns_window, windowNumber] };
+    if window_number <= 0 {
+        return Err("Window is not on screen".to_string());
+    }
+
+    // CGRectNull selects the window's own bounds
+    let null_rect = NSRect::new(NSPoint::new(f64::INFINITY, f64::INFINITY), NSSize::new(0.0, 0.0));
+    unsafe {
+        let image = CGWindowListCreateImage(
+            null_rect,
+            LIST_OPTION_INCLUDING_WINDOW,
+            window_number as u32,
+            IMAGE_BOUNDS_IGNORE_FRAMING,
+        );
+        if image.is_null() {
+            return Err("CGWindowListCreateImage failed".to_string());
+        }
+        let width = CGImageGetWidth(image);
+        let height = CGImageGetHeight(image);
+
+        let mut rgba = vec![0u8; width * height * 4];
+        let space = CGColorSpaceCreateDeviceRGB();
+        let context = CGBitmapContextCreate(
+            rgba.as_mut_ptr() as *mut c_void,
+            width,
+            height,
+            8,
+            width * 4,
+            space,
+            ALPHA_PREMULTIPLIED_LAST | BYTE_ORDER_32_BIG,
+        );
+        CGColorSpaceRelease(space);
+        if context.is_null() {
+            CGImageRelease(image);
+            return Err("Failed to create bitmap context".to_string());
+        }
+        let rect = NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(width as f64, height as f64));
+        CGContextDrawImage(context, rect, image);
+        CGContextRelease(context);
+        CGImageRelease(image);
+
+        crate::webview::unpremultiply(&mut rgba);
+        Ok((width as u32, height as u32, rgba))
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn capture_window(window: &tao::window::Window) -> Result<(u32, u32, Vec<u8>), String> {
+    use tao::platform::windows::WindowExtWindows;
+    use windows::Win32::Foundation::{HWND, RECT};
+    use windows::Win32::Graphics::Dwm::{DwmGetWindowAttribute, DWMWA_EXTENDED_FRAME_BOUNDS};
+    use windows::Win32::Graphics::Gdi::{
+        CreateCompatibleDC, CreateDIBSection, DeleteDC, DeleteObject, GetDC, ReleaseDC,
+        SelectObject, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS,
+    };
+    use windows::Win32::Storage::Xps::{PrintWindow, PRINT_WINDOW_FLAGS};
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+    /// Also capture DirectComposition content such as WebView2
+    const PW_RENDERFULLCONTENT: u32 = 2;
+
+    let hwnd = HWND(window.hwnd() as *mut _);
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(hwnd, &mut rect) }
+        .map_err(|e| format!("Failed to get window bounds: {}", e))?;
+    let width = (rect.right - rect.left).max(0) as usize;
+    let height = (rect.bottom - rect.top).max(0) as usize;
+    if width == 0 || height == 0 {
+        return Err("Window has no area".to_string());
+    }
+
+    // The window rect includes the invisible resize borders, so crop the
+    // capture to the visible frame DWM draws
+    let mut frame = RECT::default();
+    let queried = unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut frame as *mut RECT as *mut c_void,
+            std::mem::size_of::<RECT>() as u32,
+        )
+    };
+    let (crop_x, crop_y, crop_width, crop_height) = match queried {
+        Ok(()) => {
+            let x = (frame.left - rect.left).clamp(0, width as i32) as usize;
+            let y = (frame.top - rect.top).clamp(0, height as i32) as usize;
+            let w = ((frame.right - frame.left).max(0) as usize).min(width - x);
+            let h = ((frame.bottom - frame.top).max(0) as usize).min(height - y);
+            if w == 0 || h == 0 {
+                (0, 0, width, height)
+            } else {
+                (x, y, w, h)
+            }
+        }
+        Err(_) => (0, 0, width, height),
+    };
+
+    let info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width as i32,
+            // Negative height makes the bitmap top-down
+            biHeight: -(height as i32),
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    unsafe {
+        let screen = GetDC(None);
+        let dc = CreateCompatibleDC(Some(screen));
+        let mut bits: *mut c_void = std::ptr::null_mut();
+        let bitmap = match CreateDIBSection(Some(dc), &info, DIB_RGB_COLORS, &mut bits, None, 0) {
+            Ok(bitmap) => bitmap,
+            Err(e) => {
+                let _ = DeleteDC(dc);
+                ReleaseDC(None, screen);
+                return Err(format!("Failed to create bitmap: {}", e));
+            }
+        };
+        let previous = SelectObject(dc, bitmap.into());
+        let printed = PrintWindow(hwnd, dc, PRINT_WINDOW_FLAGS(PW_RENDERFULLCONTENT)).as_bool();
+
+        let pixels = if printed && !bits.is_null() {
+            let bgra = std::slice::from_raw_parts(bits as *const u8, width * height * 4);
+            let mut rgba = Vec::with_capacity(crop_width * crop_height * 4);
+            for row in bgra.chunks_exact(width * 4).skip(crop_y).take(crop_height) {
+                let row = &row[crop_x * 4..(crop_x + crop_width) * 4];
+                for px in row.chunks_exact(4) {
+                    rgba.extend_from_slice(&[px[2], px[1], px[0], 255]);
+                }
+            }
+            Ok((crop_width as u32, crop_height as u32, rgba))
+        } else {
+            Err("PrintWindow failed".to_string())
+        };
+
+        SelectObject(dc, previous);
+        let _ = DeleteObject(bitmap.into());
+        let _ = DeleteDC(dc);
+        ReleaseDC(None, screen);
+        pixels
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn capture_window(window: &tao::window::Window) -> Result<(u32, u32, Vec<u8>), String> {
+    use gtk::cairo::{Context, Format, ImageSurface};
+    use gtk::prelude::WidgetExt;
+    use tao::platform::unix::WindowExtUnix;
+
+    let gtk_window = window.gtk_window();
+    let width = gtk_window.allocated_width();
+    let height = gtk_window.allocated_height();
+    if width <= 0 || height <= 0 {
+        return Err("Window has no area".to_string());
+    }
+
+    let surface = ImageSurface::create(Format::ARgb32, width, height)
+        .map_err(|e| format!("Failed to create image surface: {}", e))?;
+    {
+        let context =
+            Context::new(&surface).map_err(|e| format!("Failed to create context: {}", e))?;
+        gtk_window.draw(&context);
+    }
+    crate::webview::surface_to_rgba((*surface).clone())
+        .ok_or_else(|| "Failed to read window pixels".to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+fn capture_window(_window: &tao::window::Window) -> Result<(u32, u32, Vec<u8>), String> {
+    Err("Not supported on this platform".to_string())
+}
@@ -12,11 +12,27 @@
 #include <stdlib.h>
 #include <string.h>
 #include <stdbool.h>
+#include <stdint.h>
 
 // FFI type definitions
 typedef void* WryApp;
 typedef void* WryWindow;
 
+typedef enum {
+    WRY_PROXY_NONE = 0,
+    WRY_PROXY_HTTP = 1,
+    WRY_PROXY_HTTPS = 2,
+    WRY_PROXY_SOCKS5 = 3
+} WryProxyKind;
+
+typedef struct {
+    WryProxyKind kind;
+    const char* host;
+    uint16_t port;
+    const char* username;
+    const char* password;
+} WryProxyConfig;
+
 typedef struct {
     const char* title;
     const char* url;
@@ -41,6 +57,13 @@ typedef struct {
     bool always_on_top;
     bool devtools_enabled;
     bool autoplay_enabled;
+    const char* const* init_scripts;
+    size_t init_script_count;
+    WryProxyConfig proxy;
+    bool default_context_menu_enabled;
+    bool allow_text_selection;
+    bool disable_zoom;
+    bool take_focus_on_click;
 } WryWindowParams;
 
 typedef struct {
@@ -56,9 +79,13 @@ extern void wry_app_quit(WryApp app);
 extern void wry_app_destroy(WryApp app);
 extern WryWindow wry_window_create(WryApp app, const WryWindowParams* params);
 extern void wry_window_destroy(WryWindow window);
+extern bool wry_window_set_icon_rgba(WryWindow window, const uint8_t* rgba, size_t len, uint32_t width, uint32_t height);
+extern bool wry_window_set_icon_file(WryWindow window, const char* path);
+extern bool wry_window_clear_icon(WryWindow window);
 extern const char* wry_get_last_error(void);
 extern const char* wry_version(void);
 extern void wry_string_free(char* s);
+extern char* wry_window_get_title(WryWindow window);
 
 int main(int argc, char** argv) {
     printf("wry-ffi test starting...\n");
@@ -101,7 +128,14 @@ int main(int argc, char** argv) {
         .decorations = true,
         .always_on_top = false,
         .devtools_enabled = true,
-        .autoplay_enabled = false
+        .autoplay_enabled = false,
+        .init_scripts = NULL,
+        .init_script_count = 0,
+        .proxy = { .kind = WRY_PROXY_NONE },
+        .default_context_menu_enabled = true,
+        .allow_text_selection = true,
+        .disable_zoom = false,
+        .take_focus_on_click = true
     };
 
     // Create window
@@ -115,6 +149,39 @@ int main(int argc, char** argv) {
     }
     printf("Window created successfully\n");
 
+    // Round-trip an allocated string
+    char* title = wry_window_get_title(window);
+    if (title == NULL || strcmp(title, params.title) != 0) {
+        fprintf(stderr, "Unexpected window title: %s\n", title ? title : "(null)");
+        return 1;
+    }
+    wry_string_free(title);
+    wry_string_free(NULL);
+    printf("String round-trip OK\n");
+
+    // Set and clear a 2x2 icon
+    const uint8_t icon[16] = {
+        255, 0, 0, 255,    0, 255, 0, 255,
+        0, 0, 255, 255,    255, 255, 255, 255
+    };
+    if (!wry_window_set_icon_rgba(window, icon, sizeof(icon), 2, 2)) {
+        fprintf(stderr, "Failed to set icon: %s\n", wry_get_last_error());
+        return 1;
+    }
+    if (wry_window_set_icon_rgba(window, icon, sizeof(icon), 4, 4)) {
+        fprintf(stderr, "Mismatched icon size was accepted\n");
+        return 1;
+    }
+    if (wry_window_set_icon_file(window, "does-not-exist.png")) {
+        fprintf(stderr, "Missing icon file was accepted\n");
+        return 1;
+    }
+    if (!wry_window_clear_icon(window)) {
+        fprintf(stderr, "Failed to clear icon: %s\n", wry_get_last_error());
+        return 1;
+    }
+    printf("Icon functions OK\n");
+
     // Run event loop
     printf("Running event loop...\n");
     WryResult result = wry_app_run(app);